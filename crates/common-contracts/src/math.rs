@@ -63,7 +63,7 @@ pub fn mul_div_ceil(a: u64, b: u64, denom: u64) -> Result<u64> {
         .checked_mul(b as u128)
         .ok_or_else(|| error!(CommonError::MathOverflow))?;
     let d = denom as u128;
-    Ok(((prod + d - 1) / d) as u64)
+    Ok(prod.div_ceil(d) as u64)
 }
 
 /// fee_floor(amount, bps) = floor(amount * bps / 10_000)
@@ -149,5 +149,5 @@ pub fn cp_in(dy: u64, x: u64, y: u64) -> Result<u64> {
         .ok_or_else(|| error!(CommonError::MathOverflow))?;
 
     // ceil(num/den)
-    Ok(((num + den - 1) / den) as u64)
+    Ok(num.div_ceil(den) as u64)
 }
@@ -0,0 +1,288 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::DexError;
+use crate::math::mul_div_u128;
+
+// Concentrated liquidity (Uniswap v3 style, n = 2), integer only. Prices are sqrt(sub per
+// kNite) in Q64.64; tick t prices at 1.0001^t. Token 0 is kNite, token 1 is sub:
+//   dx = L * (sqrt_b - sqrt_a) / (sqrt_a * sqrt_b),  dy = L * (sqrt_b - sqrt_a)
+// Amounts owed to the pool round up, amounts paid out round down.
+
+pub const MIN_TICK: i32 = -443_636;
+pub const MAX_TICK: i32 = 443_636;
+// sqrt_price_at_tick(MIN_TICK) and sqrt_price_at_tick(MAX_TICK)
+pub const MIN_SQRT_PRICE_Q64: u128 = 4_295_048_016;
+pub const MAX_SQRT_PRICE_Q64: u128 = 79_226_673_521_066_979_257_578_248_091;
+
+const Q64: u128 = 1 << 64;
+
+/// floor(sqrt(1.0001^-(2^i)) * 2^64), i = 0..=18
+const TICK_FACTORS_Q64: [u128; 19] = [
+    18_445_821_805_675_392_311,
+    18_444_899_583_751_176_498,
+    18_443_055_278_223_354_162,
+    18_439_367_220_385_604_838,
+    18_431_993_317_065_449_817,
+    18_417_254_355_718_160_513,
+    18_387_811_781_193_591_352,
+    18_329_067_761_203_520_168,
+    18_212_142_134_806_087_854,
+    17_980_523_815_641_551_639,
+    17_526_086_738_831_147_013,
+    16_651_378_430_235_024_244,
+    15_030_750_278_693_429_944,
+    12_247_334_978_882_834_399,
+    8_131_365_268_884_726_200,
+    3_584_323_654_723_342_297,
+    696_457_651_847_595_233,
+    26_294_789_957_452_057,
+    37_481_735_321_082,
+];
+
+fn overflow() -> Error {
+    error!(DexError::MathOverflow)
+}
+
+/// (a * b) >> 64 for a Q64.64 value and a factor below 2^64; no 256-bit division needed.
+fn mul_shr_64(a: u128, b: u128) -> u128 {
+    let (hi, lo) = (a >> 64, a & (Q64 - 1));
+    hi * b + ((lo * b) >> 64)
+}
+
+/// ceil(a * b / denom) with a 256-bit intermediate product.
+pub fn mul_div_u128_ceil(a: u128, b: u128, denom: u128) -> Option<u128> {
+    let q = mul_div_u128(a, b, denom)?;
+    // a*b - q*denom < denom < 2^128, so the low 128 bits decide whether it is zero
+    if a.wrapping_mul(b) == q.wrapping_mul(denom) {
+        Some(q)
+    } else {
+        q.checked_add(1)
+    }
+}
+
+/// sqrt(1.0001^tick) in Q64.64.
+pub fn sqrt_price_at_tick(tick: i32) -> Result<u128> {
+    if !(MIN_TICK..=MAX_TICK).contains(&tick) {
+        return err!(DexError::InvalidTick);
+    }
+    let abs = tick.unsigned_abs();
+    let mut ratio = Q64;
+    for (i, factor) in TICK_FACTORS_Q64.iter().enumerate() {
+        if abs & (1 << i) != 0 {
+            ratio = mul_shr_64(ratio, *factor);
+        }
+    }
+    // factors are for negative ticks; invert (2^128 / ratio) for positive ones
+    if tick > 0 {
+        ratio = u128::MAX / ratio;
+    }
+    Ok(ratio)
+}
+
+/// Largest tick whose sqrt price is at or below `sqrt_price_q64` (binary search).
+pub fn tick_at_sqrt_price(sqrt_price_q64: u128) -> Result<i32> {
+    if !(MIN_SQRT_PRICE_Q64..=MAX_SQRT_PRICE_Q64).contains(&sqrt_price_q64) {
+        return err!(DexError::InvalidTick);
+    }
+    let (mut lo, mut hi) = (MIN_TICK, MAX_TICK);
+    while lo < hi {
+        let mid = lo + (hi - lo + 1) / 2;
+        if sqrt_price_at_tick(mid)? <= sqrt_price_q64 {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    Ok(lo)
+}
+
+/// kNite between two sqrt prices for liquidity `l`.
+pub fn delta_knite(sqrt_a: u128, sqrt_b: u128, l: u128, round_up: bool) -> Result<u64> {
+    let (lo, hi) = if sqrt_a <= sqrt_b { (sqrt_a, sqrt_b) } else { (sqrt_b, sqrt_a) };
+    if lo == 0 {
+        return err!(DexError::InvalidTick);
+    }
+    let amount = if round_up {
+        mul_div_u128_ceil(l, hi - lo, hi).and_then(|v| mul_div_u128_ceil(v, Q64, lo))
+    } else {
+        mul_div_u128(l, hi - lo, hi).and_then(|v| mul_div_u128(v, Q64, lo))
+    };
+    amount.and_then(|v| u64::try_from(v).ok()).ok_or_else(overflow)
+}
+
+/// Sub between two sqrt prices for liquidity `l`.
+pub fn delta_sub(sqrt_a: u128, sqrt_b: u128, l: u128, round_up: bool) -> Result<u64> {
+    let (lo, hi) = if sqrt_a <= sqrt_b { (sqrt_a, sqrt_b) } else { (sqrt_b, sqrt_a) };
+    let amount = if round_up {
+        mul_div_u128_ceil(l, hi - lo, Q64)
+    } else {
+        mul_div_u128(l, hi - lo, Q64)
+    };
+    amount.and_then(|v| u64::try_from(v).ok()).ok_or_else(overflow)
+}
+
+/// Token amounts backing liquidity `l` in [sqrt_lower, sqrt_upper) at the current price.
+pub fn amounts_for_liquidity(
+    sqrt_price: u128,
+    sqrt_lower: u128,
+    sqrt_upper: u128,
+    l: u128,
+    round_up: bool,
+) -> Result<(u64, u64)> {
+    if sqrt_price < sqrt_lower {
+        Ok((delta_knite(sqrt_lower, sqrt_upper, l, round_up)?, 0))
+    } else if sqrt_price >= sqrt_upper {
+        Ok((0, delta_sub(sqrt_lower, sqrt_upper, l, round_up)?))
+    } else {
+        Ok((
+            delta_knite(sqrt_price, sqrt_upper, l, round_up)?,
+            delta_sub(sqrt_lower, sqrt_price, l, round_up)?,
+        ))
+    }
+}
+
+/// One swap step inside a single liquidity range (exact input).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price_next: u128,
+    /// input consumed by the curve (fee excluded)
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+}
+
+/// Moves the price from `sqrt_price` toward `sqrt_target` spending at most `amount_remaining`
+/// (fee included). kNite in moves the price down, sub in moves it up.
+pub fn compute_swap_step(
+    sqrt_price: u128,
+    sqrt_target: u128,
+    l: u128,
+    amount_remaining: u64,
+    fee_bps: u16,
+    is_knite_in: bool,
+) -> Result<SwapStep> {
+    let fee_bps = fee_bps as u128;
+    let remaining_less_fee =
+        u64::try_from((amount_remaining as u128) * (10_000 - fee_bps) / 10_000).map_err(|_| overflow())?;
+
+    // input the curve needs to reach the target
+    let to_target = if l == 0 {
+        0
+    } else if is_knite_in {
+        delta_knite(sqrt_target, sqrt_price, l, true)?
+    } else {
+        delta_sub(sqrt_price, sqrt_target, l, true)?
+    };
+
+    let reaches_target = l == 0 || remaining_less_fee >= to_target;
+    let (sqrt_price_next, amount_in) = if reaches_target {
+        (sqrt_target, to_target)
+    } else if is_knite_in {
+        // P' = L * P / (L + dx * P), rounded up so the pool never over-pays
+        let moved = mul_div_u128(remaining_less_fee as u128, sqrt_price, Q64).ok_or_else(overflow)?;
+        let denom = l.checked_add(moved).ok_or_else(overflow)?;
+        let next = mul_div_u128_ceil(l, sqrt_price, denom).ok_or_else(overflow)?;
+        (next, remaining_less_fee)
+    } else {
+        // P' = P + dy / L, rounded down
+        let moved = mul_div_u128(remaining_less_fee as u128, Q64, l).ok_or_else(overflow)?;
+        (sqrt_price.checked_add(moved).ok_or_else(overflow)?, remaining_less_fee)
+    };
+
+    let amount_out = if l == 0 {
+        0
+    } else if is_knite_in {
+        delta_sub(sqrt_price_next, sqrt_price, l, false)?
+    } else {
+        delta_knite(sqrt_price, sqrt_price_next, l, false)?
+    };
+
+    // a partial step keeps the whole remainder as fee; a full one charges fee on its input
+    let fee = if reaches_target {
+        let fee = mul_div_u128_ceil(amount_in as u128, fee_bps, 10_000 - fee_bps).ok_or_else(overflow)?;
+        u64::try_from(fee).map_err(|_| overflow())?
+    } else {
+        amount_remaining - amount_in
+    };
+
+    Ok(SwapStep { sqrt_price_next, amount_in, amount_out, fee })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_TICKS: [i32; 11] = [MIN_TICK, -200_000, -887, -60, -1, 0, 1, 60, 887, 200_000, MAX_TICK];
+
+    #[test]
+    fn sqrt_price_bounds_match_constants() -> Result<()> {
+        assert_eq!(sqrt_price_at_tick(0)?, Q64);
+        assert_eq!(sqrt_price_at_tick(MIN_TICK)?, MIN_SQRT_PRICE_Q64);
+        assert_eq!(sqrt_price_at_tick(MAX_TICK)?, MAX_SQRT_PRICE_Q64);
+        assert!(sqrt_price_at_tick(MIN_TICK - 1).is_err());
+        assert!(sqrt_price_at_tick(MAX_TICK + 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn sqrt_price_is_strictly_increasing() -> Result<()> {
+        for tick in SAMPLE_TICKS.into_iter().filter(|t| *t < MAX_TICK) {
+            assert!(sqrt_price_at_tick(tick)? < sqrt_price_at_tick(tick + 1)?, "tick {tick}");
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn tick_round_trips_through_sqrt_price() -> Result<()> {
+        for tick in SAMPLE_TICKS {
+            assert_eq!(tick_at_sqrt_price(sqrt_price_at_tick(tick)?)?, tick);
+            if tick < MAX_TICK {
+                // anything short of the next tick's price still maps to this tick
+                assert_eq!(tick_at_sqrt_price(sqrt_price_at_tick(tick + 1)? - 1)?, tick);
+            }
+        }
+        assert!(tick_at_sqrt_price(MIN_SQRT_PRICE_Q64 - 1).is_err());
+        assert!(tick_at_sqrt_price(MAX_SQRT_PRICE_Q64 + 1).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn swap_step_without_liquidity_jumps_to_target() -> Result<()> {
+        let target = sqrt_price_at_tick(-60)?;
+        let step = compute_swap_step(Q64, target, 0, 1_000_000, 30, true)?;
+        assert_eq!(step, SwapStep { sqrt_price_next: target, amount_in: 0, amount_out: 0, fee: 0 });
+        Ok(())
+    }
+
+    #[test]
+    fn swap_step_reaching_target_charges_fee_on_input() -> Result<()> {
+        let l = 1_000_000_000u128;
+        let target = sqrt_price_at_tick(-60)?;
+        let step = compute_swap_step(Q64, target, l, u64::MAX / 2, 30, true)?;
+        assert_eq!(step.sqrt_price_next, target);
+        assert_eq!(step.amount_in, delta_knite(target, Q64, l, true)?);
+        assert_eq!(step.amount_out, delta_sub(target, Q64, l, false)?);
+        assert!(step.fee > 0 && step.amount_out < step.amount_in);
+        Ok(())
+    }
+
+    #[test]
+    fn partial_swap_step_stops_between_prices() -> Result<()> {
+        let l = 1_000_000_000_000u128;
+        let remaining = 1_000_000u64;
+        for is_knite_in in [true, false] {
+            let target = sqrt_price_at_tick(if is_knite_in { -6_000 } else { 6_000 })?;
+            let step = compute_swap_step(Q64, target, l, remaining, 30, is_knite_in)?;
+            if is_knite_in {
+                assert!(target < step.sqrt_price_next && step.sqrt_price_next < Q64);
+            } else {
+                assert!(Q64 < step.sqrt_price_next && step.sqrt_price_next < target);
+            }
+            // the whole remainder is spent: curve input plus fee
+            assert_eq!(step.amount_in, remaining * 9_970 / 10_000);
+            assert_eq!(step.amount_in + step.fee, remaining);
+            assert!(step.amount_out > 0 && step.amount_out <= step.amount_in);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+use anchor_lang::prelude::*;
+use common_contracts::math::cp_in;
+
+use crate::errors::DexError;
+use crate::math::{cp_out, mul_div_u128};
+
+/// Newton iteration cap for the StableSwap invariant (converges in a handful of steps in practice)
+const MAX_NEWTON_ITERATIONS: usize = 255;
+
+/// Pricing curve of a pair, resolved at swap time (StableSwap carries the current ramped amp).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Curve {
+    ConstantProduct,
+    StableSwap { amp: u64 },
+}
+
+impl Curve {
+    /// Curve output for `amount_in` (fee-less, rounded down).
+    pub fn amount_out(self, amount_in: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+        match self {
+            Curve::ConstantProduct => cp_out(amount_in, reserve_in, reserve_out),
+            Curve::StableSwap { amp } => stable_out(amount_in, reserve_in, reserve_out, amp),
+        }
+    }
+
+    /// Input the curve needs to release exactly `amount_out` (fee-less, rounded up).
+    pub fn amount_in(self, amount_out: u64, reserve_in: u64, reserve_out: u64) -> Result<u64> {
+        match self {
+            Curve::ConstantProduct => cp_in(amount_out, reserve_in, reserve_out),
+            Curve::StableSwap { amp } => stable_in(amount_out, reserve_in, reserve_out, amp),
+        }
+    }
+}
+
+// StableSwap (Curve whitepaper, n = 2), integer only:
+//   Ann * (x + y) + D = Ann * D + D^3 / (4 * x * y),  Ann = A * n^n = 4A
+// Products go through `mul_div_u128` (256-bit intermediate) so u64 reserves cannot overflow.
+
+fn overflow() -> Error {
+    error!(DexError::MathOverflow)
+}
+
+/// Invariant D for balances (x, y).
+pub fn stable_get_d(x: u64, y: u64, amp: u64) -> Result<u128> {
+    if x == 0 || y == 0 {
+        return err!(DexError::InsufficientLiquidity);
+    }
+    let (x, y) = (x as u128, y as u128);
+    let ann = (amp as u128) * 4;
+    let s = x + y;
+
+    let mut d = s;
+    for _ in 0..MAX_NEWTON_ITERATIONS {
+        // d_p = D^3 / (4xy)
+        let d_p = mul_div_u128(d, d, x * 2).ok_or_else(overflow)?;
+        let d_p = mul_div_u128(d_p, d, y * 2).ok_or_else(overflow)?;
+
+        // D = (Ann*S + 2*d_p) * D / ((Ann - 1) * D + 3 * d_p)
+        let num = ann
+            .checked_mul(s)
+            .and_then(|v| v.checked_add(d_p.checked_mul(2)?))
+            .ok_or_else(overflow)?;
+        let den = (ann - 1)
+            .checked_mul(d)
+            .and_then(|v| v.checked_add(d_p.checked_mul(3)?))
+            .ok_or_else(overflow)?;
+        let prev = d;
+        d = mul_div_u128(num, d, den).ok_or_else(overflow)?;
+
+        if d.abs_diff(prev) <= 1 {
+            return Ok(d);
+        }
+    }
+    err!(DexError::CurveDidNotConverge)
+}
+
+/// Balance of the other coin once one side holds `x_new`, keeping D constant.
+pub fn stable_get_y(x_new: u128, d: u128, amp: u64) -> Result<u128> {
+    if x_new == 0 {
+        return err!(DexError::InsufficientLiquidity);
+    }
+    let ann = (amp as u128) * 4;
+
+    // c = D^3 / (4 * x_new * Ann), b = x_new + D / Ann
+    let c = mul_div_u128(d, d, x_new * 2).ok_or_else(overflow)?;
+    let c = mul_div_u128(c, d, ann * 2).ok_or_else(overflow)?;
+    let b = x_new.checked_add(d / ann).ok_or_else(overflow)?;
+
+    // y = (y^2 + c) / (2y + b - D)
+    let mut y = d;
+    for _ in 0..MAX_NEWTON_ITERATIONS {
+        let den = y
+            .checked_mul(2)
+            .and_then(|v| v.checked_add(b))
+            .and_then(|v| v.checked_sub(d))
+            .filter(|v| *v > 0)
+            .ok_or_else(overflow)?;
+        let prev = y;
+        // floor(y^2/den) + floor(c/den) is within 1 of floor((y^2 + c)/den); Newton absorbs it
+        y = mul_div_u128(y, y, den)
+            .and_then(|v| v.checked_add(c / den))
+            .ok_or_else(overflow)?;
+
+        // rounded up: the pool never ends below the invariant
+        if y.abs_diff(prev) <= 1 {
+            return y.checked_add(1).ok_or_else(overflow);
+        }
+    }
+    err!(DexError::CurveDidNotConverge)
+}
+
+/// StableSwap output for `amount_in`; one unit held back against rounding.
+pub fn stable_out(amount_in: u64, reserve_in: u64, reserve_out: u64, amp: u64) -> Result<u64> {
+    let d = stable_get_d(reserve_in, reserve_out, amp)?;
+    let x_new = (reserve_in as u128)
+        .checked_add(amount_in as u128)
+        .ok_or_else(overflow)?;
+    let y_new = stable_get_y(x_new, d, amp)?;
+
+    let out = (reserve_out as u128).saturating_sub(y_new).saturating_sub(1);
+    u64::try_from(out).map_err(|_| overflow())
+}
+
+/// StableSwap input needed for exactly `amount_out`; rounded up by one unit.
+pub fn stable_in(amount_out: u64, reserve_in: u64, reserve_out: u64, amp: u64) -> Result<u64> {
+    if amount_out == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+    if amount_out >= reserve_out {
+        return err!(DexError::InsufficientLiquidity);
+    }
+    let d = stable_get_d(reserve_in, reserve_out, amp)?;
+    let y_new = (reserve_out - amount_out) as u128;
+    let x_new = stable_get_y(y_new, d, amp)?;
+
+    let amount_in = x_new
+        .checked_sub(reserve_in as u128)
+        .and_then(|v| v.checked_add(1))
+        .ok_or_else(overflow)?;
+    u64::try_from(amount_in).map_err(|_| overflow())
+}
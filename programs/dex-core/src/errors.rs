@@ -28,4 +28,127 @@ pub enum DexError {
 
     #[msg("Insufficient liquidity")]
     InsufficientLiquidity,
+
+    #[msg("Initial liquidity must exceed the locked minimum")]
+    InsufficientInitialLiquidity,
+
+    #[msg("Invalid swap route")]
+    InvalidRoute,
+
+    #[msg("Not enough oracle history for the requested window")]
+    OracleWindowUnavailable,
+
+    #[msg("Swap fee out of range")]
+    InvalidFee,
+
+    #[msg("Swap expired (deadline or max slot passed)")]
+    Expired,
+
+    #[msg("Price impact exceeds the pair limit")]
+    PriceImpactTooHigh,
+
+    #[msg("Invalid referral accounts")]
+    InvalidReferral,
+
+    #[msg("The referrer must sign the swap that records the referral")]
+    ReferrerNotSigner,
+
+    #[msg("Mint uses a Token-2022 extension the pair cannot hold safely")]
+    UnsupportedMintExtension,
+
+    #[msg("A flash loan is outstanding on this pair")]
+    FlashLoanActive,
+
+    #[msg("flash_borrow needs a matching top-level flash_repay later in the transaction")]
+    FlashRepayMissing,
+
+    #[msg("No flash loan outstanding on this pair")]
+    NoFlashLoan,
+
+    #[msg("Flash loan not repaid in full")]
+    FlashLoanNotRepaid,
+
+    #[msg("Amplification coefficient or ramp out of range")]
+    InvalidAmp,
+
+    #[msg("Operation not supported by this pair's curve")]
+    InvalidCurve,
+
+    #[msg("StableSwap invariant did not converge")]
+    CurveDidNotConverge,
+
+    #[msg("Pair mints must be passed in canonical (ascending) order")]
+    MintsNotCanonical,
+
+    #[msg("Fee split must sum to 10_000 bps")]
+    InvalidFeeSplit,
+
+    #[msg("Pair fee split burns: pass the writable kNite mint as burn_mint")]
+    BurnMintRequired,
+
+    #[msg("Invalid order accounts")]
+    InvalidOrder,
+
+    #[msg("No order in the batch is fillable at its limit price")]
+    NoFillableOrders,
+
+    #[msg("Invalid DCA schedule")]
+    InvalidDcaSchedule,
+
+    #[msg("DCA cycle not due yet")]
+    DcaCycleNotDue,
+
+    #[msg("DCA escrow account does not match the vault")]
+    InvalidDcaEscrow,
+
+    #[msg("Invalid tick or price")]
+    InvalidTick,
+
+    #[msg("Pool has no room for another initialized tick")]
+    ClTickLimit,
+
+    #[msg("Position liquidity is below the pool minimum")]
+    ClPositionTooSmall,
+
+    #[msg("Pair trades through batch auctions only")]
+    BatchModeActive,
+
+    #[msg("Invalid batch or batch commit")]
+    InvalidBatch,
+
+    #[msg("Batch is not in the required phase")]
+    BatchNotReady,
+
+    #[msg("Batch has reached its commit limit")]
+    BatchFull,
+
+    #[msg("Trading on this pair has not started yet")]
+    TradingNotStarted,
+
+    #[msg("Pair is in its launch phase: swap_exact_in with the wallet's launch cooldown account only")]
+    LaunchPhaseActive,
+
+    #[msg("Invalid launch phase parameters")]
+    InvalidLaunchPhase,
+
+    #[msg("Wallet is still in its launch cooldown")]
+    LaunchCooldownActive,
+
+    #[msg("Launch buy limit per wallet per slot exceeded")]
+    LaunchBuyLimitExceeded,
+
+    #[msg("Pair must be disabled first")]
+    PairStillEnabled,
+
+    #[msg("LP holder accounts must cover the whole LP supply")]
+    InvalidLpHolders,
+
+    #[msg("Pair is not in the registry")]
+    PairNotRegistered,
+
+    #[msg("Pair account layout version is not supported; run migrate_pair")]
+    UnsupportedPairVersion,
+
+    #[msg("Pair is already at the current layout version")]
+    PairAlreadyMigrated,
 }
@@ -0,0 +1,200 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, MintTo};
+use common_contracts::utils::now_ts;
+
+use crate::{AddLiquidity, LiquidityAdded};
+use crate::errors::DexError;
+use crate::math::{mul_div_ceil, mul_div_floor, sqrt_u128};
+use crate::state::{MINIMUM_LIQUIDITY, SEED_PAIR};
+use crate::tokens::TokenSide;
+
+/// Tokens to pull and LP to mint for a deposit of at most (max_0, max_1), against the
+/// stored reserves. Shared by kNite pairs and generic pairs (side 0/1 = knite/sub or a/b).
+pub struct DepositPlan {
+    pub amount_0: u64,
+    pub amount_1: u64,
+    pub lp_minted: u64,
+}
+
+/// (total LP, locked LP) a deposit is priced against. Reserves can predate the first
+/// deposit (vaults seeded, then sync): their liquidity sqrt(x * y) is then locked, so the
+/// first depositor buys in at the existing price instead of claiming them. (0, 0) = empty pool.
+pub fn lp_before_deposit(lp_supply: u64, lp_locked: u64, reserve_0: u64, reserve_1: u64) -> Result<(u64, u64)> {
+    if lp_supply > 0 || lp_locked > 0 {
+        let total_lp = lp_supply
+            .checked_add(lp_locked)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+        return Ok((total_lp, lp_locked));
+    }
+    if reserve_0 == 0 && reserve_1 == 0 {
+        return Ok((0, 0));
+    }
+    // one-sided reserves have no price to deposit at
+    if reserve_0 == 0 || reserve_1 == 0 {
+        return err!(DexError::InsufficientLiquidity);
+    }
+    let existing = sqrt_u128((reserve_0 as u128) * (reserve_1 as u128));
+    let existing = u64::try_from(existing).map_err(|_| error!(DexError::MathOverflow))?;
+    Ok((existing, existing))
+}
+
+pub fn plan_deposit(
+    is_first_deposit: bool,
+    max_0: u64,
+    max_1: u64,
+    reserve_0: u64,
+    reserve_1: u64,
+    total_lp: u64,
+) -> Result<DepositPlan> {
+    if is_first_deposit {
+        // first deposit into an empty pool: liquidity = sqrt(x * y), MINIMUM_LIQUIDITY locked forever
+        let lp_minted = first_deposit_lp(max_0, max_1)?;
+        return Ok(DepositPlan { amount_0: max_0, amount_1: max_1, lp_minted });
+    }
+
+    if reserve_0 == 0 || reserve_1 == 0 {
+        return err!(DexError::InsufficientLiquidity);
+    }
+
+    // LP is bounded by the scarcer side so the deposit never dilutes existing LPs
+    let lp_by_0 = mul_div_floor(max_0, total_lp, reserve_0)?;
+    let lp_by_1 = mul_div_floor(max_1, total_lp, reserve_1)?;
+    let lp_minted = lp_by_0.min(lp_by_1);
+    if lp_minted == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    // pull only the proportional amounts (rounded up in favour of the pool)
+    let amount_0 = mul_div_ceil(lp_minted, reserve_0, total_lp)?;
+    let amount_1 = mul_div_ceil(lp_minted, reserve_1, total_lp)?;
+    if amount_0 > max_0 || amount_1 > max_1 {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    Ok(DepositPlan { amount_0, amount_1, lp_minted })
+}
+
+/// LP for what the vaults actually received; only differs from the plan for
+/// Token-2022 mints with a transfer fee.
+pub fn lp_for_received(
+    plan: &DepositPlan,
+    is_first_deposit: bool,
+    received_0: u64,
+    received_1: u64,
+    reserve_0: u64,
+    reserve_1: u64,
+    total_lp: u64,
+) -> Result<u64> {
+    if received_0 == plan.amount_0 && received_1 == plan.amount_1 {
+        return Ok(plan.lp_minted);
+    }
+    if is_first_deposit {
+        return first_deposit_lp(received_0, received_1);
+    }
+    let lp_by_0 = mul_div_floor(received_0, total_lp, reserve_0)?;
+    let lp_by_1 = mul_div_floor(received_1, total_lp, reserve_1)?;
+    Ok(plan.lp_minted.min(lp_by_0).min(lp_by_1))
+}
+
+fn first_deposit_lp(amount_0: u64, amount_1: u64) -> Result<u64> {
+    let liquidity = sqrt_u128((amount_0 as u128) * (amount_1 as u128));
+    let liquidity = u64::try_from(liquidity).map_err(|_| error!(DexError::MathOverflow))?;
+    if liquidity <= MINIMUM_LIQUIDITY {
+        return err!(DexError::InsufficientInitialLiquidity);
+    }
+    Ok(liquidity - MINIMUM_LIQUIDITY)
+}
+
+pub fn handler(ctx: Context<AddLiquidity>, max_knite: u64, max_sub: u64, min_lp: u64) -> Result<()> {
+    if max_knite == 0 || max_sub == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+
+    // stored reserves before
+    let reserve_knite = pair.reserve_knite;
+    let reserve_sub = pair.reserve_sub;
+
+    let (total_lp, lp_locked) =
+        lp_before_deposit(ctx.accounts.lp_mint.supply, pair.lp_locked, reserve_knite, reserve_sub)?;
+    let is_first_deposit = total_lp == 0;
+    let plan = plan_deposit(is_first_deposit, max_knite, max_sub, reserve_knite, reserve_sub, total_lp)?;
+    let (amount_knite, amount_sub) = (plan.amount_0, plan.amount_1);
+    let lp_locked = if is_first_deposit { MINIMUM_LIQUIDITY } else { lp_locked };
+
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let sub = TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub);
+    let user = ctx.accounts.user.to_account_info();
+
+    // 1) kNite user -> vault_knite
+    let received_knite = knite.transfer_measured(
+        &ctx.accounts.user_ata_knite.to_account_info(),
+        &ctx.accounts.vault_knite.to_account_info(),
+        &user,
+        &[],
+        amount_knite,
+    )?;
+
+    // 2) sub user -> vault_sub
+    let received_sub = sub.transfer_measured(
+        &ctx.accounts.user_ata_sub.to_account_info(),
+        &ctx.accounts.vault_sub.to_account_info(),
+        &user,
+        &[],
+        amount_sub,
+    )?;
+
+    // LP is re-derived from what the vaults actually received (Token-2022 transfer fees)
+    let lp_minted = lp_for_received(&plan, is_first_deposit, received_knite, received_sub, reserve_knite, reserve_sub, total_lp)?;
+    if lp_minted == 0 || lp_minted < min_lp {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    // 3) mint LP to user (pair signs)
+    let bump_seed = [pair.bump];
+    let seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &bump_seed,
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                to: ctx.accounts.user_ata_lp.to_account_info(),
+                authority: ctx.accounts.pair.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        lp_minted,
+    )?;
+
+    let pair = &mut ctx.accounts.pair;
+    pair.lp_locked = lp_locked;
+    pair.update_oracle(reserve_knite, reserve_sub, now_ts()?)?;
+    pair.reserve_knite = reserve_knite
+        .checked_add(received_knite)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    pair.reserve_sub = reserve_sub
+        .checked_add(received_sub)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+    emit!(LiquidityAdded {
+        pair: pair.key(),
+        user: ctx.accounts.user.key(),
+        amount_knite: received_knite,
+        amount_sub: received_sub,
+        lp_minted,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::now_ts;
+
+use crate::{AmpRampUpdated, UpdatePairConfig};
+use crate::errors::DexError;
+use crate::assert_treasury_signer;
+use crate::state::{CurveType, Pair, MAX_AMP, MAX_AMP_CHANGE, MIN_AMP, MIN_AMP_RAMP_SECS};
+
+/// Treasury-only: move the StableSwap amp linearly from its current value to `target_amp`,
+/// reaching it at `ramp_end_ts` (at least MIN_AMP_RAMP_SECS away, at most a MAX_AMP_CHANGE
+/// factor up or down) so LPs are not hit by a sudden curve change.
+pub fn handler_ramp(ctx: Context<UpdatePairConfig>, target_amp: u64, ramp_end_ts: i64) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+    check_stable_treasury(pair, ctx.accounts.treasury_signer.key())?;
+
+    let now = now_ts()?;
+    if !(MIN_AMP..=MAX_AMP).contains(&target_amp) || ramp_end_ts < now.saturating_add(MIN_AMP_RAMP_SECS) {
+        return err!(DexError::InvalidAmp);
+    }
+
+    let current = pair.amp_at(now);
+    let too_far_up = target_amp > current.saturating_mul(MAX_AMP_CHANGE);
+    let too_far_down = target_amp.saturating_mul(MAX_AMP_CHANGE) < current;
+    if too_far_up || too_far_down {
+        return err!(DexError::InvalidAmp);
+    }
+
+    pair.amp_initial = current;
+    pair.amp_target = target_amp;
+    pair.amp_ramp_start_ts = now;
+    pair.amp_ramp_end_ts = ramp_end_ts;
+
+    emit_ramp(pair);
+    Ok(())
+}
+
+/// Treasury-only: freeze the amp at its current ramped value.
+pub fn handler_stop(ctx: Context<UpdatePairConfig>) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+    check_stable_treasury(pair, ctx.accounts.treasury_signer.key())?;
+
+    let now = now_ts()?;
+    let current = pair.amp_at(now);
+    pair.amp_initial = current;
+    pair.amp_target = current;
+    pair.amp_ramp_start_ts = now;
+    pair.amp_ramp_end_ts = now;
+
+    emit_ramp(pair);
+    Ok(())
+}
+
+fn check_stable_treasury(pair: &Pair, treasury_signer: Pubkey) -> Result<()> {
+    assert_treasury_signer(pair, treasury_signer)?;
+    if pair.curve_type != CurveType::StableSwap {
+        return err!(DexError::InvalidCurve);
+    }
+    Ok(())
+}
+
+fn emit_ramp(pair: &Account<Pair>) {
+    emit!(AmpRampUpdated {
+        pair: pair.key(),
+        amp_initial: pair.amp_initial,
+        amp_target: pair.amp_target,
+        ramp_start_ts: pair.amp_ramp_start_ts,
+        ramp_end_ts: pair.amp_ramp_end_ts,
+    });
+}
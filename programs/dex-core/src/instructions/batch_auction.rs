@@ -0,0 +1,510 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::{now_slot, now_ts};
+
+use crate::{
+    BatchClaimed, BatchCommitted, BatchOpened, BatchSettled, BatchWithdrawn, ClaimBatch, CloseBatch, CommitBatchSwap,
+    OpenBatch, SettleBatch, SwapExecuted,
+};
+use crate::curve::Curve;
+use crate::errors::DexError;
+use crate::instructions::swap::{
+    apply_fee_split, compute_exact_in, pay_output, pull_input, record_swap, reprice_on_received, SwapAmounts,
+    SwapLegAccounts,
+};
+use crate::math::mul_div_floor;
+use crate::state::{Batch, BatchCommit, Pair, MAX_BATCH_COMMITS, SEED_BATCH, SEED_PAIR};
+use crate::tokens::TokenSide;
+use crate::assert_treasury_knite_ata;
+
+// Batch auctions: during an epoch of `pair.batch_slots` slots users commit swap intents and
+// escrow their input in the batch. Once the epoch is over anyone settles the batch: both
+// sides are matched against each other and only the imbalance trades with the pool, sized
+// so every filled commit clears at the same price (ordering inside the batch is worthless).
+// Commits whose min_out that price misses stay unfilled and get their input back on claim.
+// A batch left unsettled past BATCH_SETTLE_WINDOW_SLOTS lets every commit withdraw.
+
+pub fn handler_open(ctx: Context<OpenBatch>, epoch: u64) -> Result<()> {
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+    if pair.batch_slots == 0 {
+        return err!(DexError::InvalidBatch);
+    }
+
+    // only the running epoch can be opened
+    let slot = now_slot()?;
+    if epoch != slot / pair.batch_slots {
+        return err!(DexError::BatchNotReady);
+    }
+    let end_slot = epoch
+        .checked_add(1)
+        .and_then(|e| e.checked_mul(pair.batch_slots))
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+    let batch = &mut ctx.accounts.batch;
+    batch.bump = ctx.bumps.batch;
+    batch.escrow_knite_bump = ctx.bumps.escrow_knite;
+    batch.escrow_sub_bump = ctx.bumps.escrow_sub;
+    batch.pair = pair.key();
+    batch.payer = ctx.accounts.payer.key();
+    batch.epoch = epoch;
+    batch.end_slot = end_slot;
+    batch.escrow_knite = ctx.accounts.escrow_knite.key();
+    batch.escrow_sub = ctx.accounts.escrow_sub.key();
+    batch.commits = 0;
+    batch.commits_open = 0;
+    batch.total_knite_in = 0;
+    batch.total_sub_in = 0;
+    batch.settled = false;
+    batch.fill_knite_in = 0;
+    batch.fill_knite_out = 0;
+    batch.fill_sub_in = 0;
+    batch.fill_sub_out = 0;
+
+    emit!(BatchOpened { batch: batch.key(), pair: batch.pair, epoch, end_slot });
+
+    Ok(())
+}
+
+pub fn handler_commit(ctx: Context<CommitBatchSwap>, amount_in: u64, min_out: u64) -> Result<()> {
+    if amount_in == 0 || min_out == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+    if pair.batch_slots == 0 {
+        return err!(DexError::InvalidBatch);
+    }
+
+    let batch = &ctx.accounts.batch;
+    if now_slot()? >= batch.end_slot {
+        return err!(DexError::BatchNotReady);
+    }
+    if batch.commits >= MAX_BATCH_COMMITS {
+        return err!(DexError::BatchFull);
+    }
+
+    // direction from mint_in; the escrow must be the batch's for that side
+    let mint_in = ctx.accounts.mint_in.key();
+    let is_knite_in = mint_in == pair.mint_knite;
+    if !is_knite_in && mint_in != pair.mint_sub {
+        return err!(DexError::TokenNotAllowed);
+    }
+    let escrow = if is_knite_in { batch.escrow_knite } else { batch.escrow_sub };
+    if ctx.accounts.escrow_in.key() != escrow {
+        return err!(DexError::InvalidBatch);
+    }
+
+    // escrow what actually arrives (Token-2022 transfer fees)
+    let side_in = TokenSide::new(ctx.accounts.token_program_in.to_account_info(), &ctx.accounts.mint_in);
+    let received = side_in.transfer_measured(
+        &ctx.accounts.owner_ata_in.to_account_info(),
+        &ctx.accounts.escrow_in.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        &[],
+        amount_in,
+    )?;
+
+    let commit = &mut ctx.accounts.commit;
+    commit.bump = ctx.bumps.commit;
+    commit.batch = batch.key();
+    commit.owner = ctx.accounts.owner.key();
+    commit.is_knite_in = is_knite_in;
+    commit.amount_in = received;
+    commit.min_out = min_out;
+    commit.filled = false;
+
+    let overflow = || error!(DexError::MathOverflow);
+    let batch = &mut ctx.accounts.batch;
+    batch.commits += 1;
+    batch.commits_open += 1;
+    if is_knite_in {
+        batch.total_knite_in = batch.total_knite_in.checked_add(received).ok_or_else(overflow)?;
+    } else {
+        batch.total_sub_in = batch.total_sub_in.checked_add(received).ok_or_else(overflow)?;
+    }
+
+    emit!(BatchCommitted {
+        batch: batch.key(),
+        owner: commit.owner,
+        is_knite_in,
+        amount_in: received,
+        min_out,
+    });
+
+    Ok(())
+}
+
+/// Uniform clearing of `knite_in` against `sub_in`. The side worth more at the pool price
+/// sends `net_in` through the pool; everything else is matched inside the batch.
+struct Clearing {
+    net_is_knite_in: bool,
+    net_in: u64,
+    /// pool leg for `net_in` (None when nothing trades with the pool)
+    amounts: Option<SwapAmounts>,
+    /// sub owed to the kNite side, kNite owed to the sub side
+    knite_side_out: u64,
+    sub_side_out: u64,
+}
+
+impl Clearing {
+    /// Splits the batch output given what the pool leg delivered for `net_in`.
+    fn new(
+        net_is_knite_in: bool,
+        net_in: u64,
+        amounts: Option<SwapAmounts>,
+        knite_in: u64,
+        sub_in: u64,
+        net_out: u64,
+    ) -> Result<Self> {
+        let overflow = || error!(DexError::MathOverflow);
+        let (knite_side_out, sub_side_out) = if net_is_knite_in {
+            (sub_in.checked_add(net_out).ok_or_else(overflow)?, knite_in.checked_sub(net_in).ok_or_else(overflow)?)
+        } else {
+            (sub_in.checked_sub(net_in).ok_or_else(overflow)?, knite_in.checked_add(net_out).ok_or_else(overflow)?)
+        };
+        Ok(Self { net_is_knite_in, net_in, amounts, knite_side_out, sub_side_out })
+    }
+
+    /// Pro-rata output for a commit of `amount_in` on its side.
+    fn share(&self, is_knite_in: bool, amount_in: u64, knite_in: u64, sub_in: u64) -> Result<u64> {
+        if is_knite_in {
+            mul_div_floor(amount_in, self.knite_side_out, knite_in)
+        } else {
+            mul_div_floor(amount_in, self.sub_side_out, sub_in)
+        }
+    }
+}
+
+/// Pool leg for `amount_in`, or None when it is too small to trade.
+fn pool_leg(pair: &Pair, curve: Curve, is_knite_in: bool, amount_in: u64) -> Result<Option<SwapAmounts>> {
+    if amount_in == 0 {
+        return Ok(None);
+    }
+    match compute_exact_in(pair.swap_fee_bps, curve, is_knite_in, amount_in, pair.reserve_knite, pair.reserve_sub) {
+        Ok(mut amounts) => {
+            apply_fee_split(pair, false, &mut amounts)?;
+            Ok(Some(amounts))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// With `total` on the heavier side and `other` on the lighter one, the pool takes the
+/// largest `x` with out(x) * (total - x) >= other * x: at that point the pool price
+/// out(x) / x and the matched price other / (total - x) meet, so both sides clear at one
+/// price. out(x) / x falls and other / (total - x) rises with x, so a binary search finds it.
+fn clear(pair: &Pair, curve: Curve, knite_in: u64, sub_in: u64) -> Result<Clearing> {
+    let net_is_knite_in =
+        (knite_in as u128) * (pair.reserve_sub as u128) >= (sub_in as u128) * (pair.reserve_knite as u128);
+    let (total, other) = if net_is_knite_in { (knite_in, sub_in) } else { (sub_in, knite_in) };
+
+    let feasible = |x: u64| -> Result<bool> {
+        let out = pool_leg(pair, curve, net_is_knite_in, x)?.map_or(0, |a| a.amount_out);
+        Ok((out as u128) * ((total - x) as u128) >= (other as u128) * (x as u128))
+    };
+    let net_in = if feasible(total)? {
+        total
+    } else {
+        let (mut lo, mut hi) = (0u64, total);
+        while hi - lo > 1 {
+            let mid = lo + (hi - lo) / 2;
+            if feasible(mid)? {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+        lo
+    };
+
+    match pool_leg(pair, curve, net_is_knite_in, net_in)? {
+        Some(amounts) => Clearing::new(net_is_knite_in, net_in, Some(amounts), knite_in, sub_in, amounts.amount_out),
+        None => Clearing::new(net_is_knite_in, 0, None, knite_in, sub_in, 0),
+    }
+}
+
+pub fn handler_settle<'info>(ctx: Context<'_, '_, 'info, 'info, SettleBatch<'info>>) -> Result<()> {
+    let batch = &ctx.accounts.batch;
+    if batch.settled {
+        return err!(DexError::InvalidBatch);
+    }
+    let slot = now_slot()?;
+    if slot < batch.end_slot || batch.is_expired(slot) {
+        return err!(DexError::BatchNotReady);
+    }
+
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+    assert_treasury_knite_ata(pair, ctx.accounts.treasury_knite_ata.key(), &ctx.accounts.token_program_knite.key())?;
+
+    // every commit of the batch exactly once (ascending keys rule out duplicates)
+    let rem = ctx.remaining_accounts;
+    if rem.len() != batch.commits as usize || rem.windows(2).any(|w| w[0].key() >= w[1].key()) {
+        return err!(DexError::InvalidBatch);
+    }
+    let mut commits = Vec::with_capacity(rem.len());
+    for info in rem {
+        if !info.is_writable {
+            return err!(DexError::InvalidBatch);
+        }
+        let commit: Account<'info, BatchCommit> = Account::try_from(info)?;
+        if commit.batch != batch.key() {
+            return err!(DexError::InvalidBatch);
+        }
+        commits.push(commit);
+    }
+
+    // clear, drop the commits the price misses, and re-clear until every filled one holds
+    let curve = pair.curve_at(now_ts()?);
+    let mut filled = vec![true; commits.len()];
+    let overflow = || error!(DexError::MathOverflow);
+    let (mut knite_in, mut sub_in, mut clearing);
+    loop {
+        knite_in = 0u64;
+        sub_in = 0u64;
+        for (commit, _) in commits.iter().zip(&filled).filter(|(_, f)| **f) {
+            if commit.is_knite_in {
+                knite_in = knite_in.checked_add(commit.amount_in).ok_or_else(overflow)?;
+            } else {
+                sub_in = sub_in.checked_add(commit.amount_in).ok_or_else(overflow)?;
+            }
+        }
+        clearing = clear(pair, curve, knite_in, sub_in)?;
+
+        let mut dropped = false;
+        for (commit, f) in commits.iter().zip(filled.iter_mut()).filter(|(_, f)| **f) {
+            if clearing.share(commit.is_knite_in, commit.amount_in, knite_in, sub_in)? < commit.min_out {
+                *f = false;
+                dropped = true;
+            }
+        }
+        if !dropped {
+            break;
+        }
+    }
+
+    // signer seeds: batch PDA (escrow authority) and pair PDA (vault authority)
+    let pair_key = pair.key();
+    let epoch = batch.epoch.to_le_bytes();
+    let batch_bump = [batch.bump];
+    let batch_seeds: &[&[u8]] = &[SEED_BATCH, pair_key.as_ref(), &epoch, &batch_bump];
+    let batch_signer: &[&[&[u8]]] = &[batch_seeds];
+
+    let pair_bump = [pair.bump];
+    let pair_seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &pair_bump,
+    ];
+    let pair_signer: &[&[&[u8]]] = &[pair_seeds];
+
+    let mut net_out = 0;
+    if let Some(amounts) = clearing.amounts {
+        let is_knite_in = clearing.net_is_knite_in;
+        let (escrow_in, escrow_out) = if is_knite_in {
+            (ctx.accounts.escrow_knite.to_account_info(), ctx.accounts.escrow_sub.to_account_info())
+        } else {
+            (ctx.accounts.escrow_sub.to_account_info(), ctx.accounts.escrow_knite.to_account_info())
+        };
+        let accs = SwapLegAccounts {
+            knite: TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite),
+            sub: TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub),
+            user: batch.to_account_info(),
+            user_ata_in: escrow_in,
+            user_ata_out: escrow_out,
+            pair: pair.to_account_info(),
+            vault_knite: ctx.accounts.vault_knite.to_account_info(),
+            vault_sub: ctx.accounts.vault_sub.to_account_info(),
+            treasury_knite_ata: ctx.accounts.treasury_knite_ata.to_account_info(),
+            referrer_knite_ata: None,
+            burn_mint: ctx.accounts.burn_mint.as_ref().map(|m| m.to_account_info()),
+        };
+
+        let (reserve_knite, reserve_sub) = (pair.reserve_knite, pair.reserve_sub);
+        let received = pull_input(&accs, batch_signer, is_knite_in, &amounts)?;
+        let mut amounts =
+            reprice_on_received(pair.swap_fee_bps, curve, is_knite_in, &amounts, received, reserve_knite, reserve_sub)?;
+        apply_fee_split(pair, false, &mut amounts)?;
+        // check_swap_limits rejects pairs in batch mode; the launch gate and impact breaker still apply
+        if pair.in_launch_phase(now_ts()?)? {
+            return err!(DexError::LaunchPhaseActive);
+        }
+        let after = amounts.reserves_after(is_knite_in, reserve_knite, reserve_sub)?;
+        pair.check_price_impact((reserve_knite, reserve_sub), after)?;
+
+        net_out = pay_output(&accs, pair_signer, is_knite_in, &amounts)?;
+        record_swap(&mut ctx.accounts.pair, pair_key, is_knite_in, &amounts)?;
+
+        // split what the pool actually delivered; limits are re-checked on it
+        clearing = Clearing::new(is_knite_in, clearing.net_in, Some(amounts), knite_in, sub_in, net_out)?;
+        for (commit, _) in commits.iter().zip(&filled).filter(|(_, f)| **f) {
+            if clearing.share(commit.is_knite_in, commit.amount_in, knite_in, sub_in)? < commit.min_out {
+                return err!(DexError::SlippageExceeded);
+            }
+        }
+
+        emit!(SwapExecuted {
+            pair: pair_key,
+            user: ctx.accounts.batch.key(),
+            mint_in: if is_knite_in { ctx.accounts.mint_knite.key() } else { ctx.accounts.mint_sub.key() },
+            mint_out: if is_knite_in { ctx.accounts.mint_sub.key() } else { ctx.accounts.mint_knite.key() },
+            amount_in: amounts.amount_in,
+            amount_out: net_out,
+            fee_knite: amounts.fee_knite,
+            fee_lp: amounts.fee_lp,
+            fee_treasury: amounts.fee_treasury()?,
+            fee_burn: amounts.fee_burn,
+            fee_referral: amounts.fee_referral,
+        });
+    }
+
+    let mut unfilled = 0u8;
+    for (commit, f) in commits.iter_mut().zip(&filled) {
+        commit.filled = *f;
+        if !*f {
+            unfilled += 1;
+        }
+        commit.exit(&crate::ID)?;
+    }
+
+    let batch = &mut ctx.accounts.batch;
+    batch.settled = true;
+    batch.fill_knite_in = knite_in;
+    batch.fill_knite_out = clearing.knite_side_out;
+    batch.fill_sub_in = sub_in;
+    batch.fill_sub_out = clearing.sub_side_out;
+
+    emit!(BatchSettled {
+        batch: batch.key(),
+        pair: pair_key,
+        fill_knite_in: knite_in,
+        fill_knite_out: clearing.knite_side_out,
+        fill_sub_in: sub_in,
+        fill_sub_out: clearing.sub_side_out,
+        net_is_knite_in: clearing.net_is_knite_in,
+        net_in: clearing.net_in,
+        net_out,
+        unfilled,
+    });
+
+    Ok(())
+}
+
+pub fn handler_claim(ctx: Context<ClaimBatch>) -> Result<()> {
+    let batch = &ctx.accounts.batch;
+    if !batch.settled {
+        return err!(DexError::BatchNotReady);
+    }
+
+    let commit = &ctx.accounts.commit;
+    let (amount_out, amount_returned) = if commit.filled {
+        // pro rata on what the side has left, so the last claim takes the remainder
+        let overflow = || error!(DexError::MathOverflow);
+        let batch: &mut Batch = &mut ctx.accounts.batch;
+        let (fill_in, fill_out) = if commit.is_knite_in {
+            (&mut batch.fill_knite_in, &mut batch.fill_knite_out)
+        } else {
+            (&mut batch.fill_sub_in, &mut batch.fill_sub_out)
+        };
+        let amount_out = mul_div_floor(commit.amount_in, *fill_out, *fill_in)?;
+        *fill_in = fill_in.checked_sub(commit.amount_in).ok_or_else(overflow)?;
+        *fill_out = fill_out.checked_sub(amount_out).ok_or_else(overflow)?;
+        (amount_out, 0)
+    } else {
+        (0, commit.amount_in)
+    };
+
+    pay_commit(&ctx, !commit.is_knite_in, amount_out)?;
+    pay_commit(&ctx, commit.is_knite_in, amount_returned)?;
+    ctx.accounts.batch.commits_open -= 1;
+
+    emit!(BatchClaimed {
+        batch: ctx.accounts.batch.key(),
+        owner: commit.owner,
+        filled: commit.filled,
+        amount_out,
+        amount_returned,
+    });
+
+    // commit account itself is closed to `owner` by the Accounts constraint
+    Ok(())
+}
+
+pub fn handler_withdraw(ctx: Context<ClaimBatch>) -> Result<()> {
+    let batch = &ctx.accounts.batch;
+    if batch.settled || !batch.is_expired(now_slot()?) {
+        return err!(DexError::BatchNotReady);
+    }
+
+    let commit = &ctx.accounts.commit;
+    pay_commit(&ctx, commit.is_knite_in, commit.amount_in)?;
+    ctx.accounts.batch.commits_open -= 1;
+
+    emit!(BatchWithdrawn {
+        batch: ctx.accounts.batch.key(),
+        owner: commit.owner,
+        amount_returned: commit.amount_in,
+    });
+
+    // commit account itself is closed to `owner` by the Accounts constraint
+    Ok(())
+}
+
+pub fn handler_close(ctx: Context<CloseBatch>) -> Result<()> {
+    let batch = &ctx.accounts.batch;
+    if batch.commits_open > 0 || now_slot()? < batch.end_slot {
+        return err!(DexError::BatchNotReady);
+    }
+
+    let epoch = batch.epoch.to_le_bytes();
+    let bump_seed = [batch.bump];
+    let seeds: &[&[u8]] = &[SEED_BATCH, batch.pair.as_ref(), &epoch, &bump_seed];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let sub = TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub);
+    let batch_info = batch.to_account_info();
+    let payer = ctx.accounts.payer.to_account_info();
+    knite.close_escrow(&ctx.accounts.escrow_knite.to_account_info(), &payer, &batch_info, signer_seeds)?;
+    sub.close_escrow(&ctx.accounts.escrow_sub.to_account_info(), &payer, &batch_info, signer_seeds)?;
+
+    // batch account itself is closed to `payer` by the Accounts constraint
+    Ok(())
+}
+
+/// batch escrow -> owner ATA of the kNite or sub side (batch signs).
+fn pay_commit(ctx: &Context<ClaimBatch>, knite_side: bool, amount: u64) -> Result<()> {
+    let batch = &ctx.accounts.batch;
+    let epoch = batch.epoch.to_le_bytes();
+    let bump_seed = [batch.bump];
+    let seeds: &[&[u8]] = &[SEED_BATCH, batch.pair.as_ref(), &epoch, &bump_seed];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+    let batch_info = batch.to_account_info();
+
+    if knite_side {
+        TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite).transfer(
+            &ctx.accounts.escrow_knite.to_account_info(),
+            &ctx.accounts.owner_knite_ata.to_account_info(),
+            &batch_info,
+            signer_seeds,
+            amount,
+        )
+    } else {
+        TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub).transfer(
+            &ctx.accounts.escrow_sub.to_account_info(),
+            &ctx.accounts.owner_sub_ata.to_account_info(),
+            &batch_info,
+            signer_seeds,
+            amount,
+        )
+    }
+}
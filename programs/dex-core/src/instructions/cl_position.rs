@@ -0,0 +1,241 @@
+use anchor_lang::prelude::*;
+
+use crate::{ClFeesCollected, ClLiquidity, ClLiquidityChanged, CloseClPosition, OpenClPosition};
+use crate::clmm::{amounts_for_liquidity, sqrt_price_at_tick, MAX_TICK, MIN_TICK};
+use crate::errors::DexError;
+use crate::state::{ClPool, SEED_CL_POOL};
+use crate::tokens::{gross_up_transfer_fee, TokenSide};
+
+// A position provides liquidity only while the pool price is inside [tick_lower, tick_upper).
+// Fees are tracked with fee growth inside the range, so every liquidity change first credits
+// what the position earned; collect_cl_fees pays it out from the vaults.
+
+pub fn handler_open(ctx: Context<OpenClPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+    let spacing = ctx.accounts.pool.tick_spacing as i32;
+    if tick_lower >= tick_upper
+        || tick_lower < MIN_TICK
+        || tick_upper > MAX_TICK
+        || tick_lower % spacing != 0
+        || tick_upper % spacing != 0
+    {
+        return err!(DexError::InvalidTick);
+    }
+
+    let position = &mut ctx.accounts.position;
+    position.bump = ctx.bumps.position;
+    position.pool = ctx.accounts.pool.key();
+    position.owner = ctx.accounts.owner.key();
+    position.tick_lower = tick_lower;
+    position.tick_upper = tick_upper;
+    position.liquidity = 0;
+    position.fee_growth_inside_knite_last_q64 = 0;
+    position.fee_growth_inside_sub_last_q64 = 0;
+    position.fees_owed_knite = 0;
+    position.fees_owed_sub = 0;
+
+    Ok(())
+}
+
+pub fn handler_add(ctx: Context<ClLiquidity>, liquidity: u128, max_knite: u64, max_sub: u64) -> Result<()> {
+    let delta = i128::try_from(liquidity).map_err(|_| error!(DexError::MathOverflow))?;
+    if delta == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+    if !ctx.accounts.pool.enabled {
+        return err!(DexError::PairDisabled);
+    }
+
+    let (lower, upper) = (ctx.accounts.position.tick_lower, ctx.accounts.position.tick_upper);
+    let overflow = || error!(DexError::MathOverflow);
+
+    let pool = &mut ctx.accounts.pool;
+    pool.update_tick(lower, delta, false)?;
+    pool.update_tick(upper, delta, true)?;
+    if in_range(pool, lower, upper) {
+        pool.liquidity = pool.liquidity.checked_add(liquidity).ok_or_else(overflow)?;
+    }
+    let inside = pool.fee_growth_inside(lower, upper)?;
+
+    let position = &mut ctx.accounts.position;
+    position.accrue_fees(inside)?;
+    position.liquidity = position.liquidity.checked_add(liquidity).ok_or_else(overflow)?;
+    pool.check_position_liquidity(position.liquidity)?;
+
+    // owed to the pool: round up
+    let (amount_knite, amount_sub) = position_amounts(pool, lower, upper, liquidity, true)?;
+
+    // gross up so the vaults net the full amounts (Token-2022 transfer fees)
+    let send_knite = gross_up_transfer_fee(&ctx.accounts.mint_knite.to_account_info(), amount_knite)?;
+    let send_sub = gross_up_transfer_fee(&ctx.accounts.mint_sub.to_account_info(), amount_sub)?;
+    if send_knite > max_knite || send_sub > max_sub {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let sub = TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub);
+    let owner = ctx.accounts.owner.to_account_info();
+
+    let received_knite = knite.transfer_measured(
+        &ctx.accounts.owner_knite_ata.to_account_info(),
+        &ctx.accounts.vault_knite.to_account_info(),
+        &owner,
+        &[],
+        send_knite,
+    )?;
+    let received_sub = sub.transfer_measured(
+        &ctx.accounts.owner_sub_ata.to_account_info(),
+        &ctx.accounts.vault_sub.to_account_info(),
+        &owner,
+        &[],
+        send_sub,
+    )?;
+    if received_knite < amount_knite || received_sub < amount_sub {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    emit!(ClLiquidityChanged {
+        pool: ctx.accounts.pool.key(),
+        position: ctx.accounts.position.key(),
+        owner: owner.key(),
+        tick_lower: lower,
+        tick_upper: upper,
+        liquidity_delta: delta,
+        amount_knite: received_knite,
+        amount_sub: received_sub,
+    });
+
+    Ok(())
+}
+
+pub fn handler_remove(ctx: Context<ClLiquidity>, liquidity: u128, min_knite: u64, min_sub: u64) -> Result<()> {
+    let delta = i128::try_from(liquidity).map_err(|_| error!(DexError::MathOverflow))?;
+    if delta == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let (lower, upper) = (ctx.accounts.position.tick_lower, ctx.accounts.position.tick_upper);
+
+    // credit fees earned so far before the liquidity changes
+    let inside = ctx.accounts.pool.fee_growth_inside(lower, upper)?;
+    let position = &mut ctx.accounts.position;
+    position.accrue_fees(inside)?;
+    position.liquidity = position
+        .liquidity
+        .checked_sub(liquidity)
+        .ok_or_else(|| error!(DexError::InsufficientLiquidity))?;
+    ctx.accounts.pool.check_position_liquidity(position.liquidity)?;
+
+    let pool = &mut ctx.accounts.pool;
+    pool.update_tick(lower, -delta, false)?;
+    pool.update_tick(upper, -delta, true)?;
+    if in_range(pool, lower, upper) {
+        pool.liquidity = pool
+            .liquidity
+            .checked_sub(liquidity)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+    }
+
+    // paid out by the pool: round down
+    let (amount_knite, amount_sub) = position_amounts(pool, lower, upper, liquidity, false)?;
+    if amount_knite < min_knite || amount_sub < min_sub {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    pay_from_vaults(&ctx, amount_knite, amount_sub)?;
+
+    emit!(ClLiquidityChanged {
+        pool: ctx.accounts.pool.key(),
+        position: ctx.accounts.position.key(),
+        owner: ctx.accounts.owner.key(),
+        tick_lower: lower,
+        tick_upper: upper,
+        liquidity_delta: -delta,
+        amount_knite,
+        amount_sub,
+    });
+
+    Ok(())
+}
+
+pub fn handler_collect(ctx: Context<ClLiquidity>) -> Result<()> {
+    let position = &ctx.accounts.position;
+    if position.liquidity > 0 {
+        let inside = ctx.accounts.pool.fee_growth_inside(position.tick_lower, position.tick_upper)?;
+        ctx.accounts.position.accrue_fees(inside)?;
+    }
+
+    let amount_knite = ctx.accounts.position.fees_owed_knite;
+    let amount_sub = ctx.accounts.position.fees_owed_sub;
+    pay_from_vaults(&ctx, amount_knite, amount_sub)?;
+
+    let position = &mut ctx.accounts.position;
+    position.fees_owed_knite = 0;
+    position.fees_owed_sub = 0;
+
+    emit!(ClFeesCollected {
+        pool: ctx.accounts.pool.key(),
+        position: position.key(),
+        owner: position.owner,
+        amount_knite,
+        amount_sub,
+    });
+
+    Ok(())
+}
+
+pub fn handler_close(ctx: Context<CloseClPosition>) -> Result<()> {
+    let position = &ctx.accounts.position;
+    if position.liquidity > 0 || position.fees_owed_knite > 0 || position.fees_owed_sub > 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    // position account itself is closed to `owner` by the Accounts constraint
+    Ok(())
+}
+
+/// Whether liquidity in [lower, upper) is active at the current price.
+fn in_range(pool: &ClPool, lower: i32, upper: i32) -> bool {
+    pool.tick_current >= lower && pool.tick_current < upper
+}
+
+fn position_amounts(pool: &ClPool, lower: i32, upper: i32, liquidity: u128, round_up: bool) -> Result<(u64, u64)> {
+    amounts_for_liquidity(
+        pool.sqrt_price_q64,
+        sqrt_price_at_tick(lower)?,
+        sqrt_price_at_tick(upper)?,
+        liquidity,
+        round_up,
+    )
+}
+
+/// vault -> owner for both sides (pool signs).
+fn pay_from_vaults(ctx: &Context<ClLiquidity>, amount_knite: u64, amount_sub: u64) -> Result<()> {
+    let pool = &ctx.accounts.pool;
+    let bump_seed = [pool.bump];
+    let seeds: &[&[u8]] = &[
+        SEED_CL_POOL,
+        pool.treasury.as_ref(),
+        pool.mint_knite.as_ref(),
+        pool.mint_sub.as_ref(),
+        &bump_seed,
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+    let pool_info = pool.to_account_info();
+
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let sub = TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub);
+    knite.transfer(
+        &ctx.accounts.vault_knite.to_account_info(),
+        &ctx.accounts.owner_knite_ata.to_account_info(),
+        &pool_info,
+        signer_seeds,
+        amount_knite,
+    )?;
+    sub.transfer(
+        &ctx.accounts.vault_sub.to_account_info(),
+        &ctx.accounts.owner_sub_ata.to_account_info(),
+        &pool_info,
+        signer_seeds,
+        amount_sub,
+    )
+}
@@ -0,0 +1,188 @@
+use anchor_lang::prelude::*;
+use common_contracts::constants::BPS_DENOMINATOR;
+
+use crate::{ClSwapExecuted, SwapCl};
+use crate::clmm::{
+    compute_swap_step, sqrt_price_at_tick, tick_at_sqrt_price, MAX_SQRT_PRICE_Q64, MIN_SQRT_PRICE_Q64,
+};
+use crate::errors::DexError;
+use crate::math::{mul_div_floor, mul_div_u128};
+use crate::state::{ClPool, CL_PROTOCOL_FEE_SHARE_BPS, SEED_CL_POOL};
+use crate::tokens::TokenSide;
+use crate::assert_treasury_ata;
+
+/// Outcome of an exact-in swap against a ClPool (all in token units).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ClSwapResult {
+    /// input consumed, fee included (the rest is refunded)
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee: u64,
+    /// part of `fee` for the treasury; the rest accrued to in-range liquidity
+    pub protocol_fee: u64,
+}
+
+/// Walks the price across initialized ticks until `amount_in` is spent or liquidity runs
+/// out in the swap direction, updating price, tick, liquidity and fee growth.
+pub fn apply_cl_swap(pool: &mut ClPool, is_knite_in: bool, amount_in: u64) -> Result<ClSwapResult> {
+    let overflow = || error!(DexError::MathOverflow);
+    let mut remaining = amount_in;
+    let mut result = ClSwapResult::default();
+
+    while remaining > 0 {
+        // next initialized tick in the swap direction (kNite in moves the price down)
+        let next = if is_knite_in {
+            pool.ticks.iter().rposition(|t| t.index <= pool.tick_current)
+        } else {
+            pool.ticks.iter().position(|t| t.index > pool.tick_current)
+        };
+        if next.is_none() && pool.liquidity == 0 {
+            break;
+        }
+        let sqrt_target = match next {
+            Some(pos) => sqrt_price_at_tick(pool.ticks[pos].index)?,
+            None if is_knite_in => MIN_SQRT_PRICE_Q64,
+            None => MAX_SQRT_PRICE_Q64,
+        };
+
+        let step = compute_swap_step(
+            pool.sqrt_price_q64,
+            sqrt_target,
+            pool.liquidity,
+            remaining,
+            pool.swap_fee_bps,
+            is_knite_in,
+        )?;
+        let spent = step.amount_in.checked_add(step.fee).ok_or_else(overflow)?;
+        remaining = remaining.checked_sub(spent).ok_or_else(overflow)?;
+        result.amount_in = result.amount_in.checked_add(spent).ok_or_else(overflow)?;
+        result.amount_out = result.amount_out.checked_add(step.amount_out).ok_or_else(overflow)?;
+        result.fee = result.fee.checked_add(step.fee).ok_or_else(overflow)?;
+
+        // protocol share to the treasury, the rest to in-range liquidity
+        let protocol_fee = if pool.liquidity == 0 {
+            step.fee
+        } else {
+            mul_div_floor(step.fee, CL_PROTOCOL_FEE_SHARE_BPS as u64, BPS_DENOMINATOR)?
+        };
+        result.protocol_fee = result.protocol_fee.checked_add(protocol_fee).ok_or_else(overflow)?;
+        let lp_fee = step.fee - protocol_fee;
+        if lp_fee > 0 {
+            let growth = mul_div_u128(lp_fee as u128, 1u128 << 64, pool.liquidity).ok_or_else(overflow)?;
+            if is_knite_in {
+                pool.fee_growth_global_knite_q64 = pool.fee_growth_global_knite_q64.wrapping_add(growth);
+            } else {
+                pool.fee_growth_global_sub_q64 = pool.fee_growth_global_sub_q64.wrapping_add(growth);
+            }
+        }
+
+        pool.sqrt_price_q64 = step.sqrt_price_next;
+        if step.sqrt_price_next == sqrt_target {
+            let Some(pos) = next else {
+                // price bound reached
+                break;
+            };
+            let index = pool.ticks[pos].index;
+            pool.cross_tick(pos, !is_knite_in)?;
+            pool.tick_current = if is_knite_in { index - 1 } else { index };
+        } else {
+            pool.tick_current = tick_at_sqrt_price(pool.sqrt_price_q64)?;
+        }
+    }
+
+    Ok(result)
+}
+
+pub fn handler(ctx: Context<SwapCl>, amount_in: u64, min_out: u64) -> Result<()> {
+    if amount_in == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pool = &ctx.accounts.pool;
+    if !pool.enabled {
+        return err!(DexError::PairDisabled);
+    }
+
+    let mint_in = ctx.accounts.user_ata_in.mint;
+    let mint_out = ctx.accounts.user_ata_out.mint;
+    let is_knite_in = mint_in == pool.mint_knite;
+    if !((is_knite_in && mint_out == pool.mint_sub) || (mint_in == pool.mint_sub && mint_out == pool.mint_knite)) {
+        return err!(DexError::TokenNotAllowed);
+    }
+
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let sub = TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub);
+    let (side_in, side_out, vault_in, vault_out) = if is_knite_in {
+        (knite, sub, ctx.accounts.vault_knite.to_account_info(), ctx.accounts.vault_sub.to_account_info())
+    } else {
+        (sub, knite, ctx.accounts.vault_sub.to_account_info(), ctx.accounts.vault_knite.to_account_info())
+    };
+
+    // protocol fee goes to the treasury ATA of the input mint
+    assert_treasury_ata(&pool.treasury, &mint_in, ctx.accounts.treasury_fee_ata.key(), side_in.token_program.key)?;
+
+    // price on what the vault actually received (Token-2022 transfer fees)
+    let user = ctx.accounts.user.to_account_info();
+    let user_ata_in = ctx.accounts.user_ata_in.to_account_info();
+    let received = side_in.transfer_measured(&user_ata_in, &vault_in, &user, &[], amount_in)?;
+
+    let result = apply_cl_swap(&mut ctx.accounts.pool, is_knite_in, received)?;
+    if result.amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    // signer seeds for pool PDA
+    let pool = &ctx.accounts.pool;
+    let bump_seed = [pool.bump];
+    let seeds: &[&[u8]] = &[
+        SEED_CL_POOL,
+        pool.treasury.as_ref(),
+        pool.mint_knite.as_ref(),
+        pool.mint_sub.as_ref(),
+        &bump_seed,
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+    let pool_info = pool.to_account_info();
+
+    // unspent input (liquidity ran out) back to the user, protocol fee to the treasury
+    let refund = received
+        .checked_sub(result.amount_in)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    if refund > 0 {
+        side_in.transfer(&vault_in, &user_ata_in, &pool_info, signer_seeds, refund)?;
+    }
+    side_in.transfer(
+        &vault_in,
+        &ctx.accounts.treasury_fee_ata.to_account_info(),
+        &pool_info,
+        signer_seeds,
+        result.protocol_fee,
+    )?;
+
+    // slippage is checked on what the user actually received
+    let amount_out = side_out.transfer_measured(
+        &vault_out,
+        &ctx.accounts.user_ata_out.to_account_info(),
+        &pool_info,
+        signer_seeds,
+        result.amount_out,
+    )?;
+    if amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    emit!(ClSwapExecuted {
+        pool: pool.key(),
+        user: user.key(),
+        mint_in,
+        mint_out,
+        amount_in: result.amount_in,
+        amount_out,
+        fee: result.fee,
+        protocol_fee: result.protocol_fee,
+        sqrt_price_q64: pool.sqrt_price_q64,
+        tick: pool.tick_current,
+    });
+
+    Ok(())
+}
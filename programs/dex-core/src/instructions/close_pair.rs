@@ -0,0 +1,134 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::{ClosePair, PairClosed};
+use crate::errors::DexError;
+use crate::math::mul_div_floor;
+use crate::state::{CLOSE_PAIR_LP_ACCOUNTS, SEED_PAIR};
+use crate::tokens::{token_balance, TokenSide};
+use crate::{assert_treasury_ata, assert_treasury_knite_ata};
+use crate::assert_treasury_signer;
+
+/// Retires a disabled pair: LP holders get their pro-rata share of both vault balances
+/// (donations included), the treasury the locked LP share plus rounding dust (everything
+/// when no LP shares are out). Vaults, the pair and its registry entry are then closed;
+/// the LP mint stays, so the pair cannot be created again.
+/// With many LP holders, have them remove_liquidity first so the rest fit in one transaction.
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, ClosePair<'info>>) -> Result<()> {
+    let pair = &ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+    if pair.enabled {
+        return err!(DexError::PairStillEnabled);
+    }
+    if pair.flash_loan_active {
+        return err!(DexError::FlashLoanActive);
+    }
+
+    assert_treasury_knite_ata(pair, ctx.accounts.treasury_knite_ata.key(), &ctx.accounts.token_program_knite.key())?;
+    assert_treasury_ata(
+        &pair.treasury,
+        &pair.mint_sub,
+        ctx.accounts.treasury_sub_ata.key(),
+        &ctx.accounts.token_program_sub.key(),
+    )?;
+
+    // signer seeds for pair PDA
+    let bump_seed = [pair.bump];
+    let seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &bump_seed,
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+    let pair_info = pair.to_account_info();
+
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let sub = TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub);
+    let vault_knite = ctx.accounts.vault_knite.to_account_info();
+    let vault_sub = ctx.accounts.vault_sub.to_account_info();
+    let balance_knite = token_balance(&vault_knite)?;
+    let balance_sub = token_balance(&vault_sub)?;
+
+    // every LP account exactly once (ascending keys rule out duplicates)
+    let rem = ctx.remaining_accounts;
+    if rem.len() % CLOSE_PAIR_LP_ACCOUNTS != 0 {
+        return err!(DexError::InvalidLpHolders);
+    }
+    let holders: Vec<_> = rem.chunks(CLOSE_PAIR_LP_ACCOUNTS).collect();
+    if holders.windows(2).any(|w| w[0][0].key() >= w[1][0].key()) {
+        return err!(DexError::InvalidLpHolders);
+    }
+
+    let supply = ctx.accounts.lp_mint.supply;
+    let total_lp = pair.total_lp(supply)?;
+    let (mut lp_counted, mut knite_to_lps, mut sub_to_lps) = (0u64, 0u64, 0u64);
+    let overflow = || error!(DexError::MathOverflow);
+    for holder in &holders {
+        let lp_account: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(&holder[0])?;
+        let dest_knite: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(&holder[1])?;
+        let dest_sub: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(&holder[2])?;
+        if lp_account.mint != pair.lp_mint
+            || dest_knite.owner != lp_account.owner
+            || dest_knite.mint != pair.mint_knite
+            || dest_sub.owner != lp_account.owner
+            || dest_sub.mint != pair.mint_sub
+        {
+            return err!(DexError::InvalidLpHolders);
+        }
+        lp_counted = lp_counted.checked_add(lp_account.amount).ok_or_else(overflow)?;
+
+        // pro-rata share, rounded down in favour of the treasury
+        let amount_knite = mul_div_floor(lp_account.amount, balance_knite, total_lp)?;
+        let amount_sub = mul_div_floor(lp_account.amount, balance_sub, total_lp)?;
+        knite.transfer(&vault_knite, &holder[1], &pair_info, signer_seeds, amount_knite)?;
+        sub.transfer(&vault_sub, &holder[2], &pair_info, signer_seeds, amount_sub)?;
+        knite_to_lps = knite_to_lps.checked_add(amount_knite).ok_or_else(overflow)?;
+        sub_to_lps = sub_to_lps.checked_add(amount_sub).ok_or_else(overflow)?;
+    }
+    if lp_counted != supply {
+        return err!(DexError::InvalidLpHolders);
+    }
+
+    // the rest to the treasury, then the emptied vaults close (rent to the treasury)
+    let knite_to_treasury = balance_knite.checked_sub(knite_to_lps).ok_or_else(overflow)?;
+    let sub_to_treasury = balance_sub.checked_sub(sub_to_lps).ok_or_else(overflow)?;
+    let treasury = ctx.accounts.treasury_signer.to_account_info();
+    knite.transfer(
+        &vault_knite,
+        &ctx.accounts.treasury_knite_ata.to_account_info(),
+        &pair_info,
+        signer_seeds,
+        knite_to_treasury,
+    )?;
+    sub.transfer(
+        &vault_sub,
+        &ctx.accounts.treasury_sub_ata.to_account_info(),
+        &pair_info,
+        signer_seeds,
+        sub_to_treasury,
+    )?;
+    // withheld Token-2022 fees would block the close
+    knite.harvest_withheld(&vault_knite)?;
+    sub.harvest_withheld(&vault_sub)?;
+    knite.close(&vault_knite, &treasury, &pair_info, signer_seeds)?;
+    sub.close(&vault_sub, &treasury, &pair_info, signer_seeds)?;
+
+    let pair_key = pair.key();
+
+    // pair account and its registry entry are closed to the treasury by the Accounts constraints
+    emit!(PairClosed {
+        pair: pair_key,
+        treasury: treasury.key(),
+        lp_mint: ctx.accounts.lp_mint.key(),
+        lp_holders: holders.len() as u16,
+        knite_to_lps,
+        sub_to_lps,
+        knite_to_treasury,
+        sub_to_treasury,
+    });
+
+    Ok(())
+}
@@ -0,0 +1,212 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::now_ts;
+
+use crate::{CloseDca, DcaClosed, DcaCycleExecuted, DcaOpened, ExecuteDcaCycle, OpenDca, SwapExecuted};
+use crate::errors::DexError;
+use crate::instructions::swap::{
+    apply_fee_split, check_swap_limits, compute_exact_in, pay_output, pull_input, record_swap, reprice_on_received,
+    SwapLegAccounts,
+};
+use crate::math::amount_at_price_q64;
+use crate::state::{MIN_DCA_INTERVAL_SECS, SEED_DCA, SEED_PAIR};
+use crate::tokens::TokenSide;
+use crate::assert_treasury_knite_ata;
+
+// DCA vaults buy the pair's sub with escrowed kNite on a fixed schedule. Anyone may run a
+// due cycle (same swap path, fees and breakers as swap_exact_in, the DCA PDA signing for
+// the escrow); a cycle whose output would fall under the price floor fails and can be
+// retried later. The owner can close the vault at any time to take back the unspent kNite.
+
+pub fn handler_open(
+    ctx: Context<OpenDca>,
+    dca_id: u64,
+    amount_total: u64,
+    amount_per_cycle: u64,
+    interval_secs: i64,
+    min_price_q64: u128,
+) -> Result<()> {
+    if amount_total == 0 || amount_per_cycle == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+    if amount_per_cycle > amount_total || interval_secs < MIN_DCA_INTERVAL_SECS || min_price_q64 == 0 {
+        return err!(DexError::InvalidDcaSchedule);
+    }
+
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+
+    // escrow what actually arrives (Token-2022 transfer fees)
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let received = knite.transfer_measured(
+        &ctx.accounts.owner_knite_ata.to_account_info(),
+        &ctx.accounts.escrow.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        &[],
+        amount_total,
+    )?;
+
+    let now = now_ts()?;
+    let dca = &mut ctx.accounts.dca;
+    dca.bump = ctx.bumps.dca;
+    dca.escrow_bump = ctx.bumps.escrow;
+    dca.owner = ctx.accounts.owner.key();
+    dca.pair = pair.key();
+    dca.dca_id = dca_id;
+    dca.escrow = ctx.accounts.escrow.key();
+    dca.amount_per_cycle = amount_per_cycle;
+    dca.interval_secs = interval_secs;
+    dca.min_price_q64 = min_price_q64;
+    // first cycle is due right away
+    dca.next_cycle_ts = now;
+    dca.cycles_executed = 0;
+    dca.total_in = 0;
+    dca.total_out = 0;
+    dca.created_at = now;
+
+    emit!(DcaOpened {
+        dca: dca.key(),
+        owner: dca.owner,
+        pair: dca.pair,
+        amount_total: received,
+        amount_per_cycle,
+        interval_secs,
+        min_price_q64,
+    });
+
+    Ok(())
+}
+
+pub fn handler_execute(ctx: Context<ExecuteDcaCycle>) -> Result<()> {
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+    assert_treasury_knite_ata(pair, ctx.accounts.treasury_knite_ata.key(), &ctx.accounts.token_program_knite.key())?;
+
+    let dca = &ctx.accounts.dca;
+    let now = now_ts()?;
+    if now < dca.next_cycle_ts {
+        return err!(DexError::DcaCycleNotDue);
+    }
+
+    // last cycle spends whatever is left
+    let amount_in = dca.amount_per_cycle.min(ctx.accounts.escrow.amount);
+    if amount_in == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+    let min_out = amount_at_price_q64(amount_in, dca.min_price_q64)?;
+
+    let reserve_knite = pair.reserve_knite;
+    let reserve_sub = pair.reserve_sub;
+    let curve = pair.curve_at(now);
+    let mut amounts = compute_exact_in(pair.swap_fee_bps, curve, true, amount_in, reserve_knite, reserve_sub)?;
+    apply_fee_split(pair, false, &mut amounts)?;
+    if amounts.amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    // signer seeds: DCA PDA (escrow authority) and pair PDA (vault authority)
+    let dca_id = dca.dca_id.to_le_bytes();
+    let dca_bump = [dca.bump];
+    let dca_seeds: &[&[u8]] = &[SEED_DCA, dca.pair.as_ref(), dca.owner.as_ref(), &dca_id, &dca_bump];
+    let dca_signer: &[&[&[u8]]] = &[dca_seeds];
+
+    let pair_bump = [pair.bump];
+    let pair_seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &pair_bump,
+    ];
+    let pair_signer: &[&[&[u8]]] = &[pair_seeds];
+
+    let accs = SwapLegAccounts {
+        knite: TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite),
+        sub: TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub),
+        user: dca.to_account_info(),
+        user_ata_in: ctx.accounts.escrow.to_account_info(),
+        user_ata_out: ctx.accounts.owner_sub_ata.to_account_info(),
+        pair: pair.to_account_info(),
+        vault_knite: ctx.accounts.vault_knite.to_account_info(),
+        vault_sub: ctx.accounts.vault_sub.to_account_info(),
+        treasury_knite_ata: ctx.accounts.treasury_knite_ata.to_account_info(),
+        referrer_knite_ata: None,
+        burn_mint: ctx.accounts.burn_mint.as_ref().map(|m| m.to_account_info()),
+    };
+
+    let received = pull_input(&accs, dca_signer, true, &amounts)?;
+    let mut amounts = reprice_on_received(pair.swap_fee_bps, curve, true, &amounts, received, reserve_knite, reserve_sub)?;
+    apply_fee_split(pair, false, &mut amounts)?;
+    check_swap_limits(pair, true, &amounts)?;
+
+    // the floor holds on what the owner actually received
+    let amount_out = pay_output(&accs, pair_signer, true, &amounts)?;
+    if amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    let pair_key = ctx.accounts.pair.key();
+    record_swap(&mut ctx.accounts.pair, pair_key, true, &amounts)?;
+
+    ctx.accounts.escrow.reload()?;
+    let remaining = ctx.accounts.escrow.amount;
+
+    let overflow = || error!(DexError::MathOverflow);
+    let dca = &mut ctx.accounts.dca;
+    dca.cycles_executed = dca.cycles_executed.checked_add(1).ok_or_else(overflow)?;
+    dca.total_in = dca.total_in.checked_add(amount_in).ok_or_else(overflow)?;
+    dca.total_out = dca.total_out.checked_add(amount_out).ok_or_else(overflow)?;
+    // next cycle counts from this execution, so a late crank never bunches cycles
+    dca.next_cycle_ts = now.checked_add(dca.interval_secs).ok_or_else(overflow)?;
+
+    emit!(SwapExecuted {
+        pair: pair_key,
+        user: dca.owner,
+        mint_in: ctx.accounts.mint_knite.key(),
+        mint_out: ctx.accounts.mint_sub.key(),
+        amount_in,
+        amount_out,
+        fee_knite: amounts.fee_knite,
+        fee_lp: amounts.fee_lp,
+        fee_treasury: amounts.fee_treasury()?,
+        fee_burn: amounts.fee_burn,
+        fee_referral: amounts.fee_referral,
+    });
+    emit!(DcaCycleExecuted {
+        dca: dca.key(),
+        owner: dca.owner,
+        pair: pair_key,
+        cycle: dca.cycles_executed,
+        amount_in,
+        amount_out,
+        remaining,
+    });
+
+    Ok(())
+}
+
+pub fn handler_close(ctx: Context<CloseDca>) -> Result<()> {
+    let dca = &ctx.accounts.dca;
+
+    let dca_id = dca.dca_id.to_le_bytes();
+    let bump_seed = [dca.bump];
+    let seeds: &[&[u8]] = &[SEED_DCA, dca.pair.as_ref(), dca.owner.as_ref(), &dca_id, &bump_seed];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let escrow = ctx.accounts.escrow.to_account_info();
+    let dca_info = dca.to_account_info();
+    let owner = ctx.accounts.owner.to_account_info();
+
+    let amount_returned = ctx.accounts.escrow.amount;
+    knite.transfer(&escrow, &ctx.accounts.owner_knite_ata.to_account_info(), &dca_info, signer_seeds, amount_returned)?;
+    knite.close_escrow(&escrow, &owner, &dca_info, signer_seeds)?;
+
+    emit!(DcaClosed { dca: dca.key(), owner: dca.owner, amount_returned });
+
+    // DCA account itself is closed to `owner` by the Accounts constraint
+    Ok(())
+}
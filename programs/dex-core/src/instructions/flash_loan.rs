@@ -0,0 +1,149 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::sysvar::instructions::{load_current_index_checked, load_instruction_at_checked};
+use anchor_lang::Discriminator;
+
+use crate::{FlashLoan, PairFlashLoan};
+use crate::errors::DexError;
+use crate::math::fee_ceil;
+use crate::state::{Pair, FLASH_REPAY_PAIR_INDEX, SEED_PAIR};
+use crate::tokens::{gross_up_transfer_fee, TokenSide};
+use crate::{assert_treasury_knite_ata, assert_treasury_sub_ata};
+
+// flash_borrow sends `amount` out of one vault and marks the loan on the Pair; it only
+// succeeds when a flash_repay for the same pair follows in the same transaction, so the
+// whole transaction reverts unless the principal (+ fee to the treasury) comes back.
+// Stored reserves are untouched: swaps in between still price off the pre-loan reserves.
+
+pub fn handler_borrow(ctx: Context<PairFlashLoan>, amount: u64) -> Result<()> {
+    if amount == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+    if pair.flash_loan_active {
+        return err!(DexError::FlashLoanActive);
+    }
+
+    let is_knite = ctx.accounts.mint.key() == pair.mint_knite;
+    assert_fee_ata(pair, is_knite, ctx.accounts.treasury_ata.key(), &ctx.accounts.token_program.key())?;
+
+    let reserve = if is_knite { pair.reserve_knite } else { pair.reserve_sub };
+    if amount > reserve {
+        return err!(DexError::InsufficientLiquidity);
+    }
+
+    assert_repay_follows(&ctx.accounts.instructions, pair.key())?;
+
+    // signer seeds for pair PDA
+    let bump_seed = [pair.bump];
+    let seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &bump_seed,
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    // vault -> borrower (pair signs)
+    let side = TokenSide::new(ctx.accounts.token_program.to_account_info(), &ctx.accounts.mint);
+    side.transfer(
+        &ctx.accounts.vault.to_account_info(),
+        &ctx.accounts.borrower_ata.to_account_info(),
+        &ctx.accounts.pair.to_account_info(),
+        signer_seeds,
+        amount,
+    )?;
+
+    let pair = &mut ctx.accounts.pair;
+    pair.flash_loan_active = true;
+    pair.flash_loan_is_knite = is_knite;
+    pair.flash_loan_amount = amount;
+
+    Ok(())
+}
+
+pub fn handler_repay(ctx: Context<PairFlashLoan>) -> Result<()> {
+    let pair = &ctx.accounts.pair;
+    if !pair.flash_loan_active {
+        return err!(DexError::NoFlashLoan);
+    }
+
+    let is_knite = ctx.accounts.mint.key() == pair.mint_knite;
+    if is_knite != pair.flash_loan_is_knite {
+        return err!(DexError::MintMismatch);
+    }
+    assert_fee_ata(pair, is_knite, ctx.accounts.treasury_ata.key(), &ctx.accounts.token_program.key())?;
+
+    let amount = pair.flash_loan_amount;
+    let fee = fee_ceil(amount, pair.flash_fee_bps)?;
+
+    let side = TokenSide::new(ctx.accounts.token_program.to_account_info(), &ctx.accounts.mint);
+    let borrower_ata = ctx.accounts.borrower_ata.to_account_info();
+    let borrower = ctx.accounts.borrower.to_account_info();
+
+    // 1) principal borrower -> vault, grossed up so `amount` arrives net of transfer fees
+    let send = gross_up_transfer_fee(&ctx.accounts.mint.to_account_info(), amount)?;
+    let received = side.transfer_measured(&borrower_ata, &ctx.accounts.vault.to_account_info(), &borrower, &[], send)?;
+    if received < amount {
+        return err!(DexError::FlashLoanNotRepaid);
+    }
+
+    // 2) fee borrower -> treasury ATA
+    side.transfer(&borrower_ata, &ctx.accounts.treasury_ata.to_account_info(), &borrower, &[], fee)?;
+
+    // vault covers the stored reserves again
+    ctx.accounts.vault.reload()?;
+    let reserve = if is_knite { pair.reserve_knite } else { pair.reserve_sub };
+    if ctx.accounts.vault.amount < reserve {
+        return err!(DexError::FlashLoanNotRepaid);
+    }
+
+    let pair = &mut ctx.accounts.pair;
+    pair.flash_loan_active = false;
+    pair.flash_loan_is_knite = false;
+    pair.flash_loan_amount = 0;
+
+    emit!(FlashLoan {
+        pair: pair.key(),
+        borrower: ctx.accounts.borrower.key(),
+        mint: ctx.accounts.mint.key(),
+        amount,
+        fee,
+    });
+
+    Ok(())
+}
+
+fn assert_fee_ata(pair: &Pair, is_knite: bool, treasury_ata: Pubkey, token_program: &Pubkey) -> Result<()> {
+    if is_knite {
+        assert_treasury_knite_ata(pair, treasury_ata, token_program)
+    } else {
+        assert_treasury_sub_ata(pair, treasury_ata, token_program)
+    }
+}
+
+/// The current instruction must be a top-level call into this program (a CPI'd borrow
+/// cannot be tied to its repay), and a later top-level instruction must be
+/// `flash_repay` on the same pair.
+fn assert_repay_follows(instructions: &AccountInfo, pair: Pubkey) -> Result<()> {
+    let current = load_current_index_checked(instructions)? as usize;
+    if load_instruction_at_checked(current, instructions)?.program_id != crate::ID {
+        return err!(DexError::FlashRepayMissing);
+    }
+
+    let mut index = current + 1;
+    while let Ok(ix) = load_instruction_at_checked(index, instructions) {
+        if ix.program_id == crate::ID
+            && ix.data.starts_with(crate::instruction::FlashRepay::DISCRIMINATOR)
+            && ix.accounts.get(FLASH_REPAY_PAIR_INDEX).is_some_and(|meta| meta.pubkey == pair)
+        {
+            return Ok(());
+        }
+        index += 1;
+    }
+    err!(DexError::FlashRepayMissing)
+}
@@ -0,0 +1,180 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Burn, MintTo};
+
+use crate::{GenericLiquidity, GenericLiquidityAdded, GenericLiquidityRemoved};
+use crate::errors::DexError;
+use crate::instructions::add_liquidity::{lp_before_deposit, lp_for_received, plan_deposit};
+use crate::math::mul_div_floor;
+use crate::state::{MINIMUM_LIQUIDITY, SEED_GENERIC_PAIR};
+use crate::tokens::TokenSide;
+
+// Same LP accounting as the kNite pairs (add_liquidity / remove_liquidity), on sides a/b.
+
+pub fn handler_add(ctx: Context<GenericLiquidity>, max_a: u64, max_b: u64, min_lp: u64) -> Result<()> {
+    if max_a == 0 || max_b == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+
+    // stored reserves before
+    let reserve_a = pair.reserve_a;
+    let reserve_b = pair.reserve_b;
+
+    let (total_lp, lp_locked) = lp_before_deposit(ctx.accounts.lp_mint.supply, pair.lp_locked, reserve_a, reserve_b)?;
+    let is_first_deposit = total_lp == 0;
+    let plan = plan_deposit(is_first_deposit, max_a, max_b, reserve_a, reserve_b, total_lp)?;
+    let lp_locked = if is_first_deposit { MINIMUM_LIQUIDITY } else { lp_locked };
+
+    let side_a = TokenSide::new(ctx.accounts.token_program_a.to_account_info(), &ctx.accounts.mint_a);
+    let side_b = TokenSide::new(ctx.accounts.token_program_b.to_account_info(), &ctx.accounts.mint_b);
+    let user = ctx.accounts.user.to_account_info();
+
+    // 1) a user -> vault_a, 2) b user -> vault_b
+    let received_a = side_a.transfer_measured(
+        &ctx.accounts.user_ata_a.to_account_info(),
+        &ctx.accounts.vault_a.to_account_info(),
+        &user,
+        &[],
+        plan.amount_0,
+    )?;
+    let received_b = side_b.transfer_measured(
+        &ctx.accounts.user_ata_b.to_account_info(),
+        &ctx.accounts.vault_b.to_account_info(),
+        &user,
+        &[],
+        plan.amount_1,
+    )?;
+
+    // LP is re-derived from what the vaults actually received (Token-2022 transfer fees)
+    let lp_minted = lp_for_received(&plan, is_first_deposit, received_a, received_b, reserve_a, reserve_b, total_lp)?;
+    if lp_minted == 0 || lp_minted < min_lp {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    // 3) mint LP to user (pair signs)
+    let bump_seed = [pair.bump];
+    let seeds: &[&[u8]] = &[SEED_GENERIC_PAIR, pair.mint_a.as_ref(), pair.mint_b.as_ref(), &bump_seed];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    token_interface::mint_to(
+        CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            MintTo {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                to: ctx.accounts.user_ata_lp.to_account_info(),
+                authority: ctx.accounts.pair.to_account_info(),
+            },
+            signer_seeds,
+        ),
+        lp_minted,
+    )?;
+
+    let pair = &mut ctx.accounts.pair;
+    pair.lp_locked = lp_locked;
+    pair.reserve_a = reserve_a
+        .checked_add(received_a)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    pair.reserve_b = reserve_b
+        .checked_add(received_b)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+    emit!(GenericLiquidityAdded {
+        pair: pair.key(),
+        user: ctx.accounts.user.key(),
+        amount_a: received_a,
+        amount_b: received_b,
+        lp_minted,
+    });
+
+    Ok(())
+}
+
+pub fn handler_remove(ctx: Context<GenericLiquidity>, lp_amount: u64, min_a: u64, min_b: u64) -> Result<()> {
+    if lp_amount == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+
+    // stored reserves before
+    let reserve_a = pair.reserve_a;
+    let reserve_b = pair.reserve_b;
+
+    let total_lp = pair.total_lp(ctx.accounts.lp_mint.supply)?;
+
+    // pro-rata share, rounded down in favour of the pool
+    let amount_a = mul_div_floor(lp_amount, reserve_a, total_lp)?;
+    let amount_b = mul_div_floor(lp_amount, reserve_b, total_lp)?;
+
+    if amount_a == 0 && amount_b == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+    if amount_a < min_a || amount_b < min_b {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    // 1) burn LP from user
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                from: ctx.accounts.user_ata_lp.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        lp_amount,
+    )?;
+
+    // signer seeds for pair PDA
+    let bump_seed = [pair.bump];
+    let seeds: &[&[u8]] = &[SEED_GENERIC_PAIR, pair.mint_a.as_ref(), pair.mint_b.as_ref(), &bump_seed];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let side_a = TokenSide::new(ctx.accounts.token_program_a.to_account_info(), &ctx.accounts.mint_a);
+    let side_b = TokenSide::new(ctx.accounts.token_program_b.to_account_info(), &ctx.accounts.mint_b);
+    let pair_info = ctx.accounts.pair.to_account_info();
+
+    // 2) a vault_a -> user, 3) b vault_b -> user (pair signs)
+    let received_a = side_a.transfer_measured(
+        &ctx.accounts.vault_a.to_account_info(),
+        &ctx.accounts.user_ata_a.to_account_info(),
+        &pair_info,
+        signer_seeds,
+        amount_a,
+    )?;
+    let received_b = side_b.transfer_measured(
+        &ctx.accounts.vault_b.to_account_info(),
+        &ctx.accounts.user_ata_b.to_account_info(),
+        &pair_info,
+        signer_seeds,
+        amount_b,
+    )?;
+
+    // slippage on what the user actually received (Token-2022 transfer fees)
+    if received_a < min_a || received_b < min_b {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    let pair = &mut ctx.accounts.pair;
+    pair.reserve_a = reserve_a
+        .checked_sub(amount_a)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    pair.reserve_b = reserve_b
+        .checked_sub(amount_b)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+    emit!(GenericLiquidityRemoved {
+        pair: pair.key(),
+        user: ctx.accounts.user.key(),
+        amount_a: received_a,
+        amount_b: received_b,
+        lp_burned: lp_amount,
+    });
+
+    Ok(())
+}
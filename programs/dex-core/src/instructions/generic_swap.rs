@@ -0,0 +1,116 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+
+use crate::{GenericSwapExecuted, SwapGeneric};
+use crate::curve::Curve;
+use crate::errors::DexError;
+use crate::instructions::swap::{compute_exact_in, pay_output, pull_input, reprice_on_received, SwapLegAccounts};
+use crate::state::SEED_GENERIC_PAIR;
+use crate::tokens::TokenSide;
+
+/// Exact-in swap on a generic pair (constant product). The shared swap-leg helpers take
+/// the fee on their "kNite" side, so that side is mapped to the pair's fee mint:
+/// - fee mint = input: fee from INPUT (user -> treasury)
+/// - fee mint = output: fee from OUTPUT (vault -> treasury)
+pub fn handler(ctx: Context<SwapGeneric>, amount_in: u64, min_out: u64) -> Result<()> {
+    if amount_in == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+
+    // direction from the input account; output must be the other mint
+    let mint_in = ctx.accounts.user_ata_in.mint;
+    let a_to_b = if mint_in == pair.mint_a {
+        true
+    } else if mint_in == pair.mint_b {
+        false
+    } else {
+        return err!(DexError::TokenNotAllowed);
+    };
+    let mint_out = if a_to_b { pair.mint_b } else { pair.mint_a };
+    if ctx.accounts.user_ata_out.mint != mint_out {
+        return err!(DexError::MintMismatch);
+    }
+
+    let side_a = TokenSide::new(ctx.accounts.token_program_a.to_account_info(), &ctx.accounts.mint_a);
+    let side_b = TokenSide::new(ctx.accounts.token_program_b.to_account_info(), &ctx.accounts.mint_b);
+    let vault_a = ctx.accounts.vault_a.to_account_info();
+    let vault_b = ctx.accounts.vault_b.to_account_info();
+
+    // fee side ("kNite" side of the helpers) vs the other side
+    let fee_mint = pair.fee_mint(mint_in);
+    let fee_on_input = fee_mint == mint_in;
+    let fee_is_a = fee_mint == pair.mint_a;
+    let (fee_side, other_side, fee_vault, other_vault, reserve_fee, reserve_other) = if fee_is_a {
+        (side_a, side_b, vault_a, vault_b, pair.reserve_a, pair.reserve_b)
+    } else {
+        (side_b, side_a, vault_b, vault_a, pair.reserve_b, pair.reserve_a)
+    };
+
+    // validate treasury ATA of the fee mint
+    let expected =
+        get_associated_token_address_with_program_id(&pair.treasury, &fee_mint, fee_side.token_program.key);
+    if expected != ctx.accounts.treasury_fee_ata.key() {
+        return err!(DexError::InvalidTreasuryAta);
+    }
+
+    let curve = Curve::ConstantProduct;
+    let amounts = compute_exact_in(pair.swap_fee_bps, curve, fee_on_input, amount_in, reserve_fee, reserve_other)?;
+
+    // signer seeds for pair PDA
+    let bump_seed = [pair.bump];
+    let seeds: &[&[u8]] = &[SEED_GENERIC_PAIR, pair.mint_a.as_ref(), pair.mint_b.as_ref(), &bump_seed];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let accs = SwapLegAccounts {
+        knite: fee_side,
+        sub: other_side,
+        user: ctx.accounts.user.to_account_info(),
+        user_ata_in: ctx.accounts.user_ata_in.to_account_info(),
+        user_ata_out: ctx.accounts.user_ata_out.to_account_info(),
+        pair: ctx.accounts.pair.to_account_info(),
+        vault_knite: fee_vault,
+        vault_sub: other_vault,
+        treasury_knite_ata: ctx.accounts.treasury_fee_ata.to_account_info(),
+        referrer_knite_ata: None,
+        burn_mint: None,
+    };
+
+    // price on what the vault actually received (Token-2022 transfer fees)
+    let received = pull_input(&accs, &[], fee_on_input, &amounts)?;
+    let amounts =
+        reprice_on_received(pair.swap_fee_bps, curve, fee_on_input, &amounts, received, reserve_fee, reserve_other)?;
+    if amounts.amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    let amount_out = pay_output(&accs, signer_seeds, fee_on_input, &amounts)?;
+    if amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    let (reserve_fee, reserve_other) = amounts.reserves_after(fee_on_input, reserve_fee, reserve_other)?;
+    let pair = &mut ctx.accounts.pair;
+    (pair.reserve_a, pair.reserve_b) = if fee_is_a {
+        (reserve_fee, reserve_other)
+    } else {
+        (reserve_other, reserve_fee)
+    };
+
+    emit!(GenericSwapExecuted {
+        pair: pair.key(),
+        user: ctx.accounts.user.key(),
+        mint_in,
+        mint_out,
+        amount_in,
+        amount_out,
+        fee_mint,
+        fee: amounts.fee_knite,
+    });
+
+    Ok(())
+}
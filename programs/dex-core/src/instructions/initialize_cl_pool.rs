@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::now_ts;
+
+use crate::{ClPoolInitialized, InitializeClPool};
+use crate::clmm::{tick_at_sqrt_price, MAX_TICK};
+use crate::errors::DexError;
+use crate::state::{PairKind, PairRegistryEntry, MAX_SWAP_FEE_BPS};
+use crate::tokens::validate_mint_extensions;
+
+/// Starts the pool at `sqrt_price_q64` (sqrt(sub per kNite), Q64.64) with no liquidity;
+/// positions must sit on multiples of `tick_spacing` and hold 0 or at least
+/// `min_position_liquidity`, so each of the MAX_CL_TICKS tick slots costs real liquidity.
+pub fn handler(
+    ctx: Context<InitializeClPool>,
+    treasury: Pubkey,
+    sqrt_price_q64: u128,
+    tick_spacing: u16,
+    swap_fee_bps: u16,
+    min_position_liquidity: u128,
+) -> Result<()> {
+    // treasury must match signer
+    if ctx.accounts.treasury_signer.key() != treasury {
+        return err!(DexError::Unauthorized);
+    }
+
+    // swaps require a non-zero fee (same bounds as set_swap_fee_bps)
+    if swap_fee_bps == 0 || swap_fee_bps > MAX_SWAP_FEE_BPS {
+        return err!(DexError::InvalidFee);
+    }
+    if tick_spacing == 0 || tick_spacing as i32 > MAX_TICK {
+        return err!(DexError::InvalidTick);
+    }
+    if min_position_liquidity == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+    // also range-checks the price
+    let tick = tick_at_sqrt_price(sqrt_price_q64)?;
+
+    // prevent same mint
+    if ctx.accounts.mint_knite.key() == ctx.accounts.mint_sub.key() {
+        return err!(DexError::MintMismatch);
+    }
+
+    // Token-2022 mints: reject extensions the vaults cannot hold safely
+    validate_mint_extensions(&ctx.accounts.mint_knite.to_account_info())?;
+    validate_mint_extensions(&ctx.accounts.mint_sub.to_account_info())?;
+
+    let pool = &mut ctx.accounts.pool;
+
+    pool.bump = ctx.bumps.pool;
+    pool.vault_knite_bump = ctx.bumps.vault_knite;
+    pool.vault_sub_bump = ctx.bumps.vault_sub;
+
+    pool.treasury = treasury;
+
+    pool.mint_knite = ctx.accounts.mint_knite.key();
+    pool.mint_sub = ctx.accounts.mint_sub.key();
+
+    pool.vault_knite = ctx.accounts.vault_knite.key();
+    pool.vault_sub = ctx.accounts.vault_sub.key();
+
+    pool.tick_spacing = tick_spacing;
+    pool.swap_fee_bps = swap_fee_bps;
+    pool.enabled = true;
+    pool.min_position_liquidity = min_position_liquidity;
+
+    pool.sqrt_price_q64 = sqrt_price_q64;
+    pool.tick_current = tick;
+    pool.liquidity = 0;
+    pool.fee_growth_global_knite_q64 = 0;
+    pool.fee_growth_global_sub_q64 = 0;
+    pool.ticks = Vec::new();
+
+    let now = now_ts()?;
+    pool.created_at = now;
+
+    // vault authority must be pool PDA (TokenAccount.owner == authority pubkey)
+    if ctx.accounts.vault_knite.owner != pool.key() || ctx.accounts.vault_sub.owner != pool.key() {
+        return err!(DexError::Unauthorized);
+    }
+
+    let index = ctx.accounts.registry.claim_index()?;
+    **ctx.accounts.registry_entry = PairRegistryEntry {
+        bump: ctx.bumps.registry_entry,
+        index,
+        pair: pool.key(),
+        kind: PairKind::Concentrated,
+        mint_0: pool.mint_knite,
+        mint_1: pool.mint_sub,
+        fee_bps: pool.swap_fee_bps,
+        created_at: now,
+    };
+
+    emit!(ClPoolInitialized {
+        pool: pool.key(),
+        treasury: pool.treasury,
+        mint_knite: pool.mint_knite,
+        mint_sub: pool.mint_sub,
+        vault_knite: pool.vault_knite,
+        vault_sub: pool.vault_sub,
+        tick_spacing,
+        fee_bps: swap_fee_bps,
+        sqrt_price_q64,
+        tick,
+        min_position_liquidity,
+    });
+
+    Ok(())
+}
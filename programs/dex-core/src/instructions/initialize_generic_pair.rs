@@ -0,0 +1,81 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::now_ts;
+
+use crate::{GenericPairInitialized, InitializeGenericPair};
+use crate::errors::DexError;
+use crate::state::{FeeMode, PairKind, PairRegistryEntry, MAX_SWAP_FEE_BPS};
+use crate::tokens::validate_mint_extensions;
+
+pub fn handler(ctx: Context<InitializeGenericPair>, swap_fee_bps: u16, fee_mode: FeeMode) -> Result<()> {
+    // swaps require a non-zero fee (same bounds as set_swap_fee_bps)
+    if swap_fee_bps == 0 || swap_fee_bps > MAX_SWAP_FEE_BPS {
+        return err!(DexError::InvalidFee);
+    }
+
+    // canonical order (also constrained in Accounts): one pair per mint set
+    if ctx.accounts.mint_a.key() >= ctx.accounts.mint_b.key() {
+        return err!(DexError::MintsNotCanonical);
+    }
+
+    // Token-2022 mints: reject extensions the vaults cannot hold safely
+    validate_mint_extensions(&ctx.accounts.mint_a.to_account_info())?;
+    validate_mint_extensions(&ctx.accounts.mint_b.to_account_info())?;
+
+    let pair = &mut ctx.accounts.pair;
+
+    pair.bump = ctx.bumps.pair;
+    pair.vault_a_bump = ctx.bumps.vault_a;
+    pair.vault_b_bump = ctx.bumps.vault_b;
+    pair.lp_mint_bump = ctx.bumps.lp_mint;
+
+    pair.treasury = ctx.accounts.registry.authority;
+
+    pair.mint_a = ctx.accounts.mint_a.key();
+    pair.mint_b = ctx.accounts.mint_b.key();
+
+    pair.vault_a = ctx.accounts.vault_a.key();
+    pair.vault_b = ctx.accounts.vault_b.key();
+
+    pair.lp_mint = ctx.accounts.lp_mint.key();
+    pair.lp_locked = 0;
+
+    pair.swap_fee_bps = swap_fee_bps;
+    pair.fee_mode = fee_mode;
+    pair.enabled = true;
+
+    pair.reserve_a = 0;
+    pair.reserve_b = 0;
+    pair.created_at = now_ts()?;
+
+    // vault authority must be pair PDA (TokenAccount.owner == authority pubkey)
+    if ctx.accounts.vault_a.owner != pair.key() || ctx.accounts.vault_b.owner != pair.key() {
+        return err!(DexError::Unauthorized);
+    }
+
+    let pair_key = pair.key();
+    let index = ctx.accounts.registry.claim_index()?;
+    **ctx.accounts.registry_entry = PairRegistryEntry {
+        bump: ctx.bumps.registry_entry,
+        index,
+        pair: pair_key,
+        kind: PairKind::Generic,
+        mint_0: pair.mint_a,
+        mint_1: pair.mint_b,
+        fee_bps: pair.swap_fee_bps,
+        created_at: pair.created_at,
+    };
+
+    emit!(GenericPairInitialized {
+        pair: pair_key,
+        treasury: pair.treasury,
+        mint_a: pair.mint_a,
+        mint_b: pair.mint_b,
+        vault_a: pair.vault_a,
+        vault_b: pair.vault_b,
+        lp_mint: pair.lp_mint,
+        fee_bps: pair.swap_fee_bps,
+        fee_mode: pair.fee_mode,
+    });
+
+    Ok(())
+}
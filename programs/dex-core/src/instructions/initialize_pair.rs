@@ -1,25 +1,45 @@
 use anchor_lang::prelude::*;
 use common_contracts::constants::SWAP_FEE_BPS;
+use common_contracts::utils::now_ts;
 
 use crate::{InitializePair, PairInitialized};
 use crate::errors::DexError;
+use crate::state::{
+    CurveType, FeeSplit, PairKind, PairRegistryEntry, PairStats, DEFAULT_FLASH_FEE_BPS, DEFAULT_REFERRAL_SHARE_BPS,
+    MAX_AMP, MIN_AMP, PAIR_RESERVED_BYTES, PAIR_VERSION,
+};
+use crate::tokens::validate_mint_extensions;
 
-pub fn handler(ctx: Context<InitializePair>, treasury: Pubkey) -> Result<()> {
+/// `amp` is the StableSwap amplification coefficient; must be 0 for constant product.
+pub fn handler(ctx: Context<InitializePair>, treasury: Pubkey, curve_type: CurveType, amp: u64) -> Result<()> {
     // treasury must match signer
     if ctx.accounts.treasury_signer.key() != treasury {
         return err!(DexError::Unauthorized);
     }
 
+    let amp_ok = match curve_type {
+        CurveType::ConstantProduct => amp == 0,
+        CurveType::StableSwap => (MIN_AMP..=MAX_AMP).contains(&amp),
+    };
+    if !amp_ok {
+        return err!(DexError::InvalidAmp);
+    }
+
     // prevent same mint
     if ctx.accounts.mint_knite.key() == ctx.accounts.mint_sub.key() {
         return err!(DexError::MintMismatch);
     }
 
+    // Token-2022 mints: reject extensions the vaults cannot hold safely
+    validate_mint_extensions(&ctx.accounts.mint_knite.to_account_info())?;
+    validate_mint_extensions(&ctx.accounts.mint_sub.to_account_info())?;
+
     let pair = &mut ctx.accounts.pair;
 
     pair.bump = ctx.bumps.pair;
     pair.vault_knite_bump = ctx.bumps.vault_knite;
     pair.vault_sub_bump = ctx.bumps.vault_sub;
+    pair.lp_mint_bump = ctx.bumps.lp_mint;
 
     pair.treasury = treasury;
 
@@ -29,14 +49,65 @@ pub fn handler(ctx: Context<InitializePair>, treasury: Pubkey) -> Result<()> {
     pair.vault_knite = ctx.accounts.vault_knite.key();
     pair.vault_sub = ctx.accounts.vault_sub.key();
 
+    pair.lp_mint = ctx.accounts.lp_mint.key();
+    pair.lp_locked = 0;
+
     pair.swap_fee_bps = SWAP_FEE_BPS; // 30 bps
     pair.enabled = true;
 
+    let now = now_ts()?;
+    pair.init_oracle(now);
+
+    // circuit breaker off until the treasury configures it
+    pair.max_price_impact_bps = 0;
+    pair.max_slot_volume_knite = 0;
+    pair.volume_slot = 0;
+    pair.slot_volume_knite = 0;
+    pair.referral_share_bps = DEFAULT_REFERRAL_SHARE_BPS;
+    pair.reserve_knite = 0;
+    pair.reserve_sub = 0;
+    pair.flash_fee_bps = DEFAULT_FLASH_FEE_BPS;
+    pair.flash_loan_active = false;
+    pair.flash_loan_is_knite = false;
+    pair.flash_loan_amount = 0;
+
+    // no ramp in progress: amp_at() == amp_target from the start
+    pair.curve_type = curve_type;
+    pair.amp_initial = amp;
+    pair.amp_target = amp;
+    pair.amp_ramp_start_ts = now;
+    pair.amp_ramp_end_ts = now;
+
+    pair.fee_split = FeeSplit::TREASURY_ONLY;
+    pair.batch_slots = 0;
+
+    // no launch phase until set_launch_phase
+    pair.trading_starts_at = 0;
+    pair.launch_ends_at = 0;
+    pair.launch_max_buy_per_slot = 0;
+    pair.launch_cooldown_secs = 0;
+
+    pair.version = PAIR_VERSION;
+    pair.stats = PairStats::default();
+    pair.reserved = [0; PAIR_RESERVED_BYTES];
+
     //  vault authority must be pair PDA (TokenAccount.owner == authority pubkey)
     if ctx.accounts.vault_knite.owner != pair.key() || ctx.accounts.vault_sub.owner != pair.key() {
         return err!(DexError::Unauthorized);
     }
 
+    let index = ctx.accounts.registry.claim_index()?;
+    **ctx.accounts.registry_entry = PairRegistryEntry {
+        bump: ctx.bumps.registry_entry,
+        index,
+        pair: pair.key(),
+        kind: PairKind::Knite,
+        mint_0: pair.mint_knite,
+        mint_1: pair.mint_sub,
+        fee_bps: pair.swap_fee_bps,
+        created_at: now,
+    };
+
     emit!(PairInitialized {
         pair: pair.key(),
         treasury: pair.treasury,
@@ -44,7 +115,10 @@ pub fn handler(ctx: Context<InitializePair>, treasury: Pubkey) -> Result<()> {
         mint_sub: pair.mint_sub,
         vault_knite: pair.vault_knite,
         vault_sub: pair.vault_sub,
+        lp_mint: pair.lp_mint,
         fee_bps: pair.swap_fee_bps,
+        curve_type: pair.curve_type,
+        amp: pair.amp_target,
     });
 
     Ok(())
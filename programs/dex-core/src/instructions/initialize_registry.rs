@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+use crate::InitializeRegistry;
+
+/// One-time setup of the pair registry: `authority` (the protocol treasury) is the only
+/// signer allowed to create generic pairs. Must exist before any pair is created; only the
+/// program upgrade authority can create it, so nobody can front-run the deployment.
+pub fn handler(ctx: Context<InitializeRegistry>, authority: Pubkey) -> Result<()> {
+    let registry = &mut ctx.accounts.registry;
+    registry.bump = ctx.bumps.registry;
+    registry.authority = authority;
+    registry.pair_count = 0;
+    Ok(())
+}
@@ -0,0 +1,66 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::now_ts;
+
+use crate::{CloseLaunchCooldown, LaunchPhaseUpdated, OpenLaunchCooldown, UpdatePairConfig};
+use crate::errors::DexError;
+use crate::state::MAX_LAUNCH_PHASE_SECS;
+use crate::assert_treasury_signer;
+
+// Launch phase of a Pair: nothing trades before `trading_starts_at`; for `launch_secs` after it
+// only swap_exact_in does, with the wallet's LaunchCooldown enforcing the per-slot buy cap and
+// the cooldown. The pair falls back to normal swaps once `launch_ends_at` passes.
+
+/// Treasury-only. Typically sent with initialize_pair, before liquidity; all zero turns it off.
+pub fn handler_set(
+    ctx: Context<UpdatePairConfig>,
+    trading_starts_at: i64,
+    launch_secs: i64,
+    max_buy_per_slot: u64,
+    cooldown_secs: u32,
+) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+
+    // per-wallet limits only exist within the launch window
+    let no_window = launch_secs == 0 && (max_buy_per_slot > 0 || cooldown_secs > 0);
+    if trading_starts_at < 0 || !(0..=MAX_LAUNCH_PHASE_SECS).contains(&launch_secs) || no_window {
+        return err!(DexError::InvalidLaunchPhase);
+    }
+
+    pair.trading_starts_at = trading_starts_at;
+    pair.launch_ends_at = if launch_secs == 0 { 0 } else { trading_starts_at + launch_secs };
+    pair.launch_max_buy_per_slot = max_buy_per_slot;
+    pair.launch_cooldown_secs = cooldown_secs;
+
+    emit!(LaunchPhaseUpdated {
+        pair: pair.key(),
+        trading_starts_at: pair.trading_starts_at,
+        launch_ends_at: pair.launch_ends_at,
+        max_buy_per_slot,
+        cooldown_secs,
+    });
+
+    Ok(())
+}
+
+pub fn handler_open(ctx: Context<OpenLaunchCooldown>) -> Result<()> {
+    let cooldown = &mut ctx.accounts.launch_cooldown;
+    cooldown.bump = ctx.bumps.launch_cooldown;
+    cooldown.pair = ctx.accounts.pair.key();
+    cooldown.owner = ctx.accounts.user.key();
+    cooldown.last_trade_ts = 0;
+    cooldown.last_slot = 0;
+    cooldown.bought_in_slot = 0;
+
+    Ok(())
+}
+
+pub fn handler_close(ctx: Context<CloseLaunchCooldown>) -> Result<()> {
+    if now_ts()? < ctx.accounts.pair.launch_ends_at {
+        return err!(DexError::LaunchPhaseActive);
+    }
+
+    // cooldown account itself is closed to `owner` by the Accounts constraint
+    Ok(())
+}
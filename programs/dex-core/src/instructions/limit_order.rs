@@ -0,0 +1,260 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+use common_contracts::constants::BPS_DENOMINATOR;
+use common_contracts::utils::now_ts;
+
+use crate::{CancelOrder, FillOrders, OrderCancelled, OrderFilled, OrderPlaced, PlaceOrder, SwapExecuted};
+use crate::errors::DexError;
+use crate::instructions::swap::{
+    apply_fee_split, check_swap_limits, compute_exact_in, pay_output, pull_input, record_swap, reprice_on_received,
+    SwapLegAccounts,
+};
+use crate::math::mul_div_floor;
+use crate::state::{Order, MAX_ORDER_FILLS, ORDER_CRANK_FEE_BPS, ORDER_FILL_ACCOUNTS, SEED_ORDER, SEED_PAIR};
+use crate::tokens::{token_balance, TokenSide};
+use crate::assert_treasury_knite_ata;
+
+// Limit orders rest next to the AMM: the input sits in an escrow owned by the order PDA
+// until a cranker fills the whole order against the pair (same swap path, fees and
+// breakers as swap_exact_in) once it yields at least `min_out`, or the owner cancels.
+// The cranker earns ORDER_CRANK_FEE_BPS of the escrowed input.
+
+pub fn handler_place(ctx: Context<PlaceOrder>, order_id: u64, amount_in: u64, min_out: u64) -> Result<()> {
+    if amount_in == 0 || min_out == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+
+    // must be exactly knite <-> sub
+    let mint_in = ctx.accounts.mint_in.key();
+    let mint_out = ctx.accounts.mint_out.key();
+    if !((mint_in == pair.mint_knite && mint_out == pair.mint_sub)
+        || (mint_in == pair.mint_sub && mint_out == pair.mint_knite))
+    {
+        return err!(DexError::TokenNotAllowed);
+    }
+
+    // escrow what actually arrives (Token-2022 transfer fees)
+    let side_in = TokenSide::new(ctx.accounts.token_program_in.to_account_info(), &ctx.accounts.mint_in);
+    let received = side_in.transfer_measured(
+        &ctx.accounts.owner_ata_in.to_account_info(),
+        &ctx.accounts.escrow.to_account_info(),
+        &ctx.accounts.owner.to_account_info(),
+        &[],
+        amount_in,
+    )?;
+
+    let order = &mut ctx.accounts.order;
+    order.bump = ctx.bumps.order;
+    order.escrow_bump = ctx.bumps.escrow;
+    order.owner = ctx.accounts.owner.key();
+    order.pair = pair.key();
+    order.order_id = order_id;
+    order.mint_in = mint_in;
+    order.mint_out = mint_out;
+    order.escrow = ctx.accounts.escrow.key();
+    order.amount_in = received;
+    order.min_out = min_out;
+    order.created_at = now_ts()?;
+
+    emit!(OrderPlaced {
+        order: order.key(),
+        owner: order.owner,
+        pair: order.pair,
+        mint_in,
+        mint_out,
+        amount_in: received,
+        min_out,
+    });
+
+    Ok(())
+}
+
+pub fn handler_cancel(ctx: Context<CancelOrder>) -> Result<()> {
+    let order = &ctx.accounts.order;
+
+    let order_id = order.order_id.to_le_bytes();
+    let bump_seed = [order.bump];
+    let seeds: &[&[u8]] = &[SEED_ORDER, order.pair.as_ref(), order.owner.as_ref(), &order_id, &bump_seed];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let side_in = TokenSide::new(ctx.accounts.token_program_in.to_account_info(), &ctx.accounts.mint_in);
+    let escrow = ctx.accounts.escrow.to_account_info();
+    let order_info = order.to_account_info();
+    let owner = ctx.accounts.owner.to_account_info();
+
+    // whole escrow balance back (includes anything sent to it after placement)
+    let amount_returned = ctx.accounts.escrow.amount;
+    side_in.transfer(&escrow, &ctx.accounts.owner_ata_in.to_account_info(), &order_info, signer_seeds, amount_returned)?;
+    side_in.close_escrow(&escrow, &owner, &order_info, signer_seeds)?;
+
+    emit!(OrderCancelled { order: order.key(), owner: order.owner, amount_returned });
+
+    // order account itself is closed to `owner` by the Accounts constraint
+    Ok(())
+}
+
+pub fn handler_fill<'info>(ctx: Context<'_, '_, 'info, 'info, FillOrders<'info>>) -> Result<()> {
+    let rem = ctx.remaining_accounts;
+    if rem.is_empty() || rem.len() % ORDER_FILL_ACCOUNTS != 0 || rem.len() / ORDER_FILL_ACCOUNTS > MAX_ORDER_FILLS {
+        return err!(DexError::InvalidOrder);
+    }
+
+    if !ctx.accounts.pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+    assert_treasury_knite_ata(
+        &ctx.accounts.pair,
+        ctx.accounts.treasury_knite_ata.key(),
+        &ctx.accounts.token_program_knite.key(),
+    )?;
+
+    // orders still below their limit are skipped; a crank that fills nothing fails
+    let mut filled = 0usize;
+    for order_accounts in rem.chunks(ORDER_FILL_ACCOUNTS) {
+        if fill_one(ctx.accounts, order_accounts)? {
+            filled += 1;
+        }
+    }
+    if filled == 0 {
+        return err!(DexError::NoFillableOrders);
+    }
+
+    Ok(())
+}
+
+/// Fills one order in full, or returns `false` when the pair price is below its limit.
+fn fill_one<'info>(accs: &mut FillOrders<'info>, order_accounts: &'info [AccountInfo<'info>]) -> Result<bool> {
+    let [order_info, escrow_info, owner_info, owner_out_info, cranker_in_info] = order_accounts else {
+        return err!(DexError::InvalidOrder);
+    };
+
+    // Order: owner + discriminator checked by try_from; it must belong to this pair
+    if !order_info.is_writable || !owner_info.is_writable {
+        return err!(DexError::InvalidOrder);
+    }
+    let order: Account<'info, Order> = Account::try_from(order_info)?;
+    let pair_key = accs.pair.key();
+    if order.pair != pair_key || escrow_info.key() != order.escrow || owner_info.key() != order.owner {
+        return err!(DexError::InvalidOrder);
+    }
+
+    let owner_out: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(owner_out_info)?;
+    if owner_out.owner != order.owner {
+        return err!(DexError::Unauthorized);
+    }
+    if owner_out.mint != order.mint_out {
+        return err!(DexError::MintMismatch);
+    }
+    let cranker_in: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(cranker_in_info)?;
+    if cranker_in.owner != accs.cranker.key() {
+        return err!(DexError::Unauthorized);
+    }
+    if cranker_in.mint != order.mint_in {
+        return err!(DexError::MintMismatch);
+    }
+
+    let is_knite_in = order.mint_in == accs.pair.mint_knite;
+
+    // the whole escrow is sold, minus the cranker's cut
+    let escrowed = token_balance(escrow_info)?;
+    let crank_fee = mul_div_floor(escrowed, ORDER_CRANK_FEE_BPS as u64, BPS_DENOMINATOR)?;
+    let swap_in = escrowed
+        .checked_sub(crank_fee)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+    let reserve_knite = accs.pair.reserve_knite;
+    let reserve_sub = accs.pair.reserve_sub;
+    let curve = accs.pair.curve_at(now_ts()?);
+    let mut amounts =
+        compute_exact_in(accs.pair.swap_fee_bps, curve, is_knite_in, swap_in, reserve_knite, reserve_sub)?;
+    apply_fee_split(&accs.pair, false, &mut amounts)?;
+    if amounts.amount_out < order.min_out {
+        return Ok(false);
+    }
+
+    // signer seeds: order PDA (escrow authority) and pair PDA (vault authority)
+    let order_id = order.order_id.to_le_bytes();
+    let order_bump = [order.bump];
+    let order_seeds: &[&[u8]] = &[SEED_ORDER, order.pair.as_ref(), order.owner.as_ref(), &order_id, &order_bump];
+    let order_signer: &[&[&[u8]]] = &[order_seeds];
+
+    let pair = &accs.pair;
+    let pair_bump = [pair.bump];
+    let pair_seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &pair_bump,
+    ];
+    let pair_signer: &[&[&[u8]]] = &[pair_seeds];
+
+    let knite = TokenSide::new(accs.token_program_knite.to_account_info(), &accs.mint_knite);
+    let sub = TokenSide::new(accs.token_program_sub.to_account_info(), &accs.mint_sub);
+    let side_in = if is_knite_in { knite.clone() } else { sub.clone() };
+
+    // cranker's cut straight from the escrow
+    side_in.transfer(escrow_info, cranker_in_info, order_info, order_signer, crank_fee)?;
+
+    let leg = SwapLegAccounts {
+        knite,
+        sub,
+        user: order_info.clone(),
+        user_ata_in: escrow_info.clone(),
+        user_ata_out: owner_out_info.clone(),
+        pair: pair.to_account_info(),
+        vault_knite: accs.vault_knite.to_account_info(),
+        vault_sub: accs.vault_sub.to_account_info(),
+        treasury_knite_ata: accs.treasury_knite_ata.to_account_info(),
+        referrer_knite_ata: None,
+        burn_mint: accs.burn_mint.as_ref().map(|m| m.to_account_info()),
+    };
+
+    let received = pull_input(&leg, order_signer, is_knite_in, &amounts)?;
+    let mut amounts =
+        reprice_on_received(pair.swap_fee_bps, curve, is_knite_in, &amounts, received, reserve_knite, reserve_sub)?;
+    apply_fee_split(pair, false, &mut amounts)?;
+    check_swap_limits(pair, is_knite_in, &amounts)?;
+
+    // the limit holds on what the owner actually received
+    let delivered = pay_output(&leg, pair_signer, is_knite_in, &amounts)?;
+    if delivered < order.min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    record_swap(&mut accs.pair, pair_key, is_knite_in, &amounts)?;
+
+    // escrow is empty now: close it and the order, rent back to the owner
+    side_in.close_escrow(escrow_info, owner_info, order_info, order_signer)?;
+
+    emit!(SwapExecuted {
+        pair: pair_key,
+        user: order.owner,
+        mint_in: order.mint_in,
+        mint_out: order.mint_out,
+        amount_in: escrowed,
+        amount_out: delivered,
+        fee_knite: amounts.fee_knite,
+        fee_lp: amounts.fee_lp,
+        fee_treasury: amounts.fee_treasury()?,
+        fee_burn: amounts.fee_burn,
+        fee_referral: amounts.fee_referral,
+    });
+    emit!(OrderFilled {
+        order: order.key(),
+        owner: order.owner,
+        pair: pair_key,
+        cranker: accs.cranker.key(),
+        amount_in: escrowed,
+        amount_out: delivered,
+        crank_fee,
+    });
+
+    order.close(owner_info.clone())?;
+    Ok(true)
+}
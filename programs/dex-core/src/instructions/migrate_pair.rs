@@ -0,0 +1,158 @@
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_pack::Pack;
+use anchor_lang::system_program::{self, Allocate, Assign, CreateAccount, Transfer};
+use anchor_lang::Discriminator;
+use anchor_spl::token::{self, spl_token, InitializeMint2};
+use common_contracts::utils::now_ts;
+
+use crate::{MigratePair, PairMigrated};
+use crate::errors::DexError;
+use crate::state::{
+    CurveType, FeeSplit, Pair, PairStats, DEFAULT_FLASH_FEE_BPS, DEFAULT_REFERRAL_SHARE_BPS, PAIR_RESERVED_BYTES,
+    PAIR_VERSION, SEED_LP_MINT,
+};
+
+/// Brings a Pair up to PAIR_VERSION one version at a time. A version 0 account (the original
+/// 174-byte layout) is first grown to `Pair::space()`; its fields keep their offsets and the
+/// new tail is zeroed, so it reads as version 0. Each step fills the fields its version
+/// introduced with safe defaults.
+pub fn handler(ctx: Context<MigratePair>) -> Result<()> {
+    let info = ctx.accounts.pair.to_account_info();
+
+    // only this program can write the Pair discriminator, so owner + discriminator is a Pair
+    if info.data_len() < 8 || info.try_borrow_data()?[..8] != *Pair::DISCRIMINATOR {
+        return err!(DexError::UnsupportedPairVersion);
+    }
+
+    let len = info.data_len();
+    if len == Pair::legacy_space() {
+        let rent = Rent::get()?.minimum_balance(Pair::space());
+        let top_up = rent.saturating_sub(info.lamports());
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    ctx.accounts.system_program.to_account_info(),
+                    Transfer { from: ctx.accounts.payer.to_account_info(), to: info.clone() },
+                ),
+                top_up,
+            )?;
+        }
+        info.resize(Pair::space())?;
+    } else if len != Pair::space() {
+        return err!(DexError::UnsupportedPairVersion);
+    }
+
+    let mut pair = Pair::try_deserialize(&mut &info.try_borrow_data()?[..])?;
+    let from_version = pair.version;
+    if from_version == PAIR_VERSION {
+        return err!(DexError::PairAlreadyMigrated);
+    }
+
+    while pair.version < PAIR_VERSION {
+        match pair.version {
+            // 0 -> 1: everything after `enabled` except stats
+            0 => upgrade_from_v0(&ctx, &mut pair)?,
+            // 1 -> 2: stats out of the (zero) reserved bytes; counting starts now
+            1 => pair.stats = PairStats::default(),
+            _ => return err!(DexError::UnsupportedPairVersion),
+        }
+        pair.version += 1;
+    }
+    // newer than this program: leave it alone
+    if pair.version != PAIR_VERSION {
+        return err!(DexError::UnsupportedPairVersion);
+    }
+
+    pair.try_serialize(&mut &mut info.try_borrow_mut_data()?[..])?;
+
+    emit!(PairMigrated { pair: info.key(), from_version, to_version: pair.version });
+
+    Ok(())
+}
+
+/// Version 0 pairs priced off their vault balances and had no LP shares: the reserves
+/// start at the vault balances (same price as before), the LP mint is created as in
+/// initialize_pair, and the rest gets initialize_pair's defaults.
+fn upgrade_from_v0(ctx: &Context<MigratePair>, pair: &mut Pair) -> Result<()> {
+    let accs = &ctx.accounts;
+    if accs.mint_knite.key() != pair.mint_knite
+        || accs.vault_knite.key() != pair.vault_knite
+        || accs.vault_sub.key() != pair.vault_sub
+    {
+        return err!(DexError::MintMismatch);
+    }
+
+    create_lp_mint(ctx)?;
+    pair.lp_mint_bump = ctx.bumps.lp_mint;
+    pair.lp_mint = accs.lp_mint.key();
+    pair.lp_locked = 0;
+
+    let now = now_ts()?;
+    pair.init_oracle(now);
+    pair.reserve_knite = accs.vault_knite.amount;
+    pair.reserve_sub = accs.vault_sub.amount;
+
+    // circuit breaker, flash loan, batch and launch state stay zeroed (off)
+    pair.referral_share_bps = DEFAULT_REFERRAL_SHARE_BPS;
+    pair.flash_fee_bps = DEFAULT_FLASH_FEE_BPS;
+    pair.curve_type = CurveType::ConstantProduct;
+    pair.amp_initial = 0;
+    pair.amp_target = 0;
+    pair.amp_ramp_start_ts = now;
+    pair.amp_ramp_end_ts = now;
+    pair.fee_split = FeeSplit::TREASURY_ONLY;
+    pair.reserved = [0; PAIR_RESERVED_BYTES];
+    Ok(())
+}
+
+/// LP mint PDA with the kNite decimals and the pair as mint authority. Lamports already
+/// sent to the address are kept (allocate + assign), so pre-funding it cannot block migration.
+fn create_lp_mint(ctx: &Context<MigratePair>) -> Result<()> {
+    let accs = &ctx.accounts;
+    let system = accs.system_program.to_account_info();
+    let payer = accs.payer.to_account_info();
+    let lp_mint = accs.lp_mint.to_account_info();
+    let pair_key = accs.pair.key();
+    let bump_seed = [ctx.bumps.lp_mint];
+    let seeds: &[&[u8]] = &[SEED_LP_MINT, pair_key.as_ref(), &bump_seed];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let space = spl_token::state::Mint::LEN;
+    let rent = Rent::get()?.minimum_balance(space);
+    let current = lp_mint.lamports();
+    if current == 0 {
+        system_program::create_account(
+            CpiContext::new_with_signer(
+                system.clone(),
+                CreateAccount { from: payer, to: lp_mint.clone() },
+                signer_seeds,
+            ),
+            rent,
+            space as u64,
+            &accs.token_program.key(),
+        )?;
+    } else {
+        let top_up = rent.saturating_sub(current);
+        if top_up > 0 {
+            system_program::transfer(
+                CpiContext::new(system.clone(), Transfer { from: payer, to: lp_mint.clone() }),
+                top_up,
+            )?;
+        }
+        system_program::allocate(
+            CpiContext::new_with_signer(system.clone(), Allocate { account_to_allocate: lp_mint.clone() }, signer_seeds),
+            space as u64,
+        )?;
+        system_program::assign(
+            CpiContext::new_with_signer(system, Assign { account_to_assign: lp_mint.clone() }, signer_seeds),
+            &accs.token_program.key(),
+        )?;
+    }
+
+    token::initialize_mint2(
+        CpiContext::new(accs.token_program.to_account_info(), InitializeMint2 { mint: lp_mint }),
+        accs.mint_knite.decimals,
+        &pair_key,
+        None,
+    )
+}
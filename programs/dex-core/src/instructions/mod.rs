@@ -1,4 +1,35 @@
 pub mod initialize_pair;
+pub mod initialize_registry;
+pub mod initialize_generic_pair;
+pub mod initialize_cl_pool;
 pub mod swap;
-
-
+pub mod swap_with_deadline;
+pub mod swap_exact_out;
+pub mod sol_swap;
+pub mod swap_route;
+pub mod generic_swap;
+pub mod cl_swap;
+pub mod referral;
+pub mod add_liquidity;
+pub mod remove_liquidity;
+pub mod generic_liquidity;
+pub mod cl_position;
+pub mod observe;
+pub mod quote;
+pub mod sync;
+pub mod skim;
+pub mod flash_loan;
+pub mod limit_order;
+pub mod dca;
+pub mod batch_auction;
+pub mod close_pair;
+pub mod migrate_pair;
+pub mod set_pair_enabled;
+pub mod set_swap_fee_bps;
+pub mod set_circuit_breaker;
+pub mod set_referral_share_bps;
+pub mod set_flash_fee_bps;
+pub mod amp_ramp;
+pub mod set_fee_split;
+pub mod set_batch_slots;
+pub mod launch_phase;
@@ -0,0 +1,39 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::now_ts;
+
+use crate::Observe;
+use crate::errors::DexError;
+use crate::state::TwapResult;
+
+/// Read-only: TWAP over at least `window_secs`, ending now.
+/// Uses the newest stored observation at or before `now - window_secs`.
+pub fn handler(ctx: Context<Observe>, window_secs: u32) -> Result<TwapResult> {
+    if window_secs == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+    let now = now_ts()?;
+
+    // counterfactual cumulatives at `now` (no state write)
+    let (knite_cum, sub_cum) = pair.cumulatives_at(pair.reserve_knite, pair.reserve_sub, now)?;
+
+    let target = now
+        .checked_sub(window_secs as i64)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    let start = pair
+        .observation_at_or_before(target)
+        .ok_or_else(|| error!(DexError::OracleWindowUnavailable))?;
+
+    let elapsed = now
+        .checked_sub(start.ts)
+        .filter(|e| *e > 0)
+        .ok_or_else(|| error!(DexError::OracleWindowUnavailable))? as u128;
+
+    Ok(TwapResult {
+        price_knite_twap_q64: knite_cum.wrapping_sub(start.price_knite_cumulative) / elapsed,
+        price_sub_twap_q64: sub_cum.wrapping_sub(start.price_sub_cumulative) / elapsed,
+        start_ts: start.ts,
+        end_ts: now,
+    })
+}
@@ -0,0 +1,126 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::now_ts;
+
+use crate::Quote;
+use crate::curve::Curve;
+use crate::errors::DexError;
+use crate::instructions::swap::{apply_fee_split, check_pool_limits, check_swap_limits, compute_exact_in, SwapAmounts};
+use crate::instructions::swap_exact_out::compute_exact_out;
+use crate::math::price_impact_bps;
+use crate::state::{Pair, SwapQuote};
+
+// Quotes make no transfers and no state writes. Anchor serializes the returned
+// SwapQuote with borsh and publishes it via `set_return_data`, so clients read it
+// from `simulateTransaction` (or `.view()`) using the same math as the swap handlers.
+// Token-2022 transfer fees are not included: swaps re-price on the amounts actually received.
+// A quote fails where the matching swap would on the pair's limits (launch phase, batch
+// mode, price impact); per-wallet launch state is not known here, see check_quote_limits.
+
+pub fn handler_exact_in(ctx: Context<Quote>, amount_in: u64) -> Result<SwapQuote> {
+    if amount_in == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+    let is_knite_in = quote_direction(&ctx)?;
+    let pair = &ctx.accounts.pair;
+
+    let reserve_knite = pair.reserve_knite;
+    let reserve_sub = pair.reserve_sub;
+    let curve = pair.curve_at(now_ts()?);
+
+    let mut amounts = compute_exact_in(pair.swap_fee_bps, curve, is_knite_in, amount_in, reserve_knite, reserve_sub)?;
+    apply_fee_split(pair, false, &mut amounts)?;
+    check_quote_limits(pair, true, is_knite_in, &amounts)?;
+    build_quote(&ctx, curve, is_knite_in, &amounts, reserve_knite, reserve_sub)
+}
+
+pub fn handler_exact_out(ctx: Context<Quote>, amount_out: u64) -> Result<SwapQuote> {
+    if amount_out == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+    let is_knite_in = quote_direction(&ctx)?;
+    let pair = &ctx.accounts.pair;
+
+    let reserve_knite = pair.reserve_knite;
+    let reserve_sub = pair.reserve_sub;
+    let curve = pair.curve_at(now_ts()?);
+
+    let mut amounts = compute_exact_out(pair.swap_fee_bps, curve, is_knite_in, amount_out, reserve_knite, reserve_sub)?;
+    apply_fee_split(pair, false, &mut amounts)?;
+    check_quote_limits(pair, false, is_knite_in, &amounts)?;
+    build_quote(&ctx, curve, is_knite_in, &amounts, reserve_knite, reserve_sub)
+}
+
+/// Same pair/mint rules as the swap handlers; returns `is_knite_in`.
+fn quote_direction(ctx: &Context<Quote>) -> Result<bool> {
+    let pair: &Pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+
+    let mint_in = ctx.accounts.mint_in.key();
+    let mint_out = ctx.accounts.mint_out.key();
+
+    let is_knite_in = mint_in == pair.mint_knite;
+    let is_knite_out = mint_out == pair.mint_knite;
+
+    if !((is_knite_in && mint_out == pair.mint_sub) || (is_knite_out && mint_in == pair.mint_sub))
+    {
+        return err!(DexError::TokenNotAllowed);
+    }
+    Ok(is_knite_in)
+}
+
+/// The limits the matching swap handler enforces. swap_exact_in still trades in the launch
+/// phase; its cooldown and per-slot total need the wallet's LaunchCooldown, so the quote
+/// only rejects a single buy above the cap.
+fn check_quote_limits(pair: &Pair, exact_in: bool, is_knite_in: bool, amounts: &SwapAmounts) -> Result<()> {
+    if !exact_in {
+        return check_swap_limits(pair, is_knite_in, amounts);
+    }
+    if pair.in_launch_phase(now_ts()?)? {
+        let sub_bought = if is_knite_in { amounts.amount_out } else { 0 };
+        if pair.launch_max_buy_per_slot > 0 && sub_bought > pair.launch_max_buy_per_slot {
+            return err!(DexError::LaunchBuyLimitExceeded);
+        }
+    }
+    check_pool_limits(pair, is_knite_in, amounts)
+}
+
+fn build_quote(
+    ctx: &Context<Quote>,
+    curve: Curve,
+    is_knite_in: bool,
+    amounts: &SwapAmounts,
+    reserve_knite: u64,
+    reserve_sub: u64,
+) -> Result<SwapQuote> {
+    let (gross_out, price_impact_bps) = if is_knite_in {
+        // fee taken from input: gross is the fee-less curve output
+        let gross_out = curve.amount_out(amounts.amount_in, reserve_knite, reserve_sub)?;
+        let impact = price_impact_bps(amounts.to_vault, reserve_knite, reserve_sub, amounts.amount_out)?;
+        (gross_out, impact)
+    } else {
+        // fee taken from output kNite: vault releases net + fee
+        let gross_out = amounts
+            .amount_out
+            .checked_add(amounts.fee_knite)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+        let impact = price_impact_bps(amounts.amount_in, reserve_sub, reserve_knite, gross_out)?;
+        (gross_out, impact)
+    };
+    let (reserve_knite_after, reserve_sub_after) =
+        amounts.reserves_after(is_knite_in, reserve_knite, reserve_sub)?;
+
+    Ok(SwapQuote {
+        mint_in: ctx.accounts.mint_in.key(),
+        mint_out: ctx.accounts.mint_out.key(),
+        amount_in: amounts.amount_in,
+        gross_out,
+        fee_knite: amounts.fee_knite,
+        net_out: amounts.amount_out,
+        price_impact_bps,
+        reserve_knite_after,
+        reserve_sub_after,
+        stats: ctx.accounts.pair.stats,
+    })
+}
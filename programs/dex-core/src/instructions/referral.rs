@@ -0,0 +1,74 @@
+use anchor_lang::prelude::*;
+use referral_program_contracts::cpi::accounts::RecordFirstSwap;
+use referral_program_contracts::{RefereeState, SEED_REFERRAL_MARKER};
+
+use crate::SwapExactIn;
+use crate::errors::DexError;
+
+/// Optional referral accounts of `swap_exact_in`:
+/// - none supplied -> Ok(None), plain swap
+/// - referee + referrer ATA -> referrer owning the ATA, if it matches the user's RefereeState
+///
+/// On the user's first referred swap the RefereeState PDA is still empty; it is recorded
+/// through referral-program `record_first_swap` (dex_program path, user pays rent). The
+/// referrer ATA owner becomes the referrer only if it co-signs that swap: the ATA alone is
+/// the swapper's choice. A referral, once recorded, cannot be changed.
+pub fn resolve_referrer(accs: &SwapExactIn<'_>) -> Result<Option<Pubkey>> {
+    let (referee, referrer_knite_ata) = match (&accs.referee, &accs.referrer_knite_ata) {
+        (None, None) => return Ok(None),
+        (Some(referee), Some(ata)) => (referee, ata),
+        _ => return err!(DexError::InvalidReferral),
+    };
+
+    let user = accs.user.key();
+    let (expected_referee, _) =
+        Pubkey::find_program_address(&[SEED_REFERRAL_MARKER, user.as_ref()], &referral_program_contracts::ID);
+    if referee.key() != expected_referee {
+        return err!(DexError::InvalidReferral);
+    }
+
+    if referee.data_is_empty() {
+        let Some(referrer) = &accs.referrer else {
+            return err!(DexError::ReferrerNotSigner);
+        };
+        if referrer.key() != referrer_knite_ata.owner {
+            return err!(DexError::ReferrerNotSigner);
+        }
+        let (Some(config), Some(program), Some(dex_program), Some(system_program)) = (
+            &accs.referral_config,
+            &accs.referral_program,
+            &accs.dex_program,
+            &accs.system_program,
+        ) else {
+            return err!(DexError::InvalidReferral);
+        };
+
+        referral_program_contracts::cpi::record_first_swap(
+            CpiContext::new(
+                program.to_account_info(),
+                RecordFirstSwap {
+                    user: accs.user.to_account_info(),
+                    referee: referee.to_account_info(),
+                    payer: accs.user.to_account_info(),
+                    dex_program: dex_program.to_account_info(),
+                    treasury_signer: None,
+                    config: config.to_account_info(),
+                    system_program: system_program.to_account_info(),
+                },
+            ),
+            referrer.key(),
+            accs.pair.key(),
+        )?;
+    }
+
+    // owner + discriminator (data refreshed by the CPI above)
+    if *referee.owner != referral_program_contracts::ID {
+        return err!(DexError::InvalidReferral);
+    }
+    let state = RefereeState::try_deserialize(&mut &referee.try_borrow_data()?[..])?;
+    if state.user != user || state.referrer != referrer_knite_ata.owner {
+        return err!(DexError::InvalidReferral);
+    }
+
+    Ok(Some(state.referrer))
+}
@@ -0,0 +1,104 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Burn};
+use common_contracts::utils::now_ts;
+
+use crate::{LiquidityRemoved, RemoveLiquidity};
+use crate::errors::DexError;
+use crate::math::mul_div_floor;
+use crate::state::SEED_PAIR;
+use crate::tokens::TokenSide;
+
+pub fn handler(ctx: Context<RemoveLiquidity>, lp_amount: u64, min_knite: u64, min_sub: u64) -> Result<()> {
+    if lp_amount == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+
+    // stored reserves before
+    let reserve_knite = pair.reserve_knite;
+    let reserve_sub = pair.reserve_sub;
+
+    let total_lp = pair.total_lp(ctx.accounts.lp_mint.supply)?;
+
+    // pro-rata share, rounded down in favour of the pool
+    let amount_knite = mul_div_floor(lp_amount, reserve_knite, total_lp)?;
+    let amount_sub = mul_div_floor(lp_amount, reserve_sub, total_lp)?;
+
+    if amount_knite == 0 && amount_sub == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+    if amount_knite < min_knite || amount_sub < min_sub {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    // 1) burn LP from user
+    token_interface::burn(
+        CpiContext::new(
+            ctx.accounts.token_program.to_account_info(),
+            Burn {
+                mint: ctx.accounts.lp_mint.to_account_info(),
+                from: ctx.accounts.user_ata_lp.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+            },
+        ),
+        lp_amount,
+    )?;
+
+    // signer seeds for pair PDA
+    let bump_seed = [pair.bump];
+    let seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &bump_seed,
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let sub = TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub);
+    let pair_info = ctx.accounts.pair.to_account_info();
+
+    // 2) kNite vault_knite -> user (pair signs)
+    let received_knite = knite.transfer_measured(
+        &ctx.accounts.vault_knite.to_account_info(),
+        &ctx.accounts.user_ata_knite.to_account_info(),
+        &pair_info,
+        signer_seeds,
+        amount_knite,
+    )?;
+
+    // 3) sub vault_sub -> user (pair signs)
+    let received_sub = sub.transfer_measured(
+        &ctx.accounts.vault_sub.to_account_info(),
+        &ctx.accounts.user_ata_sub.to_account_info(),
+        &pair_info,
+        signer_seeds,
+        amount_sub,
+    )?;
+
+    // slippage on what the user actually received (Token-2022 transfer fees)
+    if received_knite < min_knite || received_sub < min_sub {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    let pair = &mut ctx.accounts.pair;
+    pair.update_oracle(reserve_knite, reserve_sub, now_ts()?)?;
+    pair.reserve_knite = reserve_knite
+        .checked_sub(amount_knite)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    pair.reserve_sub = reserve_sub
+        .checked_sub(amount_sub)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+    emit!(LiquidityRemoved {
+        pair: pair.key(),
+        user: ctx.accounts.user.key(),
+        amount_knite: received_knite,
+        amount_sub: received_sub,
+        lp_burned: lp_amount,
+    });
+
+    Ok(())
+}
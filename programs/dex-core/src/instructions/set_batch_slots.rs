@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::{PairConfigUpdated, UpdatePairConfig};
+use crate::errors::DexError;
+use crate::state::MAX_BATCH_SLOTS;
+use crate::assert_treasury_signer;
+
+/// 0 turns batch mode off; batches already open can still settle or be withdrawn.
+pub fn handler(ctx: Context<UpdatePairConfig>, batch_slots: u64) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+
+    if batch_slots > MAX_BATCH_SLOTS {
+        return err!(DexError::InvalidBatch);
+    }
+
+    pair.batch_slots = batch_slots;
+
+    emit!(PairConfigUpdated::from_pair(pair));
+
+    Ok(())
+}
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+use common_contracts::constants::BPS_DENOMINATOR;
+
+use crate::{PairConfigUpdated, UpdatePairConfig};
+use crate::errors::DexError;
+use crate::assert_treasury_signer;
+
+/// 0 disables either limit.
+pub fn handler(
+    ctx: Context<UpdatePairConfig>,
+    max_price_impact_bps: u16,
+    max_slot_volume_knite: u64,
+) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+
+    if max_price_impact_bps as u64 > BPS_DENOMINATOR {
+        return err!(DexError::InvalidAmount);
+    }
+
+    pair.max_price_impact_bps = max_price_impact_bps;
+    pair.max_slot_volume_knite = max_slot_volume_knite;
+
+    emit!(PairConfigUpdated::from_pair(pair));
+
+    Ok(())
+}
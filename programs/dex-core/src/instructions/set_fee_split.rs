@@ -0,0 +1,18 @@
+use anchor_lang::prelude::*;
+
+use crate::{PairConfigUpdated, UpdatePairConfig};
+use crate::state::FeeSplit;
+use crate::assert_treasury_signer;
+
+pub fn handler(ctx: Context<UpdatePairConfig>, fee_split: FeeSplit) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+
+    fee_split.validate()?;
+    pair.fee_split = fee_split;
+
+    emit!(PairConfigUpdated::from_pair(pair));
+
+    Ok(())
+}
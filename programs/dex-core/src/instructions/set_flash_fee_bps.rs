@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::{PairConfigUpdated, UpdatePairConfig};
+use crate::errors::DexError;
+use crate::state::MAX_FLASH_FEE_BPS;
+use crate::assert_treasury_signer;
+
+/// 0 makes flash loans free (principal must still come back).
+pub fn handler(ctx: Context<UpdatePairConfig>, flash_fee_bps: u16) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+
+    if flash_fee_bps > MAX_FLASH_FEE_BPS {
+        return err!(DexError::InvalidFee);
+    }
+
+    pair.flash_fee_bps = flash_fee_bps;
+
+    emit!(PairConfigUpdated::from_pair(pair));
+
+    Ok(())
+}
@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+use crate::{PairConfigUpdated, UpdatePairConfig};
+use crate::assert_treasury_signer;
+
+pub fn handler(ctx: Context<UpdatePairConfig>, enabled: bool) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+
+    pair.enabled = enabled;
+
+    emit!(PairConfigUpdated::from_pair(pair));
+
+    Ok(())
+}
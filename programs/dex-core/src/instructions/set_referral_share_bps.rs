@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::{PairConfigUpdated, UpdatePairConfig};
+use crate::errors::DexError;
+use crate::state::MAX_REFERRAL_SHARE_BPS;
+use crate::assert_treasury_signer;
+
+/// 0 turns referral payouts off for this pair.
+pub fn handler(ctx: Context<UpdatePairConfig>, referral_share_bps: u16) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+
+    if referral_share_bps > MAX_REFERRAL_SHARE_BPS {
+        return err!(DexError::InvalidFee);
+    }
+
+    pair.referral_share_bps = referral_share_bps;
+
+    emit!(PairConfigUpdated::from_pair(pair));
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+use crate::{PairConfigUpdated, UpdatePairConfig};
+use crate::errors::DexError;
+use crate::state::MAX_SWAP_FEE_BPS;
+use crate::assert_treasury_signer;
+
+pub fn handler(ctx: Context<UpdatePairConfig>, swap_fee_bps: u16) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+
+    // swaps require a non-zero kNite fee, so 0 would brick the pair
+    if swap_fee_bps == 0 || swap_fee_bps > MAX_SWAP_FEE_BPS {
+        return err!(DexError::InvalidFee);
+    }
+
+    pair.swap_fee_bps = swap_fee_bps;
+
+    emit!(PairConfigUpdated::from_pair(pair));
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+use anchor_lang::prelude::*;
+
+use crate::{Skim, Skimmed};
+use crate::errors::DexError;
+use crate::state::SEED_PAIR;
+use crate::tokens::TokenSide;
+use crate::{assert_treasury_knite_ata, assert_treasury_sub_ata};
+
+/// Sends vault balance above the stored reserves to the treasury ATAs.
+pub fn handler(ctx: Context<Skim>) -> Result<()> {
+    let pair = &ctx.accounts.pair;
+    if pair.flash_loan_active {
+        return err!(DexError::FlashLoanActive);
+    }
+
+    assert_treasury_knite_ata(pair, ctx.accounts.treasury_knite_ata.key(), &ctx.accounts.token_program_knite.key())?;
+    assert_treasury_sub_ata(pair, ctx.accounts.treasury_sub_ata.key(), &ctx.accounts.token_program_sub.key())?;
+
+    let amount_knite = ctx.accounts.vault_knite.amount.saturating_sub(pair.reserve_knite);
+    let amount_sub = ctx.accounts.vault_sub.amount.saturating_sub(pair.reserve_sub);
+    if amount_knite == 0 && amount_sub == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    // signer seeds for pair PDA
+    let bump_seed = [pair.bump];
+    let seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &bump_seed,
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let knite = TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite);
+    let sub = TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub);
+    let pair_info = ctx.accounts.pair.to_account_info();
+
+    // 1) surplus kNite vault_knite -> treasury (pair signs)
+    knite.transfer(
+        &ctx.accounts.vault_knite.to_account_info(),
+        &ctx.accounts.treasury_knite_ata.to_account_info(),
+        &pair_info,
+        signer_seeds,
+        amount_knite,
+    )?;
+
+    // 2) surplus sub vault_sub -> treasury (pair signs)
+    sub.transfer(
+        &ctx.accounts.vault_sub.to_account_info(),
+        &ctx.accounts.treasury_sub_ata.to_account_info(),
+        &pair_info,
+        signer_seeds,
+        amount_sub,
+    )?;
+
+    emit!(Skimmed {
+        pair: pair.key(),
+        amount_knite,
+        amount_sub,
+    });
+
+    Ok(())
+}
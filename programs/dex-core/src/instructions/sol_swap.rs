@@ -0,0 +1,144 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program::{self, Transfer};
+use anchor_spl::token::{self, SyncNative};
+use common_contracts::utils::now_ts;
+
+use crate::{SwapExecuted, SwapSol};
+use crate::errors::DexError;
+use crate::instructions::swap::{
+    apply_fee_split, check_swap_limits, compute_exact_in, pay_output, pull_input, record_swap, reprice_on_received,
+    SwapLegAccounts,
+};
+use crate::state::SEED_PAIR;
+use crate::tokens::TokenSide;
+use crate::assert_treasury_knite_ata;
+
+// Native SOL on kNite/WSOL pairs: the user's side of the swap runs through `temp_wsol`,
+// a WSOL account created by the instruction and closed to the user at the end, so the
+// user only ever holds lamports and gets the account rent back in the same transaction.
+
+/// lamports -> kNite: wraps `lamports_in` into temp_wsol and sells it.
+pub fn handler_sol_in(mut ctx: Context<SwapSol>, lamports_in: u64, min_out: u64) -> Result<()> {
+    if lamports_in == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    // wrap: lamports onto the token account, then sync its token amount
+    system_program::transfer(
+        CpiContext::new(
+            ctx.accounts.system_program.to_account_info(),
+            Transfer { from: ctx.accounts.user.to_account_info(), to: ctx.accounts.temp_wsol.to_account_info() },
+        ),
+        lamports_in,
+    )?;
+    token::sync_native(CpiContext::new(
+        ctx.accounts.token_program_sub.to_account_info(),
+        SyncNative { account: ctx.accounts.temp_wsol.to_account_info() },
+    ))?;
+
+    swap_through_temp(&mut ctx, false, lamports_in, min_out)?;
+    close_temp(&ctx)
+}
+
+/// kNite -> lamports: buys WSOL into temp_wsol and unwraps it by closing the account.
+pub fn handler_sol_out(mut ctx: Context<SwapSol>, amount_in: u64, min_lamports_out: u64) -> Result<()> {
+    if amount_in == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    swap_through_temp(&mut ctx, true, amount_in, min_lamports_out)?;
+    // closing hands the user the WSOL output as lamports, plus the rent
+    close_temp(&ctx)
+}
+
+/// Exact-in swap with temp_wsol as the user's WSOL account (input or output side).
+fn swap_through_temp(ctx: &mut Context<SwapSol>, is_knite_in: bool, amount_in: u64, min_out: u64) -> Result<()> {
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+    assert_treasury_knite_ata(pair, ctx.accounts.treasury_knite_ata.key(), &ctx.accounts.token_program_knite.key())?;
+
+    // stored reserves before (vault donations are ignored until sync)
+    let reserve_knite = pair.reserve_knite;
+    let reserve_sub = pair.reserve_sub;
+    let curve = pair.curve_at(now_ts()?);
+    let mut amounts = compute_exact_in(pair.swap_fee_bps, curve, is_knite_in, amount_in, reserve_knite, reserve_sub)?;
+    apply_fee_split(pair, false, &mut amounts)?;
+
+    // signer seeds for pair PDA
+    let bump_seed = [pair.bump];
+    let seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &bump_seed,
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let user_knite_ata = ctx.accounts.user_knite_ata.to_account_info();
+    let temp_wsol = ctx.accounts.temp_wsol.to_account_info();
+    let (user_ata_in, user_ata_out) =
+        if is_knite_in { (user_knite_ata, temp_wsol) } else { (temp_wsol, user_knite_ata) };
+    let accs = SwapLegAccounts {
+        knite: TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), &ctx.accounts.mint_knite),
+        sub: TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub),
+        user: ctx.accounts.user.to_account_info(),
+        user_ata_in,
+        user_ata_out,
+        pair: pair.to_account_info(),
+        vault_knite: ctx.accounts.vault_knite.to_account_info(),
+        vault_sub: ctx.accounts.vault_sub.to_account_info(),
+        treasury_knite_ata: ctx.accounts.treasury_knite_ata.to_account_info(),
+        referrer_knite_ata: None,
+        burn_mint: ctx.accounts.burn_mint.as_ref().map(|m| m.to_account_info()),
+    };
+
+    // price on what the vault actually received (Token-2022 transfer fees on kNite)
+    let received = pull_input(&accs, &[], is_knite_in, &amounts)?;
+    let mut amounts =
+        reprice_on_received(pair.swap_fee_bps, curve, is_knite_in, &amounts, received, reserve_knite, reserve_sub)?;
+    apply_fee_split(pair, false, &mut amounts)?;
+    if amounts.amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+    check_swap_limits(pair, is_knite_in, &amounts)?;
+
+    // slippage is checked on what the user actually received
+    let amount_out = pay_output(&accs, signer_seeds, is_knite_in, &amounts)?;
+    if amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    let pair_key = ctx.accounts.pair.key();
+    record_swap(&mut ctx.accounts.pair, pair_key, is_knite_in, &amounts)?;
+
+    let (mint_knite, mint_sub) = (ctx.accounts.mint_knite.key(), ctx.accounts.mint_sub.key());
+    emit!(SwapExecuted {
+        pair: pair_key,
+        user: ctx.accounts.user.key(),
+        mint_in: if is_knite_in { mint_knite } else { mint_sub },
+        mint_out: if is_knite_in { mint_sub } else { mint_knite },
+        amount_in,
+        amount_out,
+        fee_knite: amounts.fee_knite,
+        fee_lp: amounts.fee_lp,
+        fee_treasury: amounts.fee_treasury()?,
+        fee_burn: amounts.fee_burn,
+        fee_referral: amounts.fee_referral,
+    });
+
+    Ok(())
+}
+
+/// Closes temp_wsol to the user: its rent and any WSOL it holds come back as lamports.
+fn close_temp(ctx: &Context<SwapSol>) -> Result<()> {
+    let user = ctx.accounts.user.to_account_info();
+    TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), &ctx.accounts.mint_sub).close(
+        &ctx.accounts.temp_wsol.to_account_info(),
+        &user,
+        &user,
+        &[],
+    )
+}
@@ -1,12 +1,317 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Transfer};
+use common_contracts::constants::BPS_DENOMINATOR;
+use common_contracts::utils::{now_slot, now_ts};
 
-use crate::{SwapExactIn, SwapExecuted};
+use crate::{CircuitBreakerTripped, ReferralFeePaid, SwapExactIn, SwapExecuted};
+use crate::curve::Curve;
 use crate::errors::DexError;
-use crate::math::{cp_out, fee_ceil};
-use crate::state::SEED_PAIR;
+use crate::instructions::referral::resolve_referrer;
+use crate::math::{fee_ceil, mul_div_floor};
+use crate::state::{Pair, SEED_PAIR};
+use crate::tokens::TokenSide;
 use crate::assert_treasury_knite_ata;
 
+/// Token movements for one swap leg. The fee is always taken on the kNite side.
+#[derive(Clone, Copy)]
+pub struct SwapAmounts {
+    /// total debited from the user
+    pub amount_in: u64,
+    /// part of the input that lands in the input vault
+    pub to_vault: u64,
+    /// kNite fee taken on this leg (lp + treasury + burn + referrer)
+    pub fee_knite: u64,
+    /// part of `fee_knite` paid to the referrer instead of the treasury
+    pub fee_referral: u64,
+    /// part of `fee_knite` left in vault_knite for LPs
+    pub fee_lp: u64,
+    /// part of `fee_knite` burned from kNite supply
+    pub fee_burn: u64,
+    /// credited to the user
+    pub amount_out: u64,
+}
+
+impl SwapAmounts {
+    /// part of `fee_knite` sent to the treasury ATA (everything not otherwise assigned)
+    pub fn fee_treasury(&self) -> Result<u64> {
+        self.fee_knite
+            .checked_sub(self.fee_referral)
+            .and_then(|v| v.checked_sub(self.fee_lp))
+            .and_then(|v| v.checked_sub(self.fee_burn))
+            .ok_or_else(|| error!(DexError::MathOverflow))
+    }
+
+    /// (reserve_knite, reserve_sub) once this leg settles; the LP fee share stays in the pool
+    pub fn reserves_after(&self, is_knite_in: bool, reserve_knite: u64, reserve_sub: u64) -> Result<(u64, u64)> {
+        let overflow = || error!(DexError::MathOverflow);
+        if is_knite_in {
+            let knite_added = self.to_vault.checked_add(self.fee_lp).ok_or_else(overflow)?;
+            Ok((
+                reserve_knite.checked_add(knite_added).ok_or_else(overflow)?,
+                reserve_sub.checked_sub(self.amount_out).ok_or_else(overflow)?,
+            ))
+        } else {
+            let knite_released = self
+                .amount_out
+                .checked_add(self.fee_knite)
+                .and_then(|v| v.checked_sub(self.fee_lp))
+                .ok_or_else(overflow)?;
+            Ok((
+                reserve_knite.checked_sub(knite_released).ok_or_else(overflow)?,
+                reserve_sub.checked_add(self.to_vault).ok_or_else(overflow)?,
+            ))
+        }
+    }
+
+    /// kNite-side volume of this leg (input kNite, or gross kNite released)
+    pub fn knite_volume(&self, is_knite_in: bool) -> Result<u64> {
+        if is_knite_in {
+            Ok(self.amount_in)
+        } else {
+            self.amount_out
+                .checked_add(self.fee_knite)
+                .ok_or_else(|| error!(DexError::MathOverflow))
+        }
+    }
+}
+
+/// Accounts touched by one swap leg (already validated by the caller).
+/// The fee is taken on the `knite` side: kNite for `Pair`, the fee mint for `GenericPair`.
+pub struct SwapLegAccounts<'info> {
+    pub knite: TokenSide<'info>,
+    pub sub: TokenSide<'info>,
+    pub user: AccountInfo<'info>,
+    pub user_ata_in: AccountInfo<'info>,
+    pub user_ata_out: AccountInfo<'info>,
+    pub pair: AccountInfo<'info>,
+    pub vault_knite: AccountInfo<'info>,
+    pub vault_sub: AccountInfo<'info>,
+    pub treasury_knite_ata: AccountInfo<'info>,
+    /// required when `SwapAmounts::fee_referral > 0`
+    pub referrer_knite_ata: Option<AccountInfo<'info>>,
+    /// writable `knite` mint; required when `SwapAmounts::fee_burn > 0`
+    pub burn_mint: Option<AccountInfo<'info>>,
+}
+
+/// Exact-in amounts:
+/// - kNite -> Sub: fee from INPUT kNite (user -> treasury)
+/// - Sub -> kNite: fee from OUTPUT kNite (vault -> treasury)
+pub fn compute_exact_in(
+    swap_fee_bps: u16,
+    curve: Curve,
+    is_knite_in: bool,
+    amount_in: u64,
+    reserve_knite: u64,
+    reserve_sub: u64,
+) -> Result<SwapAmounts> {
+    if is_knite_in {
+        let fee_knite = fee_ceil(amount_in, swap_fee_bps)?;
+        if fee_knite == 0 || amount_in <= fee_knite {
+            return err!(DexError::InvalidAmount);
+        }
+
+        let net_in = amount_in
+            .checked_sub(fee_knite)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+        // output in sub token
+        let amount_out = curve.amount_out(net_in, reserve_knite, reserve_sub)?;
+
+        Ok(SwapAmounts { amount_in, to_vault: net_in, fee_knite, fee_referral: 0, fee_lp: 0, fee_burn: 0, amount_out })
+    } else {
+        let gross_knite_out = curve.amount_out(amount_in, reserve_sub, reserve_knite)?;
+
+        let fee_knite = fee_ceil(gross_knite_out, swap_fee_bps)?;
+        if fee_knite == 0 || gross_knite_out <= fee_knite {
+            return err!(DexError::InvalidAmount);
+        }
+
+        let net_knite_out = gross_knite_out
+            .checked_sub(fee_knite)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+        Ok(SwapAmounts {
+            amount_in,
+            to_vault: amount_in,
+            fee_knite,
+            fee_referral: 0,
+            fee_lp: 0,
+            fee_burn: 0,
+            amount_out: net_knite_out,
+        })
+    }
+}
+
+/// Input side of a leg: user -> input vault (plus the kNite fee when kNite is the input).
+/// Returns what the input vault actually received for the curve (net of any Token-2022
+/// transfer fee, excluding the LP fee share). Empty `user_seeds` = user-signed; a PDA
+/// `user` (e.g. a limit order escrow) passes its seeds.
+pub fn pull_input<'info>(
+    accs: &SwapLegAccounts<'info>,
+    user_seeds: &[&[&[u8]]],
+    is_knite_in: bool,
+    amounts: &SwapAmounts,
+) -> Result<u64> {
+    if is_knite_in {
+        // 1) fee kNite user -> treasury ATA (+ referrer share, burn)
+        pay_fee(accs, &accs.user_ata_in, &accs.user, user_seeds, amounts)?;
+
+        // 2) net kNite + LP fee share user -> vault_knite
+        let to_vault = amounts
+            .to_vault
+            .checked_add(amounts.fee_lp)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+        let received = accs
+            .knite
+            .transfer_measured(&accs.user_ata_in, &accs.vault_knite, &accs.user, user_seeds, to_vault)?;
+        received
+            .checked_sub(amounts.fee_lp)
+            .ok_or_else(|| error!(DexError::MathOverflow))
+    } else {
+        // 1) sub user -> vault_sub
+        accs.sub
+            .transfer_measured(&accs.user_ata_in, &accs.vault_sub, &accs.user, user_seeds, amounts.to_vault)
+    }
+}
+
+/// Output side of a leg (pair signs): output vault -> user (plus the kNite fee when kNite
+/// is the output). Returns what the user actually received.
+pub fn pay_output<'info>(
+    accs: &SwapLegAccounts<'info>,
+    signer_seeds: &[&[&[u8]]],
+    is_knite_in: bool,
+    amounts: &SwapAmounts,
+) -> Result<u64> {
+    if is_knite_in {
+        // 3) sub vault_sub -> user
+        accs.sub
+            .transfer_measured(&accs.vault_sub, &accs.user_ata_out, &accs.pair, signer_seeds, amounts.amount_out)
+    } else {
+        // 2) fee kNite vault_knite -> treasury (+ referrer share, burn); LP share stays
+        pay_fee(accs, &accs.vault_knite, &accs.pair, signer_seeds, amounts)?;
+
+        // 3) net kNite vault_knite -> user
+        accs.knite
+            .transfer_measured(&accs.vault_knite, &accs.user_ata_out, &accs.pair, signer_seeds, amounts.amount_out)
+    }
+}
+
+/// Fee movements out of `from`: `fee_referral` to the referrer, `fee_burn` burned,
+/// the treasury share to the treasury ATA. `fee_lp` is handled by the caller.
+fn pay_fee<'info>(
+    accs: &SwapLegAccounts<'info>,
+    from: &AccountInfo<'info>,
+    authority: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amounts: &SwapAmounts,
+) -> Result<()> {
+    accs.knite
+        .transfer(from, &accs.treasury_knite_ata, authority, signer_seeds, amounts.fee_treasury()?)?;
+    if amounts.fee_burn > 0 {
+        let burn_mint = accs.burn_mint.as_ref().ok_or_else(|| error!(DexError::BurnMintRequired))?;
+        accs.knite.burn(burn_mint, from, authority, signer_seeds, amounts.fee_burn)?;
+    }
+
+    if amounts.fee_referral > 0 {
+        let referrer_knite_ata = accs
+            .referrer_knite_ata
+            .as_ref()
+            .ok_or_else(|| error!(DexError::InvalidReferral))?;
+        accs.knite
+            .transfer(from, referrer_knite_ata, authority, signer_seeds, amounts.fee_referral)?;
+    }
+    Ok(())
+}
+
+/// Re-prices an exact-in leg on what the input vault actually received; only differs
+/// from `amounts` for Token-2022 mints with a transfer fee. `amount_in` (user debit) is kept;
+/// a re-priced kNite-out fee needs `apply_fee_split` again.
+pub fn reprice_on_received(
+    swap_fee_bps: u16,
+    curve: Curve,
+    is_knite_in: bool,
+    amounts: &SwapAmounts,
+    received: u64,
+    reserve_knite: u64,
+    reserve_sub: u64,
+) -> Result<SwapAmounts> {
+    if received == amounts.to_vault {
+        return Ok(*amounts);
+    }
+    if is_knite_in {
+        // kNite fee was taken on the user's input; only the curve input shrinks
+        let amount_out = curve.amount_out(received, reserve_knite, reserve_sub)?;
+        Ok(SwapAmounts { to_vault: received, amount_out, ..*amounts })
+    } else {
+        let repriced = compute_exact_in(swap_fee_bps, curve, false, received, reserve_knite, reserve_sub)?;
+        Ok(SwapAmounts { amount_in: amounts.amount_in, ..repriced })
+    }
+}
+
+/// Assigns a leg's kNite fee: the referrer's cut first (when there is one), then the
+/// remainder by `pair.fee_split`, rounded down; the treasury keeps the dust.
+pub fn apply_fee_split(pair: &Pair, has_referrer: bool, amounts: &mut SwapAmounts) -> Result<()> {
+    amounts.fee_referral = if has_referrer {
+        mul_div_floor(amounts.fee_knite, pair.referral_share_bps as u64, BPS_DENOMINATOR)?
+    } else {
+        0
+    };
+    let rest = amounts
+        .fee_knite
+        .checked_sub(amounts.fee_referral)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    amounts.fee_lp = mul_div_floor(rest, pair.fee_split.lp_bps as u64, BPS_DENOMINATOR)?;
+    amounts.fee_burn = mul_div_floor(rest, pair.fee_split.burn_bps as u64, BPS_DENOMINATOR)?;
+    Ok(())
+}
+
+/// Pre-settlement guards shared by every swap path (against the stored reserves).
+/// A pair in batch mode only trades through settle_batch, which checks the impact itself;
+/// a pair in its launch phase only through swap_exact_in, which tracks the wallet.
+pub fn check_swap_limits(pair: &Pair, is_knite_in: bool, amounts: &SwapAmounts) -> Result<()> {
+    if pair.in_launch_phase(now_ts()?)? {
+        return err!(DexError::LaunchPhaseActive);
+    }
+    check_pool_limits(pair, is_knite_in, amounts)
+}
+
+/// Batch mode and price impact; `check_swap_limits` without the launch gate.
+pub fn check_pool_limits(pair: &Pair, is_knite_in: bool, amounts: &SwapAmounts) -> Result<()> {
+    if pair.batch_slots > 0 {
+        return err!(DexError::BatchModeActive);
+    }
+    let before = (pair.reserve_knite, pair.reserve_sub);
+    let after = amounts.reserves_after(is_knite_in, before.0, before.1)?;
+    pair.check_price_impact(before, after)
+}
+
+/// Post-settlement bookkeeping shared by every swap path:
+/// TWAP accrues the pre-trade price, stored reserves and stats move by this leg,
+/// then the per-slot volume breaker runs.
+pub fn record_swap(pair: &mut Pair, pair_key: Pubkey, is_knite_in: bool, amounts: &SwapAmounts) -> Result<()> {
+    let now = now_ts()?;
+    let (reserve_knite, reserve_sub) = (pair.reserve_knite, pair.reserve_sub);
+    pair.update_oracle(reserve_knite, reserve_sub, now)?;
+    (pair.reserve_knite, pair.reserve_sub) = amounts.reserves_after(is_knite_in, reserve_knite, reserve_sub)?;
+
+    let (knite, sub) = if is_knite_in {
+        (amounts.amount_in, amounts.amount_out)
+    } else {
+        (amounts.amount_out, amounts.amount_in)
+    };
+    pair.stats.record(is_knite_in, knite, sub, amounts.fee_knite, now)?;
+
+    let slot = now_slot()?;
+    if pair.record_slot_volume(amounts.knite_volume(is_knite_in)?, slot)? {
+        emit!(CircuitBreakerTripped {
+            pair: pair_key,
+            slot,
+            slot_volume_knite: pair.slot_volume_knite,
+            max_slot_volume_knite: pair.max_slot_volume_knite,
+        });
+    }
+    Ok(())
+}
+
 pub fn handler(ctx: Context<SwapExactIn>, amount_in: u64, min_out: u64) -> Result<()> {
     if amount_in == 0 {
         return err!(DexError::InvalidAmount);
@@ -30,15 +335,20 @@ pub fn handler(ctx: Context<SwapExactIn>, amount_in: u64, min_out: u64) -> Resul
     }
 
     // validate treasury knite ATA
-    assert_treasury_knite_ata(pair, ctx.accounts.treasury_knite_ata.key())?;
+    assert_treasury_knite_ata(pair, ctx.accounts.treasury_knite_ata.key(), &ctx.accounts.token_program_knite.key())?;
+
+    // stored reserves before (vault donations are ignored until sync)
+    let reserve_knite = pair.reserve_knite;
+    let reserve_sub = pair.reserve_sub;
+    let curve = pair.curve_at(now_ts()?);
 
-    // reserves before
-    let reserve_knite = ctx.accounts.vault_knite.amount;
-    let reserve_sub = ctx.accounts.vault_sub.amount;
+    let mut amounts = compute_exact_in(pair.swap_fee_bps, curve, is_knite_in, amount_in, reserve_knite, reserve_sub)?;
+
+    // optional referral: records the referee on the first swap, then splits the fee
+    let referrer = resolve_referrer(ctx.accounts)?;
+    apply_fee_split(pair, referrer.is_some(), &mut amounts)?;
 
-  
     // signer seeds for pair PDA (PRODUCTION-SAFE)
-    
     let bump = pair.bump;
     let bump_seed = [bump]; // stable stack value
 
@@ -51,130 +361,60 @@ pub fn handler(ctx: Context<SwapExactIn>, amount_in: u64, min_out: u64) -> Resul
     ];
     let signer_seeds: &[&[&[u8]]] = &[seeds];
 
-    let fee_knite: u64;
-    let amount_out: u64;
-
-    if is_knite_in {
-       
-        // kNite -> Sub
-        // fee from INPUT kNite (user -> treasury)
-      
-        fee_knite = fee_ceil(amount_in, pair.swap_fee_bps)?;
-        if fee_knite == 0 || amount_in <= fee_knite {
-            return err!(DexError::InvalidAmount);
-        }
-
-        let net_in = amount_in
-            .checked_sub(fee_knite)
-            .ok_or_else(|| error!(DexError::MathOverflow))?;
-
-        // output in sub token
-        let out_sub = cp_out(net_in, reserve_knite, reserve_sub)?;
-        if out_sub < min_out {
-            return err!(DexError::SlippageExceeded);
-        }
-        amount_out = out_sub;
-
-        // 1) fee kNite user -> treasury ATA
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user_ata_in.to_account_info(),
-                    to: ctx.accounts.treasury_knite_ata.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
-            fee_knite,
-        )?;
-
-        // 2) net kNite user -> vault_knite
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user_ata_in.to_account_info(),
-                    to: ctx.accounts.vault_knite.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
-            net_in,
-        )?;
-
-        // 3) sub vault_sub -> user (pair signs)
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.vault_sub.to_account_info(),
-                    to: ctx.accounts.user_ata_out.to_account_info(),
-                    authority: ctx.accounts.pair.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount_out,
-        )?;
+    let (mint_knite, mint_sub) = if is_knite_in {
+        (&ctx.accounts.mint_in, &ctx.accounts.mint_out)
     } else {
-       
-        // Sub -> kNite
-        // fee from OUTPUT kNite (vault -> treasury)
-   
-        let gross_knite_out = cp_out(amount_in, reserve_sub, reserve_knite)?;
+        (&ctx.accounts.mint_out, &ctx.accounts.mint_in)
+    };
+    let accs = SwapLegAccounts {
+        knite: TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), mint_knite),
+        sub: TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), mint_sub),
+        user: ctx.accounts.user.to_account_info(),
+        user_ata_in: ctx.accounts.user_ata_in.to_account_info(),
+        user_ata_out: ctx.accounts.user_ata_out.to_account_info(),
+        pair: ctx.accounts.pair.to_account_info(),
+        vault_knite: ctx.accounts.vault_knite.to_account_info(),
+        vault_sub: ctx.accounts.vault_sub.to_account_info(),
+        treasury_knite_ata: ctx.accounts.treasury_knite_ata.to_account_info(),
+        referrer_knite_ata: ctx.accounts.referrer_knite_ata.as_ref().map(|a| a.to_account_info()),
+        burn_mint: ctx.accounts.burn_mint.as_ref().map(|m| m.to_account_info()),
+    };
 
-        fee_knite = fee_ceil(gross_knite_out, pair.swap_fee_bps)?;
-        if fee_knite == 0 || gross_knite_out <= fee_knite {
-            return err!(DexError::InvalidAmount);
-        }
+    // price on what the vault actually received (Token-2022 transfer fees)
+    let received = pull_input(&accs, &[], is_knite_in, &amounts)?;
+    let mut amounts =
+        reprice_on_received(pair.swap_fee_bps, curve, is_knite_in, &amounts, received, reserve_knite, reserve_sub)?;
+    apply_fee_split(pair, referrer.is_some(), &mut amounts)?;
+    if amounts.amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+    let now = now_ts()?;
+    if pair.in_launch_phase(now)? {
+        let Some(cooldown) = ctx.accounts.launch_cooldown.as_mut() else {
+            return err!(DexError::LaunchPhaseActive);
+        };
+        // the cap is on buys of the launched token; selling it back is not limited
+        let sub_bought = if is_knite_in { amounts.amount_out } else { 0 };
+        cooldown.record_trade(pair, sub_bought, now, now_slot()?)?;
+    }
+    check_pool_limits(pair, is_knite_in, &amounts)?;
 
-        let net_knite_out = gross_knite_out
-            .checked_sub(fee_knite)
-            .ok_or_else(|| error!(DexError::MathOverflow))?;
+    // slippage is checked on what the user actually received
+    let amount_out = pay_output(&accs, signer_seeds, is_knite_in, &amounts)?;
+    if amount_out < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
 
-        if net_knite_out < min_out {
-            return err!(DexError::SlippageExceeded);
-        }
-        amount_out = net_knite_out;
+    let pair_key = ctx.accounts.pair.key();
+    record_swap(&mut ctx.accounts.pair, pair_key, is_knite_in, &amounts)?;
 
-        // 1) sub user -> vault_sub
-        token::transfer(
-            CpiContext::new(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.user_ata_in.to_account_info(),
-                    to: ctx.accounts.vault_sub.to_account_info(),
-                    authority: ctx.accounts.user.to_account_info(),
-                },
-            ),
-            amount_in,
-        )?;
-
-        // 2) fee kNite vault_knite -> treasury (pair signs)
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.vault_knite.to_account_info(),
-                    to: ctx.accounts.treasury_knite_ata.to_account_info(),
-                    authority: ctx.accounts.pair.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            fee_knite,
-        )?;
-
-        // 3) net kNite vault_knite -> user (pair signs)
-        token::transfer(
-            CpiContext::new_with_signer(
-                ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx.accounts.vault_knite.to_account_info(),
-                    to: ctx.accounts.user_ata_out.to_account_info(),
-                    authority: ctx.accounts.pair.to_account_info(),
-                },
-                signer_seeds,
-            ),
-            amount_out,
-        )?;
+    if let (Some(referrer), true) = (referrer, amounts.fee_referral > 0) {
+        emit!(ReferralFeePaid {
+            pair: pair_key,
+            user: ctx.accounts.user.key(),
+            referrer,
+            amount_knite: amounts.fee_referral,
+        });
     }
 
     emit!(SwapExecuted {
@@ -184,7 +424,11 @@ pub fn handler(ctx: Context<SwapExactIn>, amount_in: u64, min_out: u64) -> Resul
         mint_out,
         amount_in,
         amount_out,
-        fee_knite,
+        fee_knite: amounts.fee_knite,
+        fee_lp: amounts.fee_lp,
+        fee_treasury: amounts.fee_treasury()?,
+        fee_burn: amounts.fee_burn,
+        fee_referral: amounts.fee_referral,
     });
 
     Ok(())
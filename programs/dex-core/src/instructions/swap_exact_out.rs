@@ -0,0 +1,183 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::now_ts;
+
+use crate::{SwapExactOut, SwapExecuted};
+use crate::curve::Curve;
+use crate::errors::DexError;
+use crate::instructions::swap::{
+    apply_fee_split, check_swap_limits, pay_output, pull_input, record_swap, SwapAmounts, SwapLegAccounts,
+};
+use crate::math::{fee_ceil, gross_up_for_fee};
+use crate::state::SEED_PAIR;
+use crate::tokens::{gross_up_transfer_fee, TokenSide};
+use crate::assert_treasury_knite_ata;
+
+/// Exact-out amounts, same kNite-side fee semantics as exact-in:
+/// - kNite -> Sub: fee = ceil(amount_in * bps) on INPUT kNite
+/// - Sub -> kNite: vault releases net + fee, fee goes to treasury
+pub fn compute_exact_out(
+    swap_fee_bps: u16,
+    curve: Curve,
+    is_knite_in: bool,
+    amount_out: u64,
+    reserve_knite: u64,
+    reserve_sub: u64,
+) -> Result<SwapAmounts> {
+    if is_knite_in {
+        // net kNite the vault needs for exactly `amount_out` sub (inverse curve)
+        if amount_out >= reserve_sub {
+            return err!(DexError::InsufficientLiquidity);
+        }
+        let net_needed = curve.amount_in(amount_out, reserve_knite, reserve_sub)?;
+
+        // gross up so that the exact-in fee (ceil on input) still leaves `net_needed`
+        let amount_in = gross_up_for_fee(net_needed, swap_fee_bps)?;
+
+        let fee_knite = fee_ceil(amount_in, swap_fee_bps)?;
+        if fee_knite == 0 || amount_in <= fee_knite {
+            return err!(DexError::InvalidAmount);
+        }
+
+        let net_in = amount_in
+            .checked_sub(fee_knite)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+        Ok(SwapAmounts { amount_in, to_vault: net_in, fee_knite, fee_referral: 0, fee_lp: 0, fee_burn: 0, amount_out })
+    } else {
+        let gross_knite_out = gross_up_for_fee(amount_out, swap_fee_bps)?;
+        if gross_knite_out >= reserve_knite {
+            return err!(DexError::InsufficientLiquidity);
+        }
+
+        let fee_knite = gross_knite_out
+            .checked_sub(amount_out)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+        if fee_knite == 0 {
+            return err!(DexError::InvalidAmount);
+        }
+
+        let amount_in = curve.amount_in(gross_knite_out, reserve_sub, reserve_knite)?;
+
+        Ok(SwapAmounts { amount_in, to_vault: amount_in, fee_knite, fee_referral: 0, fee_lp: 0, fee_burn: 0, amount_out })
+    }
+}
+
+pub fn handler(ctx: Context<SwapExactOut>, amount_out: u64, max_in: u64) -> Result<()> {
+    if amount_out == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let pair = &ctx.accounts.pair;
+    if !pair.enabled {
+        return err!(DexError::PairDisabled);
+    }
+
+    let mint_in = ctx.accounts.mint_in.key();
+    let mint_out = ctx.accounts.mint_out.key();
+
+    let is_knite_in = mint_in == pair.mint_knite;
+    let is_knite_out = mint_out == pair.mint_knite;
+
+    // internal-only: must be exactly knite <-> sub
+    if !((is_knite_in && mint_out == pair.mint_sub) || (is_knite_out && mint_in == pair.mint_sub))
+    {
+        return err!(DexError::TokenNotAllowed);
+    }
+
+    // validate treasury knite ATA
+    assert_treasury_knite_ata(pair, ctx.accounts.treasury_knite_ata.key(), &ctx.accounts.token_program_knite.key())?;
+
+    // stored reserves before (vault donations are ignored until sync)
+    let reserve_knite = pair.reserve_knite;
+    let reserve_sub = pair.reserve_sub;
+
+    // Token-2022 transfer fees: the vault releases enough for `amount_out` to arrive,
+    // and the user sends enough for the input vault to still receive `to_vault`
+    let vault_out = gross_up_transfer_fee(&ctx.accounts.mint_out.to_account_info(), amount_out)?;
+    let curve = pair.curve_at(now_ts()?);
+    let mut amounts = compute_exact_out(pair.swap_fee_bps, curve, is_knite_in, vault_out, reserve_knite, reserve_sub)?;
+    apply_fee_split(pair, false, &mut amounts)?;
+    // on kNite input the LP fee share travels with `to_vault`, so gross up both together
+    let needed_in = amounts.to_vault;
+    let extra_in = if is_knite_in { amounts.fee_lp } else { 0 };
+    let sent = needed_in
+        .checked_add(extra_in)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    let gross_sent = gross_up_transfer_fee(&ctx.accounts.mint_in.to_account_info(), sent)?;
+    amounts.to_vault = gross_sent
+        .checked_sub(extra_in)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    let transfer_fee_in = gross_sent
+        .checked_sub(sent)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    amounts.amount_in = amounts
+        .amount_in
+        .checked_add(transfer_fee_in)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    if amounts.amount_in > max_in {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    // signer seeds for pair PDA
+    let bump = pair.bump;
+    let bump_seed = [bump];
+
+    let seeds: &[&[u8]] = &[
+        SEED_PAIR,
+        pair.treasury.as_ref(),
+        pair.mint_knite.as_ref(),
+        pair.mint_sub.as_ref(),
+        &bump_seed,
+    ];
+    let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+    let (mint_knite, mint_sub) = if is_knite_in {
+        (&ctx.accounts.mint_in, &ctx.accounts.mint_out)
+    } else {
+        (&ctx.accounts.mint_out, &ctx.accounts.mint_in)
+    };
+    let accs = SwapLegAccounts {
+        knite: TokenSide::new(ctx.accounts.token_program_knite.to_account_info(), mint_knite),
+        sub: TokenSide::new(ctx.accounts.token_program_sub.to_account_info(), mint_sub),
+        user: ctx.accounts.user.to_account_info(),
+        user_ata_in: ctx.accounts.user_ata_in.to_account_info(),
+        user_ata_out: ctx.accounts.user_ata_out.to_account_info(),
+        pair: ctx.accounts.pair.to_account_info(),
+        vault_knite: ctx.accounts.vault_knite.to_account_info(),
+        vault_sub: ctx.accounts.vault_sub.to_account_info(),
+        treasury_knite_ata: ctx.accounts.treasury_knite_ata.to_account_info(),
+        referrer_knite_ata: None,
+        burn_mint: ctx.accounts.burn_mint.as_ref().map(|m| m.to_account_info()),
+    };
+
+    let received = pull_input(&accs, &[], is_knite_in, &amounts)?;
+    if received < needed_in {
+        return err!(DexError::SlippageExceeded);
+    }
+    amounts.to_vault = received;
+    check_swap_limits(pair, is_knite_in, &amounts)?;
+
+    let received_out = pay_output(&accs, signer_seeds, is_knite_in, &amounts)?;
+    if received_out < amount_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    let pair_key = ctx.accounts.pair.key();
+    record_swap(&mut ctx.accounts.pair, pair_key, is_knite_in, &amounts)?;
+
+    emit!(SwapExecuted {
+        pair: ctx.accounts.pair.key(),
+        user: ctx.accounts.user.key(),
+        mint_in,
+        mint_out,
+        amount_in: amounts.amount_in,
+        amount_out: received_out,
+        fee_knite: amounts.fee_knite,
+        fee_lp: amounts.fee_lp,
+        fee_treasury: amounts.fee_treasury()?,
+        fee_burn: amounts.fee_burn,
+        fee_referral: amounts.fee_referral,
+    });
+
+    Ok(())
+}
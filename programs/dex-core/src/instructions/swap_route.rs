@@ -0,0 +1,210 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use common_contracts::utils::now_ts;
+
+use crate::{RouteExecuted, SwapExecuted, SwapRoute};
+use crate::errors::DexError;
+use crate::instructions::swap::{
+    apply_fee_split, check_swap_limits, compute_exact_in, pay_output, pull_input, record_swap, reprice_on_received,
+    SwapLegAccounts,
+};
+use crate::state::{Pair, MAX_ROUTE_HOPS, PAIR_VERSION, ROUTE_ACCOUNTS_PER_HOP, SEED_PAIR};
+use crate::tokens::TokenSide;
+use crate::assert_treasury_knite_ata;
+
+/// remaining_accounts, per hop (in order):
+/// [pair(mut), mint_knite, mint_sub, vault_knite(mut), vault_sub(mut), treasury_knite_ata(mut), user_ata_out(mut)]
+/// mint_knite must also be writable when the pair's fee split burns.
+/// Hop N spends what hop N-1 actually delivered; hop 0 spends `user_ata_in`.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+    amount_in: u64,
+    min_out: u64,
+) -> Result<()> {
+    if amount_in == 0 {
+        return err!(DexError::InvalidAmount);
+    }
+
+    let rem = ctx.remaining_accounts;
+    if rem.is_empty() || rem.len() % ROUTE_ACCOUNTS_PER_HOP != 0 {
+        return err!(DexError::InvalidRoute);
+    }
+    let hops = rem.len() / ROUTE_ACCOUNTS_PER_HOP;
+    if hops > MAX_ROUTE_HOPS {
+        return err!(DexError::InvalidRoute);
+    }
+
+    let user_key = ctx.accounts.user.key();
+    let now = now_ts()?;
+    let route_mint_in = ctx.accounts.user_ata_in.mint;
+
+    let mut current_mint = route_mint_in;
+    let mut current_amount = amount_in;
+    let mut current_ata = ctx.accounts.user_ata_in.to_account_info();
+
+    for hop in rem.chunks(ROUTE_ACCOUNTS_PER_HOP) {
+        let pair_info = &hop[0];
+        let mint_knite_info = &hop[1];
+        let mint_sub_info = &hop[2];
+        let vault_knite_info = &hop[3];
+        let vault_sub_info = &hop[4];
+        let treasury_info = &hop[5];
+        let user_out_info = &hop[6];
+
+        // Pair: owner + discriminator checked by try_from, PDA re-derived from stored bump
+        if !pair_info.is_writable {
+            return err!(DexError::InvalidRoute);
+        }
+        let mut pair: Account<'info, Pair> = Account::try_from(pair_info)?;
+        let bump_seed = [pair.bump];
+        let seeds: &[&[u8]] = &[
+            SEED_PAIR,
+            pair.treasury.as_ref(),
+            pair.mint_knite.as_ref(),
+            pair.mint_sub.as_ref(),
+            &bump_seed,
+        ];
+        let expected = Pubkey::create_program_address(seeds, ctx.program_id)
+            .map_err(|_| error!(DexError::InvalidRoute))?;
+        if expected != pair.key() {
+            return err!(DexError::InvalidRoute);
+        }
+        if pair.version != PAIR_VERSION {
+            return err!(DexError::UnsupportedPairVersion);
+        }
+        if !pair.enabled {
+            return err!(DexError::PairDisabled);
+        }
+
+        // direction is decided by the mint we currently hold
+        let is_knite_in = if current_mint == pair.mint_knite {
+            true
+        } else if current_mint == pair.mint_sub {
+            false
+        } else {
+            return err!(DexError::InvalidRoute);
+        };
+        let mint_out = if is_knite_in { pair.mint_sub } else { pair.mint_knite };
+
+        // mints decide which token program each side uses
+        let mint_knite: InterfaceAccount<'info, Mint> = InterfaceAccount::try_from(mint_knite_info)?;
+        let mint_sub: InterfaceAccount<'info, Mint> = InterfaceAccount::try_from(mint_sub_info)?;
+        if mint_knite.key() != pair.mint_knite || mint_sub.key() != pair.mint_sub {
+            return err!(DexError::MintMismatch);
+        }
+        let knite = TokenSide::new(token_program_for(ctx.accounts, mint_knite_info)?, &mint_knite);
+        let sub = TokenSide::new(token_program_for(ctx.accounts, mint_sub_info)?, &mint_sub);
+
+        // vaults
+        let vault_knite: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(vault_knite_info)?;
+        let vault_sub: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(vault_sub_info)?;
+        if vault_knite.key() != pair.vault_knite
+            || vault_knite.mint != pair.mint_knite
+            || vault_sub.key() != pair.vault_sub
+            || vault_sub.mint != pair.mint_sub
+        {
+            return err!(DexError::MintMismatch);
+        }
+        if vault_knite.owner != pair.key() || vault_sub.owner != pair.key() {
+            return err!(DexError::Unauthorized);
+        }
+
+        // treasury kNite ATA of this pair
+        let treasury_ata: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(treasury_info)?;
+        if treasury_ata.mint != pair.mint_knite {
+            return err!(DexError::MintMismatch);
+        }
+        assert_treasury_knite_ata(&pair, treasury_ata.key(), knite.token_program.key)?;
+
+        // user output account for this hop
+        let user_out: InterfaceAccount<'info, TokenAccount> = InterfaceAccount::try_from(user_out_info)?;
+        if user_out.owner != user_key {
+            return err!(DexError::Unauthorized);
+        }
+        if user_out.mint != mint_out {
+            return err!(DexError::MintMismatch);
+        }
+        if user_out_info.key() == current_ata.key() {
+            return err!(DexError::InvalidRoute);
+        }
+
+        let reserve_knite = pair.reserve_knite;
+        let reserve_sub = pair.reserve_sub;
+        let curve = pair.curve_at(now);
+        let mut amounts =
+            compute_exact_in(pair.swap_fee_bps, curve, is_knite_in, current_amount, reserve_knite, reserve_sub)?;
+        apply_fee_split(&pair, false, &mut amounts)?;
+
+        let accs = SwapLegAccounts {
+            knite,
+            sub,
+            user: ctx.accounts.user.to_account_info(),
+            user_ata_in: current_ata.clone(),
+            user_ata_out: user_out_info.clone(),
+            pair: pair_info.clone(),
+            vault_knite: vault_knite_info.clone(),
+            vault_sub: vault_sub_info.clone(),
+            treasury_knite_ata: treasury_info.clone(),
+            referrer_knite_ata: None,
+            burn_mint: mint_knite_info.is_writable.then(|| mint_knite_info.clone()),
+        };
+        let signer_seeds: &[&[&[u8]]] = &[seeds];
+
+        let received = pull_input(&accs, &[], is_knite_in, &amounts)?;
+        let mut amounts =
+            reprice_on_received(pair.swap_fee_bps, curve, is_knite_in, &amounts, received, reserve_knite, reserve_sub)?;
+        apply_fee_split(&pair, false, &mut amounts)?;
+        check_swap_limits(&pair, is_knite_in, &amounts)?;
+        let delivered = pay_output(&accs, signer_seeds, is_knite_in, &amounts)?;
+
+        // oracle + breaker bookkeeping; persist since the pair is not in Context
+        let pair_key = pair.key();
+        record_swap(&mut pair, pair_key, is_knite_in, &amounts)?;
+        pair.exit(ctx.program_id)?;
+
+        emit!(SwapExecuted {
+            pair: pair.key(),
+            user: user_key,
+            mint_in: current_mint,
+            mint_out,
+            amount_in: current_amount,
+            amount_out: delivered,
+            fee_knite: amounts.fee_knite,
+            fee_lp: amounts.fee_lp,
+            fee_treasury: amounts.fee_treasury()?,
+            fee_burn: amounts.fee_burn,
+            fee_referral: amounts.fee_referral,
+        });
+
+        current_mint = mint_out;
+        current_amount = delivered;
+        current_ata = user_out_info.clone();
+    }
+
+    // one slippage check for the whole route
+    if current_amount < min_out {
+        return err!(DexError::SlippageExceeded);
+    }
+
+    emit!(RouteExecuted {
+        user: user_key,
+        mint_in: route_mint_in,
+        mint_out: current_mint,
+        amount_in,
+        amount_out: current_amount,
+        hops: hops as u8,
+    });
+
+    Ok(())
+}
+
+/// SPL Token or Token-2022, whichever owns `mint`.
+fn token_program_for<'info>(accs: &SwapRoute<'info>, mint: &AccountInfo<'info>) -> Result<AccountInfo<'info>> {
+    if *mint.owner == accs.token_program.key() {
+        Ok(accs.token_program.to_account_info())
+    } else if *mint.owner == accs.token_program_2022.key() {
+        Ok(accs.token_program_2022.to_account_info())
+    } else {
+        err!(DexError::InvalidRoute)
+    }
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+
+use crate::SwapExactIn;
+use crate::errors::DexError;
+use crate::instructions::swap;
+
+/// Rejects once the cluster clock passes `deadline_ts` (unix seconds, inclusive)
+/// or the current slot passes `max_slot` (inclusive). `None` skips that bound.
+pub fn require_not_expired(deadline_ts: Option<i64>, max_slot: Option<u64>) -> Result<()> {
+    let clock = Clock::get()?;
+
+    if let Some(deadline) = deadline_ts {
+        if clock.unix_timestamp > deadline {
+            return err!(DexError::Expired);
+        }
+    }
+    if let Some(max_slot) = max_slot {
+        if clock.slot > max_slot {
+            return err!(DexError::Expired);
+        }
+    }
+    Ok(())
+}
+
+pub fn handler(
+    ctx: Context<SwapExactIn>,
+    amount_in: u64,
+    min_out: u64,
+    deadline_ts: Option<i64>,
+    max_slot: Option<u64>,
+) -> Result<()> {
+    require_not_expired(deadline_ts, max_slot)?;
+    swap::handler(ctx, amount_in, min_out)
+}
@@ -0,0 +1,34 @@
+use anchor_lang::prelude::*;
+use common_contracts::utils::now_ts;
+
+use crate::{ReservesSynced, SyncReserves};
+use crate::errors::DexError;
+use crate::assert_treasury_signer;
+
+/// Treasury-only: adopt the actual vault balances as the stored reserves
+/// (e.g. after seeding the vaults directly, or to absorb a donation into the price).
+pub fn handler(ctx: Context<SyncReserves>) -> Result<()> {
+    let pair = &mut ctx.accounts.pair;
+
+    assert_treasury_signer(pair, ctx.accounts.treasury_signer.key())?;
+
+    // vaults are short by the borrowed amount until flash_repay
+    if pair.flash_loan_active {
+        return err!(DexError::FlashLoanActive);
+    }
+
+    // TWAP accrues the old price up to now before the reserves jump
+    let (reserve_knite, reserve_sub) = (pair.reserve_knite, pair.reserve_sub);
+    pair.update_oracle(reserve_knite, reserve_sub, now_ts()?)?;
+
+    pair.reserve_knite = ctx.accounts.vault_knite.amount;
+    pair.reserve_sub = ctx.accounts.vault_sub.amount;
+
+    emit!(ReservesSynced {
+        pair: pair.key(),
+        reserve_knite: pair.reserve_knite,
+        reserve_sub: pair.reserve_sub,
+    });
+
+    Ok(())
+}
@@ -4,13 +4,20 @@
 #![deny(unsafe_code)]
 
 use anchor_lang::prelude::*;
-use anchor_spl::associated_token::get_associated_token_address;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::associated_token::get_associated_token_address_with_program_id;
+use anchor_spl::token::Token;
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
+use referral_program_contracts::program::ReferralProgramContracts;
+use referral_program_contracts::ReferralConfig;
 
+pub mod clmm;
+pub mod curve;
 pub mod errors;
 pub mod instructions;
 pub mod math;
 pub mod state;
+pub mod tokens;
 
 pub use errors::*;
 pub use math::*;
@@ -28,7 +35,10 @@ pub struct PairInitialized {
     pub mint_sub: Pubkey,
     pub vault_knite: Pubkey,
     pub vault_sub: Pubkey,
+    pub lp_mint: Pubkey,
     pub fee_bps: u16,
+    pub curve_type: CurveType,
+    pub amp: u64,
 }
 
 #[event]
@@ -39,7 +49,352 @@ pub struct SwapExecuted {
     pub mint_out: Pubkey,
     pub amount_in: u64,
     pub amount_out: u64,
+    /// total kNite fee = fee_lp + fee_treasury + fee_burn + fee_referral
     pub fee_knite: u64,
+    pub fee_lp: u64,
+    pub fee_treasury: u64,
+    pub fee_burn: u64,
+    pub fee_referral: u64,
+}
+
+#[event]
+pub struct PairConfigUpdated {
+    pub pair: Pubkey,
+    pub treasury: Pubkey,
+    pub enabled: bool,
+    pub swap_fee_bps: u16,
+    pub max_price_impact_bps: u16,
+    pub max_slot_volume_knite: u64,
+    pub referral_share_bps: u16,
+    pub flash_fee_bps: u16,
+    pub fee_split: FeeSplit,
+    pub batch_slots: u64,
+}
+
+#[event]
+pub struct CircuitBreakerTripped {
+    pub pair: Pubkey,
+    pub slot: u64,
+    pub slot_volume_knite: u64,
+    pub max_slot_volume_knite: u64,
+}
+
+impl PairConfigUpdated {
+    /// Snapshot of the pair's current config, emitted by every treasury setter.
+    pub fn from_pair(pair: &Account<Pair>) -> Self {
+        Self {
+            pair: pair.key(),
+            treasury: pair.treasury,
+            enabled: pair.enabled,
+            swap_fee_bps: pair.swap_fee_bps,
+            max_price_impact_bps: pair.max_price_impact_bps,
+            max_slot_volume_knite: pair.max_slot_volume_knite,
+            referral_share_bps: pair.referral_share_bps,
+            flash_fee_bps: pair.flash_fee_bps,
+            fee_split: pair.fee_split,
+            batch_slots: pair.batch_slots,
+        }
+    }
+}
+
+#[event]
+pub struct RouteExecuted {
+    pub user: Pubkey,
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub hops: u8,
+}
+
+#[event]
+pub struct LiquidityAdded {
+    pub pair: Pubkey,
+    pub user: Pubkey,
+    pub amount_knite: u64,
+    pub amount_sub: u64,
+    pub lp_minted: u64,
+}
+
+#[event]
+pub struct LiquidityRemoved {
+    pub pair: Pubkey,
+    pub user: Pubkey,
+    pub amount_knite: u64,
+    pub amount_sub: u64,
+    pub lp_burned: u64,
+}
+
+#[event]
+pub struct ReservesSynced {
+    pub pair: Pubkey,
+    pub reserve_knite: u64,
+    pub reserve_sub: u64,
+}
+
+#[event]
+pub struct Skimmed {
+    pub pair: Pubkey,
+    pub amount_knite: u64,
+    pub amount_sub: u64,
+}
+
+#[event]
+pub struct ReferralFeePaid {
+    pub pair: Pubkey,
+    pub user: Pubkey,
+    pub referrer: Pubkey,
+    pub amount_knite: u64,
+}
+
+#[event]
+pub struct FlashLoan {
+    pub pair: Pubkey,
+    pub borrower: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub fee: u64,
+}
+
+#[event]
+pub struct GenericPairInitialized {
+    pub pair: Pubkey,
+    pub treasury: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+    pub lp_mint: Pubkey,
+    pub fee_bps: u16,
+    pub fee_mode: FeeMode,
+}
+
+#[event]
+pub struct GenericSwapExecuted {
+    pub pair: Pubkey,
+    pub user: Pubkey,
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub fee_mint: Pubkey,
+    pub fee: u64,
+}
+
+#[event]
+pub struct GenericLiquidityAdded {
+    pub pair: Pubkey,
+    pub user: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_minted: u64,
+}
+
+#[event]
+pub struct GenericLiquidityRemoved {
+    pub pair: Pubkey,
+    pub user: Pubkey,
+    pub amount_a: u64,
+    pub amount_b: u64,
+    pub lp_burned: u64,
+}
+
+#[event]
+pub struct AmpRampUpdated {
+    pub pair: Pubkey,
+    pub amp_initial: u64,
+    pub amp_target: u64,
+    pub ramp_start_ts: i64,
+    pub ramp_end_ts: i64,
+}
+
+#[event]
+pub struct ClPoolInitialized {
+    pub pool: Pubkey,
+    pub treasury: Pubkey,
+    pub mint_knite: Pubkey,
+    pub mint_sub: Pubkey,
+    pub vault_knite: Pubkey,
+    pub vault_sub: Pubkey,
+    pub tick_spacing: u16,
+    pub fee_bps: u16,
+    pub sqrt_price_q64: u128,
+    pub tick: i32,
+    pub min_position_liquidity: u128,
+}
+
+#[event]
+pub struct ClLiquidityChanged {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    /// positive on add, negative on remove
+    pub liquidity_delta: i128,
+    pub amount_knite: u64,
+    pub amount_sub: u64,
+}
+
+#[event]
+pub struct ClFeesCollected {
+    pub pool: Pubkey,
+    pub position: Pubkey,
+    pub owner: Pubkey,
+    pub amount_knite: u64,
+    pub amount_sub: u64,
+}
+
+#[event]
+pub struct ClSwapExecuted {
+    pub pool: Pubkey,
+    pub user: Pubkey,
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    /// total fee in mint_in; `protocol_fee` of it went to the treasury ATA
+    pub fee: u64,
+    pub protocol_fee: u64,
+    pub sqrt_price_q64: u128,
+    pub tick: i32,
+}
+
+#[event]
+pub struct BatchOpened {
+    pub batch: Pubkey,
+    pub pair: Pubkey,
+    pub epoch: u64,
+    pub end_slot: u64,
+}
+
+#[event]
+pub struct BatchCommitted {
+    pub batch: Pubkey,
+    pub owner: Pubkey,
+    pub is_knite_in: bool,
+    pub amount_in: u64,
+    pub min_out: u64,
+}
+
+#[event]
+pub struct BatchSettled {
+    pub batch: Pubkey,
+    pub pair: Pubkey,
+    /// filled input per side and the output it clears for (sub for kNite, kNite for sub)
+    pub fill_knite_in: u64,
+    pub fill_knite_out: u64,
+    pub fill_sub_in: u64,
+    pub fill_sub_out: u64,
+    /// imbalance routed through the pool (0 when both sides matched exactly)
+    pub net_is_knite_in: bool,
+    pub net_in: u64,
+    pub net_out: u64,
+    /// commits left unfilled because the clearing price missed their min_out
+    pub unfilled: u8,
+}
+
+#[event]
+pub struct BatchClaimed {
+    pub batch: Pubkey,
+    pub owner: Pubkey,
+    pub filled: bool,
+    pub amount_out: u64,
+    pub amount_returned: u64,
+}
+
+#[event]
+pub struct BatchWithdrawn {
+    pub batch: Pubkey,
+    pub owner: Pubkey,
+    pub amount_returned: u64,
+}
+
+#[event]
+pub struct PairClosed {
+    pub pair: Pubkey,
+    pub treasury: Pubkey,
+    /// LP mint left behind; its outstanding supply no longer redeems for anything
+    pub lp_mint: Pubkey,
+    pub lp_holders: u16,
+    pub knite_to_lps: u64,
+    pub sub_to_lps: u64,
+    pub knite_to_treasury: u64,
+    pub sub_to_treasury: u64,
+}
+
+#[event]
+pub struct PairMigrated {
+    pub pair: Pubkey,
+    pub from_version: u8,
+    pub to_version: u8,
+}
+
+#[event]
+pub struct LaunchPhaseUpdated {
+    pub pair: Pubkey,
+    pub trading_starts_at: i64,
+    pub launch_ends_at: i64,
+    pub max_buy_per_slot: u64,
+    pub cooldown_secs: u32,
+}
+
+#[event]
+pub struct OrderPlaced {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub pair: Pubkey,
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub min_out: u64,
+}
+
+#[event]
+pub struct OrderCancelled {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub amount_returned: u64,
+}
+
+#[event]
+pub struct DcaOpened {
+    pub dca: Pubkey,
+    pub owner: Pubkey,
+    pub pair: Pubkey,
+    pub amount_total: u64,
+    pub amount_per_cycle: u64,
+    pub interval_secs: i64,
+    pub min_price_q64: u128,
+}
+
+#[event]
+pub struct DcaCycleExecuted {
+    pub dca: Pubkey,
+    pub owner: Pubkey,
+    pub pair: Pubkey,
+    pub cycle: u64,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub remaining: u64,
+}
+
+#[event]
+pub struct DcaClosed {
+    pub dca: Pubkey,
+    pub owner: Pubkey,
+    pub amount_returned: u64,
+}
+
+#[event]
+pub struct OrderFilled {
+    pub order: Pubkey,
+    pub owner: Pubkey,
+    pub pair: Pubkey,
+    pub cranker: Pubkey,
+    pub amount_in: u64,
+    pub amount_out: u64,
+    pub crank_fee: u64,
 }
 
 
@@ -50,8 +405,24 @@ pub struct InitializePair<'info> {
     #[account(mut)]
     pub treasury_signer: Signer<'info>,
 
-    pub mint_knite: Account<'info, Mint>,
-    pub mint_sub: Account<'info, Mint>,
+    /// Every new pair takes the next index
+    #[account(mut, seeds = [SEED_PAIR_REGISTRY], bump = registry.bump)]
+    pub registry: Box<Account<'info, PairRegistry>>,
+
+    /// Entry for the new pair, at the registry's next index
+    #[account(
+        init,
+        payer = treasury_signer,
+        space = PairRegistryEntry::space(),
+        seeds = [SEED_PAIR_REGISTRY_ENTRY, &registry.pair_count.to_le_bytes()],
+        bump
+    )]
+    pub registry_entry: Box<Account<'info, PairRegistryEntry>>,
+
+    #[account(mint::token_program = token_program_knite)]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program = token_program_sub)]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
         init,
@@ -60,7 +431,7 @@ pub struct InitializePair<'info> {
         seeds = [SEED_PAIR, treasury.as_ref(), mint_knite.key().as_ref(), mint_sub.key().as_ref()],
         bump
     )]
-    pub pair: Account<'info, Pair>,
+    pub pair: Box<Account<'info, Pair>>,
 
     /// Dex vaults are created here and OWNED by Pair PDA (authority = pair PDA).
     #[account(
@@ -68,21 +439,39 @@ pub struct InitializePair<'info> {
         payer = treasury_signer,
         token::mint = mint_knite,
         token::authority = pair,
+        token::token_program = token_program_knite,
         seeds = [SEED_VAULT_KNITE, pair.key().as_ref()],
         bump
     )]
-    pub vault_knite: Account<'info, TokenAccount>,
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         init,
         payer = treasury_signer,
         token::mint = mint_sub,
         token::authority = pair,
+        token::token_program = token_program_sub,
         seeds = [SEED_VAULT_SUB, pair.key().as_ref()],
         bump
     )]
-    pub vault_sub: Account<'info, TokenAccount>,
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// LP share mint (legacy SPL Token), mint authority = pair PDA.
+    #[account(
+        init,
+        payer = treasury_signer,
+        mint::decimals = mint_knite.decimals,
+        mint::authority = pair,
+        mint::token_program = token_program,
+        seeds = [SEED_LP_MINT, pair.key().as_ref()],
+        bump
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
 
+    /// Programs owning each pair mint (SPL Token or Token-2022)
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+    /// LP mint program
     pub token_program: Program<'info, Token>,
     pub system_program: Program<'info, System>,
     pub rent: Sysvar<'info, Rent>,
@@ -93,14 +482,16 @@ pub struct SwapExactIn<'info> {
     #[account(mut)]
     pub user: Signer<'info>,
 
-    pub mint_in: Account<'info, Mint>,
-    pub mint_out: Account<'info, Mint>,
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_out: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
+        mut,
         seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
-        bump = pair.bump
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
     )]
-    pub pair: Account<'info, Pair>,
+    pub pair: Box<Account<'info, Pair>>,
 
 
     #[account(
@@ -108,61 +499,2040 @@ pub struct SwapExactIn<'info> {
         constraint = user_ata_in.owner == user.key() @ DexError::Unauthorized,
         constraint = user_ata_in.mint == mint_in.key() @ DexError::MintMismatch
     )]
-    pub user_ata_in: Box<Account<'info, TokenAccount>>,
+    pub user_ata_in: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         constraint = user_ata_out.owner == user.key() @ DexError::Unauthorized,
         constraint = user_ata_out.mint == mint_out.key() @ DexError::MintMismatch
     )]
-    pub user_ata_out: Box<Account<'info, TokenAccount>>,
+    pub user_ata_out: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
         constraint = vault_knite.mint == pair.mint_knite @ DexError::MintMismatch,
-        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized
+        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_knite
     )]
-    pub vault_knite: Box<Account<'info, TokenAccount>>,
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
 
     #[account(
         mut,
         constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
         constraint = vault_sub.mint == pair.mint_sub @ DexError::MintMismatch,
-        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized
+        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_sub
     )]
-    pub vault_sub: Box<Account<'info, TokenAccount>>,
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
 
     /// Treasury kNite ATA (strictly validated in handler)
     #[account(
         mut,
         constraint = treasury_knite_ata.mint == pair.mint_knite @ DexError::MintMismatch
     )]
-    pub treasury_knite_ata: Box<Account<'info, TokenAccount>>,
+    pub treasury_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+
+    // ---- optional referral accounts (all omitted for a plain swap) ----
+    /// CHECK: referral-program RefereeState PDA of `user`; validated in handler.
+    /// Still empty on the first referred swap, which records it via CPI.
+    #[account(mut)]
+    pub referee: Option<UncheckedAccount<'info>>,
+
+    /// Referrer kNite account receiving the referral share of `fee_knite`
+    #[account(
+        mut,
+        constraint = referrer_knite_ata.mint == pair.mint_knite @ DexError::MintMismatch
+    )]
+    pub referrer_knite_ata: Option<Box<InterfaceAccount<'info, TokenAccount>>>,
+
+    /// Owner of `referrer_knite_ata`; must sign the first referred swap, so the swapper
+    /// cannot name a referrer (e.g. a second wallet) on their own
+    pub referrer: Option<Signer<'info>>,
+
+    /// Only needed on the first referred swap (record_first_swap CPI)
+    pub referral_config: Option<Box<Account<'info, ReferralConfig>>>,
+    pub referral_program: Option<Program<'info, ReferralProgramContracts>>,
+
+    /// CHECK: this program's executable account; referral-program checks it
+    /// against `allowed_dex_program`.
+    #[account(address = crate::ID)]
+    pub dex_program: Option<UncheckedAccount<'info>>,
+    pub system_program: Option<Program<'info, System>>,
+
+    /// The user's LaunchCooldown; required while the pair is in its launch phase
+    #[account(
+        mut,
+        seeds = [SEED_LAUNCH_COOLDOWN, pair.key().as_ref(), user.key().as_ref()],
+        bump = launch_cooldown.bump
+    )]
+    pub launch_cooldown: Option<Box<Account<'info, LaunchCooldown>>>,
+
+    /// Writable kNite mint; only needed (and only write-locked) when the pair's fee split burns
+    #[account(
+        mut,
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub burn_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
 }
 
+/// Exact-in swap on a kNite/WSOL pair paid or received in lamports. `temp_wsol` is created,
+/// used as the user's WSOL account and closed again within the instruction (rent refunded).
+#[derive(Accounts)]
+pub struct SwapSol<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
 
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.mint_sub == anchor_spl::token::spl_token::native_mint::ID @ DexError::TokenNotAllowed,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
 
-#[program]
-pub mod dex_core_contracts {
-    use super::*;
+    #[account(
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(address = pair.mint_sub @ DexError::MintMismatch)]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
 
-    pub fn initialize_pair(ctx: Context<InitializePair>, treasury: Pubkey) -> Result<()> {
-        instructions::initialize_pair::handler(ctx, treasury)
-    }
+    #[account(
+        mut,
+        constraint = user_knite_ata.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_knite_ata.mint == pair.mint_knite @ DexError::MintMismatch
+    )]
+    pub user_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub fn swap_exact_in(ctx: Context<SwapExactIn>, amount_in: u64, min_out: u64) -> Result<()> {
-        instructions::swap::handler(ctx, amount_in, min_out)
-    }
+    #[account(
+        init,
+        payer = user,
+        token::mint = mint_sub,
+        token::authority = user,
+        token::token_program = token_program_sub,
+        seeds = [SEED_TEMP_WSOL, user.key().as_ref()],
+        bump
+    )]
+    pub temp_wsol: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
+        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_knite
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
+        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_sub
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury kNite ATA (strictly validated in handler)
+    #[account(
+        mut,
+        constraint = treasury_knite_ata.mint == pair.mint_knite @ DexError::MintMismatch
+    )]
+    pub treasury_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    /// native WSOL lives under the legacy token program
+    pub token_program_sub: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+
+    /// Writable kNite mint; only needed (and only write-locked) when the pair's fee split burns
+    #[account(
+        mut,
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub burn_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
 }
 
+#[derive(Accounts)]
+pub struct SwapExactOut<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_out: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_in.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_in.mint == mint_in.key() @ DexError::MintMismatch
+    )]
+    pub user_ata_in: Box<InterfaceAccount<'info, TokenAccount>>,
 
+    #[account(
+        mut,
+        constraint = user_ata_out.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_out.mint == mint_out.key() @ DexError::MintMismatch
+    )]
+    pub user_ata_out: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
+        constraint = vault_knite.mint == pair.mint_knite @ DexError::MintMismatch,
+        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_knite
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
+        constraint = vault_sub.mint == pair.mint_sub @ DexError::MintMismatch,
+        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_sub
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury kNite ATA (strictly validated in handler)
+    #[account(
+        mut,
+        constraint = treasury_knite_ata.mint == pair.mint_knite @ DexError::MintMismatch
+    )]
+    pub treasury_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+
+    /// Writable kNite mint; only needed (and only write-locked) when the pair's fee split burns
+    #[account(
+        mut,
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub burn_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+}
+
+
+/// Hop accounts are passed in `remaining_accounts` (see instructions::swap_route).
+#[derive(Accounts)]
+pub struct SwapRoute<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = user_ata_in.owner == user.key() @ DexError::Unauthorized
+    )]
+    pub user_ata_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Each hop picks the program owning its mints
+    pub token_program: Program<'info, Token>,
+    pub token_program_2022: Program<'info, Token2022>,
+}
+
+#[derive(Accounts)]
+pub struct AddLiquidity<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        mut,
+        constraint = lp_mint.key() == pair.lp_mint @ DexError::MintMismatch
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pair.mint_knite @ DexError::MintMismatch)]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pair.mint_sub @ DexError::MintMismatch)]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_knite.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_knite.mint == pair.mint_knite @ DexError::MintMismatch
+    )]
+    pub user_ata_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_sub.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_sub.mint == pair.mint_sub @ DexError::MintMismatch
+    )]
+    pub user_ata_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_lp.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_lp.mint == pair.lp_mint @ DexError::MintMismatch
+    )]
+    pub user_ata_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
+        constraint = vault_knite.mint == pair.mint_knite @ DexError::MintMismatch,
+        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_knite
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
+        constraint = vault_sub.mint == pair.mint_sub @ DexError::MintMismatch,
+        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_sub
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+    /// LP mint program
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct RemoveLiquidity<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        mut,
+        constraint = lp_mint.key() == pair.lp_mint @ DexError::MintMismatch
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pair.mint_knite @ DexError::MintMismatch)]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pair.mint_sub @ DexError::MintMismatch)]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_knite.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_knite.mint == pair.mint_knite @ DexError::MintMismatch
+    )]
+    pub user_ata_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_sub.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_sub.mint == pair.mint_sub @ DexError::MintMismatch
+    )]
+    pub user_ata_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_lp.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_lp.mint == pair.lp_mint @ DexError::MintMismatch
+    )]
+    pub user_ata_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
+        constraint = vault_knite.mint == pair.mint_knite @ DexError::MintMismatch,
+        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_knite
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
+        constraint = vault_sub.mint == pair.mint_sub @ DexError::MintMismatch,
+        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_sub
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+    /// LP mint program
+    pub token_program: Program<'info, Token>,
+}
+
+
+#[derive(Accounts)]
+pub struct UpdatePairConfig<'info> {
+    /// Pair treasury; in production the treasury-multisig PDA signing via CPI
+    pub treasury_signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.treasury == treasury_signer.key() @ DexError::Unauthorized,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+}
+
+/// Treasury-only, on a disabled pair. remaining_accounts, per LP holder (CLOSE_PAIR_LP_ACCOUNTS,
+/// ascending LP account keys, covering the whole LP supply):
+/// [lp token account, holder kNite account(mut), holder sub account(mut)]
+///
+/// Closing is permanent: the LP mint PDA outlives the pair (an SPL mint with supply cannot be
+/// closed), so `initialize_pair` for the same treasury and mints fails afterwards.
+#[derive(Accounts)]
+pub struct ClosePair<'info> {
+    /// Pair treasury; receives the rent of the pair and both vaults
+    #[account(mut)]
+    pub treasury_signer: Signer<'info>,
+
+    /// The pair's registry entry, closed to the treasury
+    #[account(
+        mut,
+        close = treasury_signer,
+        constraint = registry_entry.pair == pair.key() @ DexError::PairNotRegistered
+    )]
+    pub registry_entry: Box<Account<'info, PairRegistryEntry>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.treasury == treasury_signer.key() @ DexError::Unauthorized,
+        close = treasury_signer,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    /// Writable: Token-2022 transfer fees withheld in the vaults are harvested to the mints
+    #[account(
+        mut,
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mut,
+        address = pair.mint_sub @ DexError::MintMismatch,
+        mint::token_program = token_program_sub
+    )]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pair.lp_mint @ DexError::MintMismatch)]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
+        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_knite
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
+        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_sub
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury ATAs of both mints (strictly validated in handler)
+    #[account(mut)]
+    pub treasury_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut)]
+    pub treasury_sub_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Permissionless: upgrades a Pair account to PAIR_VERSION in place (payer tops up rent
+/// and, for a version 0 pair, pays for the LP mint it never had).
+#[derive(Accounts)]
+pub struct MigratePair<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    /// CHECK: Pair of any layout version, so not deserialized here; owner checked,
+    /// discriminator and size checked in handler.
+    #[account(mut, owner = crate::ID @ DexError::Unauthorized)]
+    pub pair: UncheckedAccount<'info>,
+
+    /// Pair mints and vaults; checked against the pair in the handler
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// CHECK: LP mint PDA; created by the handler when migrating a version 0 pair
+    #[account(mut, seeds = [SEED_LP_MINT, pair.key().as_ref()], bump)]
+    pub lp_mint: UncheckedAccount<'info>,
+
+    /// LP mint program
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SyncReserves<'info> {
+    /// Pair treasury; in production the treasury-multisig PDA signing via CPI
+    pub treasury_signer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.treasury == treasury_signer.key() @ DexError::Unauthorized,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch)]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch)]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+}
+
+/// Permissionless: surplus always goes to the pair treasury ATAs.
+#[derive(Accounts)]
+pub struct Skim<'info> {
+    #[account(
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(address = pair.mint_knite @ DexError::MintMismatch)]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pair.mint_sub @ DexError::MintMismatch)]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
+        token::token_program = token_program_knite
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
+        token::token_program = token_program_sub
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury kNite ATA (strictly validated in handler)
+    #[account(mut)]
+    pub treasury_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury sub ATA (strictly validated in handler)
+    #[account(mut)]
+    pub treasury_sub_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+}
+
+/// Shared by `flash_borrow` and `flash_repay`; one side (kNite or sub) per loan.
+/// `flash_borrow` looks for `pair` at index FLASH_REPAY_PAIR_INDEX of the repay instruction.
+#[derive(Accounts)]
+pub struct PairFlashLoan<'info> {
+    pub borrower: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        constraint = mint.key() == pair.mint_knite || mint.key() == pair.mint_sub @ DexError::TokenNotAllowed,
+        mint::token_program = token_program
+    )]
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = vault.key() == pair.vault_knite || vault.key() == pair.vault_sub @ DexError::MintMismatch,
+        constraint = vault.mint == mint.key() @ DexError::MintMismatch,
+        constraint = vault.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program
+    )]
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = borrower_ata.owner == borrower.key() @ DexError::Unauthorized,
+        constraint = borrower_ata.mint == mint.key() @ DexError::MintMismatch
+    )]
+    pub borrower_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury ATA of `mint`, receives the fee (strictly validated in handler)
+    #[account(
+        mut,
+        constraint = treasury_ata.mint == mint.key() @ DexError::MintMismatch
+    )]
+    pub treasury_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program: Interface<'info, TokenInterface>,
+
+    /// CHECK: instructions sysvar (address checked)
+    #[account(address = anchor_lang::solana_program::sysvar::instructions::ID)]
+    pub instructions: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct InitializeRegistry<'info> {
+    /// Program upgrade authority; only the deployer can create the registry
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(constraint = program.programdata_address()? == Some(program_data.key()) @ DexError::Unauthorized)]
+    pub program: Program<'info, crate::program::DexCoreContracts>,
+
+    #[account(constraint = program_data.upgrade_authority_address == Some(payer.key()) @ DexError::Unauthorized)]
+    pub program_data: Account<'info, ProgramData>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = PairRegistry::space(),
+        seeds = [SEED_PAIR_REGISTRY],
+        bump
+    )]
+    pub registry: Box<Account<'info, PairRegistry>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Factory: any two mints, passed in canonical order (mint_a < mint_b).
+#[derive(Accounts)]
+pub struct InitializeGenericPair<'info> {
+    /// Registry authority; in production the treasury-multisig PDA signing via CPI
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR_REGISTRY],
+        bump = registry.bump,
+        constraint = registry.authority == authority.key() @ DexError::Unauthorized
+    )]
+    pub registry: Box<Account<'info, PairRegistry>>,
+
+    /// Entry for the new pair, at the registry's next index
+    #[account(
+        init,
+        payer = authority,
+        space = PairRegistryEntry::space(),
+        seeds = [SEED_PAIR_REGISTRY_ENTRY, &registry.pair_count.to_le_bytes()],
+        bump
+    )]
+    pub registry_entry: Box<Account<'info, PairRegistryEntry>>,
+
+    #[account(mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        mint::token_program = token_program_b,
+        constraint = mint_a.key() < mint_b.key() @ DexError::MintsNotCanonical
+    )]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = GenericPair::space(),
+        seeds = [SEED_GENERIC_PAIR, mint_a.key().as_ref(), mint_b.key().as_ref()],
+        bump
+    )]
+    pub pair: Box<Account<'info, GenericPair>>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint_a,
+        token::authority = pair,
+        token::token_program = token_program_a,
+        seeds = [SEED_VAULT_A, pair.key().as_ref()],
+        bump
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = authority,
+        token::mint = mint_b,
+        token::authority = pair,
+        token::token_program = token_program_b,
+        seeds = [SEED_VAULT_B, pair.key().as_ref()],
+        bump
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// LP share mint (legacy SPL Token), mint authority = pair PDA.
+    #[account(
+        init,
+        payer = authority,
+        mint::decimals = mint_a.decimals,
+        mint::authority = pair,
+        mint::token_program = token_program,
+        seeds = [SEED_LP_MINT, pair.key().as_ref()],
+        bump
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// LP mint program
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Direction is taken from `user_ata_in.mint` (mint_a or mint_b).
+#[derive(Accounts)]
+pub struct SwapGeneric<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GENERIC_PAIR, pair.mint_a.as_ref(), pair.mint_b.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Box<Account<'info, GenericPair>>,
+
+    #[account(address = pair.mint_a @ DexError::MintMismatch, mint::token_program = token_program_a)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pair.mint_b @ DexError::MintMismatch, mint::token_program = token_program_b)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_in.owner == user.key() @ DexError::Unauthorized
+    )]
+    pub user_ata_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_out.owner == user.key() @ DexError::Unauthorized
+    )]
+    pub user_ata_out: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_a.key() == pair.vault_a @ DexError::MintMismatch,
+        constraint = vault_a.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_a
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_b.key() == pair.vault_b @ DexError::MintMismatch,
+        constraint = vault_b.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_b
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury ATA of the fee mint (strictly validated in handler)
+    #[account(mut)]
+    pub treasury_fee_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+}
+
+/// Shared by `add_generic_liquidity` and `remove_generic_liquidity`.
+#[derive(Accounts)]
+pub struct GenericLiquidity<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_GENERIC_PAIR, pair.mint_a.as_ref(), pair.mint_b.as_ref()],
+        bump = pair.bump
+    )]
+    pub pair: Box<Account<'info, GenericPair>>,
+
+    #[account(
+        mut,
+        constraint = lp_mint.key() == pair.lp_mint @ DexError::MintMismatch
+    )]
+    pub lp_mint: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pair.mint_a @ DexError::MintMismatch)]
+    pub mint_a: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(address = pair.mint_b @ DexError::MintMismatch)]
+    pub mint_b: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_a.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_a.mint == pair.mint_a @ DexError::MintMismatch
+    )]
+    pub user_ata_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_b.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_b.mint == pair.mint_b @ DexError::MintMismatch
+    )]
+    pub user_ata_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = user_ata_lp.owner == user.key() @ DexError::Unauthorized,
+        constraint = user_ata_lp.mint == pair.lp_mint @ DexError::MintMismatch
+    )]
+    pub user_ata_lp: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_a.key() == pair.vault_a @ DexError::MintMismatch,
+        constraint = vault_a.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_a
+    )]
+    pub vault_a: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_b.key() == pair.vault_b @ DexError::MintMismatch,
+        constraint = vault_b.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_b
+    )]
+    pub vault_b: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    /// LP mint program
+    pub token_program: Program<'info, Token>,
+}
+
+/// Escrows `amount_in` of mint_in (one side of `pair`) under a fresh order PDA.
+#[derive(Accounts)]
+#[instruction(order_id: u64)]
+pub struct PlaceOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(mint::token_program = token_program_in)]
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_out: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = Order::space(),
+        seeds = [SEED_ORDER, pair.key().as_ref(), owner.key().as_ref(), &order_id.to_le_bytes()],
+        bump
+    )]
+    pub order: Box<Account<'info, Order>>,
+
+    #[account(
+        init,
+        payer = owner,
+        token::mint = mint_in,
+        token::authority = order,
+        token::token_program = token_program_in,
+        seeds = [SEED_ORDER_ESCROW, order.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_ata_in.owner == owner.key() @ DexError::Unauthorized,
+        constraint = owner_ata_in.mint == mint_in.key() @ DexError::MintMismatch
+    )]
+    pub owner_ata_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_in: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Owner-only: returns the escrow to `owner_ata_in` and closes order + escrow (rent to owner).
+#[derive(Accounts)]
+pub struct CancelOrder<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_ORDER, order.pair.as_ref(), owner.key().as_ref(), &order.order_id.to_le_bytes()],
+        bump = order.bump,
+        has_one = owner @ DexError::Unauthorized,
+        close = owner
+    )]
+    pub order: Box<Account<'info, Order>>,
+
+    #[account(
+        address = order.mint_in @ DexError::MintMismatch,
+        mint::token_program = token_program_in
+    )]
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = order.escrow @ DexError::InvalidOrder)]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_ata_in.owner == owner.key() @ DexError::Unauthorized,
+        constraint = owner_ata_in.mint == mint_in.key() @ DexError::MintMismatch
+    )]
+    pub owner_ata_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_in: Interface<'info, TokenInterface>,
+}
+
+/// Permissionless crank. remaining_accounts, per order (ORDER_FILL_ACCOUNTS, in order):
+/// [order(mut), escrow(mut), owner(mut), owner_ata_out(mut), cranker_ata_in(mut)]
+#[derive(Accounts)]
+pub struct FillOrders<'info> {
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        address = pair.mint_sub @ DexError::MintMismatch,
+        mint::token_program = token_program_sub
+    )]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
+        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_knite
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
+        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_sub
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury kNite ATA (strictly validated in handler)
+    #[account(
+        mut,
+        constraint = treasury_knite_ata.mint == pair.mint_knite @ DexError::MintMismatch
+    )]
+    pub treasury_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+
+    /// Writable kNite mint; only needed (and only write-locked) when the pair's fee split burns
+    #[account(
+        mut,
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub burn_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+}
+
+/// Escrows `amount_total` kNite under a fresh DCA PDA for recurring buys of the pair's sub.
+#[derive(Accounts)]
+#[instruction(dca_id: u64)]
+pub struct OpenDca<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = DcaVault::space(),
+        seeds = [SEED_DCA, pair.key().as_ref(), owner.key().as_ref(), &dca_id.to_le_bytes()],
+        bump
+    )]
+    pub dca: Box<Account<'info, DcaVault>>,
+
+    #[account(
+        init,
+        payer = owner,
+        token::mint = mint_knite,
+        token::authority = dca,
+        token::token_program = token_program_knite,
+        seeds = [SEED_DCA_ESCROW, dca.key().as_ref()],
+        bump
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_knite_ata.owner == owner.key() @ DexError::Unauthorized,
+        constraint = owner_knite_ata.mint == mint_knite.key() @ DexError::MintMismatch
+    )]
+    pub owner_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Permissionless: swaps one due cycle of `dca` through `pair`, output to the owner.
+#[derive(Accounts)]
+pub struct ExecuteDcaCycle<'info> {
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_DCA, pair.key().as_ref(), dca.owner.as_ref(), &dca.dca_id.to_le_bytes()],
+        bump = dca.bump
+    )]
+    pub dca: Box<Account<'info, DcaVault>>,
+
+    #[account(mut, address = dca.escrow @ DexError::InvalidDcaEscrow)]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_sub_ata.owner == dca.owner @ DexError::Unauthorized,
+        constraint = owner_sub_ata.mint == pair.mint_sub @ DexError::MintMismatch
+    )]
+    pub owner_sub_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        address = pair.mint_sub @ DexError::MintMismatch,
+        mint::token_program = token_program_sub
+    )]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
+        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_knite
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
+        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_sub
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury kNite ATA (strictly validated in handler)
+    #[account(
+        mut,
+        constraint = treasury_knite_ata.mint == pair.mint_knite @ DexError::MintMismatch
+    )]
+    pub treasury_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+
+    /// Writable kNite mint; only needed (and only write-locked) when the pair's fee split burns
+    #[account(
+        mut,
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub burn_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+}
+
+/// Owner-only: returns the unspent kNite and closes the DCA + escrow (rent to owner).
+#[derive(Accounts)]
+pub struct CloseDca<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_DCA, dca.pair.as_ref(), owner.key().as_ref(), &dca.dca_id.to_le_bytes()],
+        bump = dca.bump,
+        has_one = owner @ DexError::Unauthorized,
+        close = owner
+    )]
+    pub dca: Box<Account<'info, DcaVault>>,
+
+    #[account(mint::token_program = token_program_knite)]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        mut,
+        address = dca.escrow @ DexError::InvalidDcaEscrow,
+        constraint = escrow.mint == mint_knite.key() @ DexError::MintMismatch
+    )]
+    pub escrow: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_knite_ata.owner == owner.key() @ DexError::Unauthorized,
+        constraint = owner_knite_ata.mint == mint_knite.key() @ DexError::MintMismatch
+    )]
+    pub owner_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+#[instruction(treasury: Pubkey)]
+pub struct InitializeClPool<'info> {
+    #[account(mut)]
+    pub treasury_signer: Signer<'info>,
+
+    /// Every new pair takes the next index
+    #[account(mut, seeds = [SEED_PAIR_REGISTRY], bump = registry.bump)]
+    pub registry: Box<Account<'info, PairRegistry>>,
+
+    /// Entry for the new pair, at the registry's next index
+    #[account(
+        init,
+        payer = treasury_signer,
+        space = PairRegistryEntry::space(),
+        seeds = [SEED_PAIR_REGISTRY_ENTRY, &registry.pair_count.to_le_bytes()],
+        bump
+    )]
+    pub registry_entry: Box<Account<'info, PairRegistryEntry>>,
+
+    #[account(mint::token_program = token_program_knite)]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(mint::token_program = token_program_sub)]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = treasury_signer,
+        space = ClPool::space(),
+        seeds = [SEED_CL_POOL, treasury.as_ref(), mint_knite.key().as_ref(), mint_sub.key().as_ref()],
+        bump
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    #[account(
+        init,
+        payer = treasury_signer,
+        token::mint = mint_knite,
+        token::authority = pool,
+        token::token_program = token_program_knite,
+        seeds = [SEED_VAULT_KNITE, pool.key().as_ref()],
+        bump
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = treasury_signer,
+        token::mint = mint_sub,
+        token::authority = pool,
+        token::token_program = token_program_sub,
+        seeds = [SEED_VAULT_SUB, pool.key().as_ref()],
+        bump
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+#[derive(Accounts)]
+#[instruction(tick_lower: i32, tick_upper: i32)]
+pub struct OpenClPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_CL_POOL, pool.treasury.as_ref(), pool.mint_knite.as_ref(), pool.mint_sub.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = ClPosition::space(),
+        seeds = [
+            SEED_CL_POSITION,
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &tick_lower.to_le_bytes(),
+            &tick_upper.to_le_bytes()
+        ],
+        bump
+    )]
+    pub position: Box<Account<'info, ClPosition>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Shared by `add_cl_liquidity`, `remove_cl_liquidity` and `collect_cl_fees` (owner-only).
+#[derive(Accounts)]
+pub struct ClLiquidity<'info> {
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CL_POOL, pool.treasury.as_ref(), pool.mint_knite.as_ref(), pool.mint_sub.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_CL_POSITION,
+            pool.key().as_ref(),
+            owner.key().as_ref(),
+            &position.tick_lower.to_le_bytes(),
+            &position.tick_upper.to_le_bytes()
+        ],
+        bump = position.bump,
+        has_one = owner @ DexError::Unauthorized,
+        has_one = pool @ DexError::Unauthorized
+    )]
+    pub position: Box<Account<'info, ClPosition>>,
+
+    #[account(
+        address = pool.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        address = pool.mint_sub @ DexError::MintMismatch,
+        mint::token_program = token_program_sub
+    )]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = pool.vault_knite @ DexError::MintMismatch)]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_sub @ DexError::MintMismatch)]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_knite_ata.owner == owner.key() @ DexError::Unauthorized,
+        constraint = owner_knite_ata.mint == pool.mint_knite @ DexError::MintMismatch
+    )]
+    pub owner_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = owner_sub_ata.owner == owner.key() @ DexError::Unauthorized,
+        constraint = owner_sub_ata.mint == pool.mint_sub @ DexError::MintMismatch
+    )]
+    pub owner_sub_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+}
+
+/// Owner-only, once the position holds no liquidity and no uncollected fees.
+#[derive(Accounts)]
+pub struct CloseClPosition<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [
+            SEED_CL_POSITION,
+            position.pool.as_ref(),
+            owner.key().as_ref(),
+            &position.tick_lower.to_le_bytes(),
+            &position.tick_upper.to_le_bytes()
+        ],
+        bump = position.bump,
+        has_one = owner @ DexError::Unauthorized,
+        close = owner
+    )]
+    pub position: Box<Account<'info, ClPosition>>,
+}
+
+/// Exact-in swap through a ClPool; the fee is taken in the input token.
+#[derive(Accounts)]
+pub struct SwapCl<'info> {
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_CL_POOL, pool.treasury.as_ref(), pool.mint_knite.as_ref(), pool.mint_sub.as_ref()],
+        bump = pool.bump
+    )]
+    pub pool: Box<Account<'info, ClPool>>,
+
+    #[account(
+        address = pool.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        address = pool.mint_sub @ DexError::MintMismatch,
+        mint::token_program = token_program_sub
+    )]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    /// direction is taken from `user_ata_in.mint`
+    #[account(mut, constraint = user_ata_in.owner == user.key() @ DexError::Unauthorized)]
+    pub user_ata_in: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, constraint = user_ata_out.owner == user.key() @ DexError::Unauthorized)]
+    pub user_ata_out: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(mut, address = pool.vault_knite @ DexError::MintMismatch)]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = pool.vault_sub @ DexError::MintMismatch)]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury ATA of the input mint (strictly validated in handler)
+    #[account(mut)]
+    pub treasury_fee_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+}
+
+/// Permissionless: creates the batch of the current epoch (payer gets the rent back on close).
+#[derive(Accounts)]
+#[instruction(epoch: u64)]
+pub struct OpenBatch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        address = pair.mint_sub @ DexError::MintMismatch,
+        mint::token_program = token_program_sub
+    )]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = Batch::space(),
+        seeds = [SEED_BATCH, pair.key().as_ref(), &epoch.to_le_bytes()],
+        bump
+    )]
+    pub batch: Box<Account<'info, Batch>>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_knite,
+        token::authority = batch,
+        token::token_program = token_program_knite,
+        seeds = [SEED_BATCH_ESCROW_KNITE, batch.key().as_ref()],
+        bump
+    )]
+    pub escrow_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint_sub,
+        token::authority = batch,
+        token::token_program = token_program_sub,
+        seeds = [SEED_BATCH_ESCROW_SUB, batch.key().as_ref()],
+        bump
+    )]
+    pub escrow_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Escrows `amount_in` of mint_in (one side of the pair) in the batch, one commit per owner.
+#[derive(Accounts)]
+pub struct CommitBatchSwap<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_BATCH, pair.key().as_ref(), &batch.epoch.to_le_bytes()],
+        bump = batch.bump,
+        has_one = pair @ DexError::InvalidBatch
+    )]
+    pub batch: Box<Account<'info, Batch>>,
+
+    #[account(
+        init,
+        payer = owner,
+        space = BatchCommit::space(),
+        seeds = [SEED_BATCH_COMMIT, batch.key().as_ref(), owner.key().as_ref()],
+        bump
+    )]
+    pub commit: Box<Account<'info, BatchCommit>>,
+
+    #[account(mint::token_program = token_program_in)]
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+
+    /// batch escrow of mint_in (checked in handler)
+    #[account(mut)]
+    pub escrow_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_ata_in.owner == owner.key() @ DexError::Unauthorized,
+        constraint = owner_ata_in.mint == mint_in.key() @ DexError::MintMismatch
+    )]
+    pub owner_ata_in: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_in: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+    pub rent: Sysvar<'info, Rent>,
+}
+
+/// Permissionless crank once the epoch is over. remaining_accounts: every commit of the
+/// batch (mut), in ascending key order.
+#[derive(Accounts)]
+pub struct SettleBatch<'info> {
+    pub cranker: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_BATCH, pair.key().as_ref(), &batch.epoch.to_le_bytes()],
+        bump = batch.bump,
+        has_one = pair @ DexError::InvalidBatch
+    )]
+    pub batch: Box<Account<'info, Batch>>,
+
+    #[account(
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        address = pair.mint_sub @ DexError::MintMismatch,
+        mint::token_program = token_program_sub
+    )]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = batch.escrow_knite @ DexError::InvalidBatch)]
+    pub escrow_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = batch.escrow_sub @ DexError::InvalidBatch)]
+    pub escrow_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_knite.key() == pair.vault_knite @ DexError::MintMismatch,
+        constraint = vault_knite.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_knite
+    )]
+    pub vault_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = vault_sub.key() == pair.vault_sub @ DexError::MintMismatch,
+        constraint = vault_sub.owner == pair.key() @ DexError::Unauthorized,
+        token::token_program = token_program_sub
+    )]
+    pub vault_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    /// Treasury kNite ATA (strictly validated in handler)
+    #[account(
+        mut,
+        constraint = treasury_knite_ata.mint == pair.mint_knite @ DexError::MintMismatch
+    )]
+    pub treasury_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+
+    /// Writable kNite mint; only needed (and only write-locked) when the pair's fee split burns
+    #[account(
+        mut,
+        address = pair.mint_knite @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub burn_mint: Option<Box<InterfaceAccount<'info, Mint>>>,
+}
+
+/// Shared by `claim_batch` (settled) and `withdraw_batch` (expired unsettled); owner-only,
+/// closes the commit (rent to owner).
+#[derive(Accounts)]
+pub struct ClaimBatch<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_BATCH, batch.pair.as_ref(), &batch.epoch.to_le_bytes()],
+        bump = batch.bump
+    )]
+    pub batch: Box<Account<'info, Batch>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_BATCH_COMMIT, batch.key().as_ref(), owner.key().as_ref()],
+        bump = commit.bump,
+        has_one = owner @ DexError::Unauthorized,
+        has_one = batch @ DexError::InvalidBatch,
+        close = owner
+    )]
+    pub commit: Box<Account<'info, BatchCommit>>,
+
+    #[account(
+        address = escrow_knite.mint @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        address = escrow_sub.mint @ DexError::MintMismatch,
+        mint::token_program = token_program_sub
+    )]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = batch.escrow_knite @ DexError::InvalidBatch)]
+    pub escrow_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = batch.escrow_sub @ DexError::InvalidBatch)]
+    pub escrow_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    #[account(
+        mut,
+        constraint = owner_knite_ata.owner == owner.key() @ DexError::Unauthorized,
+        constraint = owner_knite_ata.mint == mint_knite.key() @ DexError::MintMismatch
+    )]
+    pub owner_knite_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        constraint = owner_sub_ata.owner == owner.key() @ DexError::Unauthorized,
+        constraint = owner_sub_ata.mint == mint_sub.key() @ DexError::MintMismatch
+    )]
+    pub owner_sub_ata: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct OpenLaunchCooldown<'info> {
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        init,
+        payer = user,
+        space = LaunchCooldown::space(),
+        seeds = [SEED_LAUNCH_COOLDOWN, pair.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub launch_cooldown: Box<Account<'info, LaunchCooldown>>,
+
+    pub system_program: Program<'info, System>,
+}
+
+/// Owner-only, once the pair's launch phase is over (rent back to the owner).
+#[derive(Accounts)]
+pub struct CloseLaunchCooldown<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+
+    #[account(
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+
+    #[account(
+        mut,
+        seeds = [SEED_LAUNCH_COOLDOWN, pair.key().as_ref(), owner.key().as_ref()],
+        bump = launch_cooldown.bump,
+        has_one = owner @ DexError::Unauthorized,
+        close = owner
+    )]
+    pub launch_cooldown: Box<Account<'info, LaunchCooldown>>,
+}
+
+/// Payer-only, once the epoch is over and every commit has left: closes escrows and batch.
+#[derive(Accounts)]
+pub struct CloseBatch<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    #[account(
+        mut,
+        seeds = [SEED_BATCH, batch.pair.as_ref(), &batch.epoch.to_le_bytes()],
+        bump = batch.bump,
+        has_one = payer @ DexError::Unauthorized,
+        close = payer
+    )]
+    pub batch: Box<Account<'info, Batch>>,
+
+    #[account(
+        address = escrow_knite.mint @ DexError::MintMismatch,
+        mint::token_program = token_program_knite
+    )]
+    pub mint_knite: Box<InterfaceAccount<'info, Mint>>,
+    #[account(
+        address = escrow_sub.mint @ DexError::MintMismatch,
+        mint::token_program = token_program_sub
+    )]
+    pub mint_sub: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(mut, address = batch.escrow_knite @ DexError::InvalidBatch)]
+    pub escrow_knite: Box<InterfaceAccount<'info, TokenAccount>>,
+    #[account(mut, address = batch.escrow_sub @ DexError::InvalidBatch)]
+    pub escrow_sub: Box<InterfaceAccount<'info, TokenAccount>>,
+
+    pub token_program_knite: Interface<'info, TokenInterface>,
+    pub token_program_sub: Interface<'info, TokenInterface>,
+}
+
+#[derive(Accounts)]
+pub struct Quote<'info> {
+    pub mint_in: Box<InterfaceAccount<'info, Mint>>,
+    pub mint_out: Box<InterfaceAccount<'info, Mint>>,
+
+    #[account(
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+}
+
+#[derive(Accounts)]
+pub struct Observe<'info> {
+    #[account(
+        seeds = [SEED_PAIR, pair.treasury.as_ref(), pair.mint_knite.as_ref(), pair.mint_sub.as_ref()],
+        bump = pair.bump,
+        constraint = pair.version == PAIR_VERSION @ DexError::UnsupportedPairVersion
+    )]
+    pub pair: Box<Account<'info, Pair>>,
+}
+
+
+#[program]
+pub mod dex_core_contracts {
+    use super::*;
+
+    pub fn initialize_pair(
+        ctx: Context<InitializePair>,
+        treasury: Pubkey,
+        curve_type: CurveType,
+        amp: u64,
+    ) -> Result<()> {
+        instructions::initialize_pair::handler(ctx, treasury, curve_type, amp)
+    }
+
+    pub fn swap_exact_in(ctx: Context<SwapExactIn>, amount_in: u64, min_out: u64) -> Result<()> {
+        instructions::swap::handler(ctx, amount_in, min_out)
+    }
+
+    pub fn swap_exact_in_with_deadline(
+        ctx: Context<SwapExactIn>,
+        amount_in: u64,
+        min_out: u64,
+        deadline_ts: Option<i64>,
+        max_slot: Option<u64>,
+    ) -> Result<()> {
+        instructions::swap_with_deadline::handler(ctx, amount_in, min_out, deadline_ts, max_slot)
+    }
+
+    pub fn swap_exact_out(ctx: Context<SwapExactOut>, amount_out: u64, max_in: u64) -> Result<()> {
+        instructions::swap_exact_out::handler(ctx, amount_out, max_in)
+    }
+
+    pub fn swap_route<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SwapRoute<'info>>,
+        amount_in: u64,
+        min_out: u64,
+    ) -> Result<()> {
+        instructions::swap_route::handler(ctx, amount_in, min_out)
+    }
+
+    pub fn add_liquidity(
+        ctx: Context<AddLiquidity>,
+        max_knite: u64,
+        max_sub: u64,
+        min_lp: u64,
+    ) -> Result<()> {
+        instructions::add_liquidity::handler(ctx, max_knite, max_sub, min_lp)
+    }
+
+    pub fn remove_liquidity(
+        ctx: Context<RemoveLiquidity>,
+        lp_amount: u64,
+        min_knite: u64,
+        min_sub: u64,
+    ) -> Result<()> {
+        instructions::remove_liquidity::handler(ctx, lp_amount, min_knite, min_sub)
+    }
+
+    pub fn set_pair_enabled(ctx: Context<UpdatePairConfig>, enabled: bool) -> Result<()> {
+        instructions::set_pair_enabled::handler(ctx, enabled)
+    }
+
+    pub fn set_swap_fee_bps(ctx: Context<UpdatePairConfig>, swap_fee_bps: u16) -> Result<()> {
+        instructions::set_swap_fee_bps::handler(ctx, swap_fee_bps)
+    }
+
+    pub fn set_referral_share_bps(ctx: Context<UpdatePairConfig>, referral_share_bps: u16) -> Result<()> {
+        instructions::set_referral_share_bps::handler(ctx, referral_share_bps)
+    }
+
+    pub fn quote_exact_in(ctx: Context<Quote>, amount_in: u64) -> Result<SwapQuote> {
+        instructions::quote::handler_exact_in(ctx, amount_in)
+    }
+
+    pub fn quote_exact_out(ctx: Context<Quote>, amount_out: u64) -> Result<SwapQuote> {
+        instructions::quote::handler_exact_out(ctx, amount_out)
+    }
+
+    pub fn set_circuit_breaker(
+        ctx: Context<UpdatePairConfig>,
+        max_price_impact_bps: u16,
+        max_slot_volume_knite: u64,
+    ) -> Result<()> {
+        instructions::set_circuit_breaker::handler(ctx, max_price_impact_bps, max_slot_volume_knite)
+    }
+
+    pub fn observe(ctx: Context<Observe>, window_secs: u32) -> Result<TwapResult> {
+        instructions::observe::handler(ctx, window_secs)
+    }
+
+    pub fn sync(ctx: Context<SyncReserves>) -> Result<()> {
+        instructions::sync::handler(ctx)
+    }
+
+    pub fn skim(ctx: Context<Skim>) -> Result<()> {
+        instructions::skim::handler(ctx)
+    }
+
+    pub fn flash_borrow(ctx: Context<PairFlashLoan>, amount: u64) -> Result<()> {
+        instructions::flash_loan::handler_borrow(ctx, amount)
+    }
+
+    pub fn flash_repay(ctx: Context<PairFlashLoan>) -> Result<()> {
+        instructions::flash_loan::handler_repay(ctx)
+    }
+
+    pub fn set_flash_fee_bps(ctx: Context<UpdatePairConfig>, flash_fee_bps: u16) -> Result<()> {
+        instructions::set_flash_fee_bps::handler(ctx, flash_fee_bps)
+    }
+
+    pub fn initialize_registry(ctx: Context<InitializeRegistry>, authority: Pubkey) -> Result<()> {
+        instructions::initialize_registry::handler(ctx, authority)
+    }
+
+    pub fn initialize_generic_pair(
+        ctx: Context<InitializeGenericPair>,
+        swap_fee_bps: u16,
+        fee_mode: FeeMode,
+    ) -> Result<()> {
+        instructions::initialize_generic_pair::handler(ctx, swap_fee_bps, fee_mode)
+    }
+
+    pub fn swap_generic(ctx: Context<SwapGeneric>, amount_in: u64, min_out: u64) -> Result<()> {
+        instructions::generic_swap::handler(ctx, amount_in, min_out)
+    }
+
+    pub fn add_generic_liquidity(
+        ctx: Context<GenericLiquidity>,
+        max_a: u64,
+        max_b: u64,
+        min_lp: u64,
+    ) -> Result<()> {
+        instructions::generic_liquidity::handler_add(ctx, max_a, max_b, min_lp)
+    }
+
+    pub fn remove_generic_liquidity(
+        ctx: Context<GenericLiquidity>,
+        lp_amount: u64,
+        min_a: u64,
+        min_b: u64,
+    ) -> Result<()> {
+        instructions::generic_liquidity::handler_remove(ctx, lp_amount, min_a, min_b)
+    }
+
+    pub fn set_fee_split(ctx: Context<UpdatePairConfig>, fee_split: FeeSplit) -> Result<()> {
+        instructions::set_fee_split::handler(ctx, fee_split)
+    }
+
+    pub fn ramp_amp(ctx: Context<UpdatePairConfig>, target_amp: u64, ramp_end_ts: i64) -> Result<()> {
+        instructions::amp_ramp::handler_ramp(ctx, target_amp, ramp_end_ts)
+    }
+
+    pub fn stop_ramp_amp(ctx: Context<UpdatePairConfig>) -> Result<()> {
+        instructions::amp_ramp::handler_stop(ctx)
+    }
+
+    pub fn place_order(ctx: Context<PlaceOrder>, order_id: u64, amount_in: u64, min_out: u64) -> Result<()> {
+        instructions::limit_order::handler_place(ctx, order_id, amount_in, min_out)
+    }
+
+    pub fn cancel_order(ctx: Context<CancelOrder>) -> Result<()> {
+        instructions::limit_order::handler_cancel(ctx)
+    }
+
+    pub fn fill_orders<'info>(ctx: Context<'_, '_, 'info, 'info, FillOrders<'info>>) -> Result<()> {
+        instructions::limit_order::handler_fill(ctx)
+    }
+
+    pub fn open_dca(
+        ctx: Context<OpenDca>,
+        dca_id: u64,
+        amount_total: u64,
+        amount_per_cycle: u64,
+        interval_secs: i64,
+        min_price_q64: u128,
+    ) -> Result<()> {
+        instructions::dca::handler_open(ctx, dca_id, amount_total, amount_per_cycle, interval_secs, min_price_q64)
+    }
+
+    pub fn execute_dca_cycle(ctx: Context<ExecuteDcaCycle>) -> Result<()> {
+        instructions::dca::handler_execute(ctx)
+    }
+
+    pub fn close_dca(ctx: Context<CloseDca>) -> Result<()> {
+        instructions::dca::handler_close(ctx)
+    }
+
+    pub fn initialize_cl_pool(
+        ctx: Context<InitializeClPool>,
+        treasury: Pubkey,
+        sqrt_price_q64: u128,
+        tick_spacing: u16,
+        swap_fee_bps: u16,
+        min_position_liquidity: u128,
+    ) -> Result<()> {
+        instructions::initialize_cl_pool::handler(
+            ctx,
+            treasury,
+            sqrt_price_q64,
+            tick_spacing,
+            swap_fee_bps,
+            min_position_liquidity,
+        )
+    }
+
+    pub fn open_cl_position(ctx: Context<OpenClPosition>, tick_lower: i32, tick_upper: i32) -> Result<()> {
+        instructions::cl_position::handler_open(ctx, tick_lower, tick_upper)
+    }
+
+    pub fn add_cl_liquidity(
+        ctx: Context<ClLiquidity>,
+        liquidity: u128,
+        max_knite: u64,
+        max_sub: u64,
+    ) -> Result<()> {
+        instructions::cl_position::handler_add(ctx, liquidity, max_knite, max_sub)
+    }
+
+    pub fn remove_cl_liquidity(
+        ctx: Context<ClLiquidity>,
+        liquidity: u128,
+        min_knite: u64,
+        min_sub: u64,
+    ) -> Result<()> {
+        instructions::cl_position::handler_remove(ctx, liquidity, min_knite, min_sub)
+    }
+
+    pub fn collect_cl_fees(ctx: Context<ClLiquidity>) -> Result<()> {
+        instructions::cl_position::handler_collect(ctx)
+    }
+
+    pub fn close_cl_position(ctx: Context<CloseClPosition>) -> Result<()> {
+        instructions::cl_position::handler_close(ctx)
+    }
+
+    pub fn swap_cl(ctx: Context<SwapCl>, amount_in: u64, min_out: u64) -> Result<()> {
+        instructions::cl_swap::handler(ctx, amount_in, min_out)
+    }
+
+    pub fn swap_sol_in(ctx: Context<SwapSol>, lamports_in: u64, min_out: u64) -> Result<()> {
+        instructions::sol_swap::handler_sol_in(ctx, lamports_in, min_out)
+    }
+
+    pub fn swap_sol_out(ctx: Context<SwapSol>, amount_in: u64, min_lamports_out: u64) -> Result<()> {
+        instructions::sol_swap::handler_sol_out(ctx, amount_in, min_lamports_out)
+    }
+
+    pub fn set_batch_slots(ctx: Context<UpdatePairConfig>, batch_slots: u64) -> Result<()> {
+        instructions::set_batch_slots::handler(ctx, batch_slots)
+    }
+
+    pub fn open_batch(ctx: Context<OpenBatch>, epoch: u64) -> Result<()> {
+        instructions::batch_auction::handler_open(ctx, epoch)
+    }
+
+    pub fn commit_batch_swap(ctx: Context<CommitBatchSwap>, amount_in: u64, min_out: u64) -> Result<()> {
+        instructions::batch_auction::handler_commit(ctx, amount_in, min_out)
+    }
+
+    pub fn settle_batch<'info>(ctx: Context<'_, '_, 'info, 'info, SettleBatch<'info>>) -> Result<()> {
+        instructions::batch_auction::handler_settle(ctx)
+    }
+
+    pub fn claim_batch(ctx: Context<ClaimBatch>) -> Result<()> {
+        instructions::batch_auction::handler_claim(ctx)
+    }
+
+    pub fn withdraw_batch(ctx: Context<ClaimBatch>) -> Result<()> {
+        instructions::batch_auction::handler_withdraw(ctx)
+    }
+
+    pub fn close_batch(ctx: Context<CloseBatch>) -> Result<()> {
+        instructions::batch_auction::handler_close(ctx)
+    }
+
+    pub fn close_pair<'info>(ctx: Context<'_, '_, 'info, 'info, ClosePair<'info>>) -> Result<()> {
+        instructions::close_pair::handler(ctx)
+    }
+
+    pub fn migrate_pair(ctx: Context<MigratePair>) -> Result<()> {
+        instructions::migrate_pair::handler(ctx)
+    }
+
+    pub fn set_launch_phase(
+        ctx: Context<UpdatePairConfig>,
+        trading_starts_at: i64,
+        launch_secs: i64,
+        max_buy_per_slot: u64,
+        cooldown_secs: u32,
+    ) -> Result<()> {
+        instructions::launch_phase::handler_set(ctx, trading_starts_at, launch_secs, max_buy_per_slot, cooldown_secs)
+    }
+
+    pub fn open_launch_cooldown(ctx: Context<OpenLaunchCooldown>) -> Result<()> {
+        instructions::launch_phase::handler_open(ctx)
+    }
+
+    pub fn close_launch_cooldown(ctx: Context<CloseLaunchCooldown>) -> Result<()> {
+        instructions::launch_phase::handler_close(ctx)
+    }
+}
+
+
+
+/// Treasury-only handlers re-check the signer (defense in depth; also constrained in Accounts).
+pub fn assert_treasury_signer(pair: &Pair, treasury_signer: Pubkey) -> Result<()> {
+    if treasury_signer != pair.treasury {
+        return err!(DexError::Unauthorized);
+    }
+    Ok(())
+}
+
+pub fn assert_treasury_knite_ata(pair: &Pair, treasury_knite_ata: Pubkey, token_program: &Pubkey) -> Result<()> {
+    assert_treasury_ata(&pair.treasury, &pair.mint_knite, treasury_knite_ata, token_program)
+}
+
+pub fn assert_treasury_sub_ata(pair: &Pair, treasury_sub_ata: Pubkey, token_program: &Pubkey) -> Result<()> {
+    assert_treasury_ata(&pair.treasury, &pair.mint_sub, treasury_sub_ata, token_program)
+}
 
-pub fn assert_treasury_knite_ata(pair: &Pair, treasury_knite_ata: Pubkey) -> Result<()> {
-    let expected = get_associated_token_address(&pair.treasury, &pair.mint_knite);
-    if expected != treasury_knite_ata {
+/// `ata` must be the treasury's ATA of `mint` (shared by every pool type's fee routing).
+pub fn assert_treasury_ata(treasury: &Pubkey, mint: &Pubkey, ata: Pubkey, token_program: &Pubkey) -> Result<()> {
+    let expected = get_associated_token_address_with_program_id(treasury, mint, token_program);
+    if expected != ata {
         return err!(DexError::InvalidTreasuryAta);
     }
     Ok(())
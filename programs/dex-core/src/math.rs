@@ -38,5 +38,150 @@ pub fn fee_ceil(amount: u64, bps: u16) -> Result<u64> {
         .checked_mul(bps_u)
         .ok_or_else(|| error!(DexError::MathOverflow))?;
 
-    Ok(((num + den - 1) / den) as u64)
+    Ok(num.div_ceil(den) as u64)
+}
+
+/// Smallest gross amount whose ceil-fee still leaves `net`:
+/// gross = ceil(net * 10_000 / (10_000 - bps))
+#[inline(always)]
+pub fn gross_up_for_fee(net: u64, bps: u16) -> Result<u64> {
+    if bps as u128 >= 10_000 {
+        return err!(DexError::InvalidAmount);
+    }
+    let den = 10_000u128 - bps as u128;
+
+    let num = (net as u128)
+        .checked_mul(10_000u128)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+    u64::try_from(num.div_ceil(den)).map_err(|_| error!(DexError::MathOverflow))
+}
+
+/// (a * b) / denom, floor, checked into u64
+#[inline(always)]
+pub fn mul_div_floor(a: u64, b: u64, denom: u64) -> Result<u64> {
+    if denom == 0 {
+        return err!(DexError::InsufficientLiquidity);
+    }
+    let prod = (a as u128)
+        .checked_mul(b as u128)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+    u64::try_from(prod / denom as u128).map_err(|_| error!(DexError::MathOverflow))
+}
+
+/// ceil((a * b) / denom), checked into u64
+#[inline(always)]
+pub fn mul_div_ceil(a: u64, b: u64, denom: u64) -> Result<u64> {
+    if denom == 0 {
+        return err!(DexError::InsufficientLiquidity);
+    }
+    let prod = (a as u128)
+        .checked_mul(b as u128)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+    u64::try_from(prod.div_ceil(denom as u128)).map_err(|_| error!(DexError::MathOverflow))
+}
+
+/// floor(sqrt(n)) via Newton iteration (integer only)
+#[inline(always)]
+pub fn sqrt_u128(n: u128) -> u128 {
+    if n < 2 {
+        return n;
+    }
+    let mut x = n;
+    let mut y = x / 2 + (x & 1);
+    while y < x {
+        x = y;
+        y = (x + n / x) / 2;
+    }
+    x
+}
+
+/// Q64.64 price: (numerator << 64) / denominator
+#[inline(always)]
+pub fn price_q64(numerator: u64, denominator: u64) -> Result<u128> {
+    if denominator == 0 {
+        return err!(DexError::InsufficientLiquidity);
+    }
+    Ok(((numerator as u128) << 64) / denominator as u128)
+}
+
+/// amount * price (Q64.64), rounded down
+pub fn amount_at_price_q64(amount: u64, price_q64: u128) -> Result<u64> {
+    let out = mul_div_u128(amount as u128, price_q64, 1u128 << 64).ok_or_else(|| error!(DexError::MathOverflow))?;
+    u64::try_from(out).map_err(|_| error!(DexError::MathOverflow))
+}
+
+/// Price impact vs. the spot price, in bps (fee excluded):
+/// ideal = amount_in * R_out / R_in, impact = (ideal - actual) / ideal
+#[inline(always)]
+pub fn price_impact_bps(amount_in: u64, reserve_in: u64, reserve_out: u64, actual_out: u64) -> Result<u16> {
+    if reserve_in == 0 || reserve_out == 0 {
+        return err!(DexError::InsufficientLiquidity);
+    }
+    let ideal = (amount_in as u128)
+        .checked_mul(reserve_out as u128)
+        .ok_or_else(|| error!(DexError::MathOverflow))?
+        / reserve_in as u128;
+    if ideal == 0 || actual_out as u128 >= ideal {
+        return Ok(0);
+    }
+    let bps = (ideal - actual_out as u128)
+        .checked_mul(10_000u128)
+        .ok_or_else(|| error!(DexError::MathOverflow))?
+        / ideal;
+    Ok(bps.min(10_000) as u16)
+}
+
+/// |p_after - p_before| / p_before in bps, with p = reserve_sub / reserve_knite.
+/// Reserves are (knite, sub). Saturates instead of overflowing.
+#[inline(always)]
+pub fn marginal_price_move_bps(before: (u64, u64), after: (u64, u64)) -> Result<u64> {
+    let (k0, s0) = (before.0 as u128, before.1 as u128);
+    let (k1, s1) = (after.0 as u128, after.1 as u128);
+    if k0 == 0 || s0 == 0 || k1 == 0 {
+        return err!(DexError::InsufficientLiquidity);
+    }
+
+    // p1/p0 = (s1 * k0) / (s0 * k1); u64 * u64 always fits in u128
+    let num = s1 * k0;
+    let den = s0 * k1;
+    let diff = num.abs_diff(den);
+
+    Ok(match diff.checked_mul(10_000) {
+        Some(scaled) => u64::try_from(scaled / den).unwrap_or(u64::MAX),
+        None => u64::MAX,
+    })
+}
+
+/// floor(a * b / denom) with a 256-bit intermediate product.
+/// None when `denom` is 0 or the quotient does not fit in u128.
+pub fn mul_div_u128(a: u128, b: u128, denom: u128) -> Option<u128> {
+    if denom == 0 {
+        return None;
+    }
+    // full 256-bit product as (hi, lo) from 64-bit limbs
+    const LO: u128 = u64::MAX as u128;
+    let (a1, a0) = (a >> 64, a & LO);
+    let (b1, b0) = (b >> 64, b & LO);
+    let (mid, mid_carry) = (a0 * b1).overflowing_add(a1 * b0);
+    let (lo, lo_carry) = (a0 * b0).overflowing_add(mid << 64);
+    let hi = (a1 * b1) + (mid >> 64) + ((mid_carry as u128) << 64) + lo_carry as u128;
+    if hi >= denom {
+        return None;
+    }
+
+    // restoring long division of (hi, lo) by denom; remainder stays < denom
+    let mut rem = hi;
+    let mut quot = 0u128;
+    for i in (0..128).rev() {
+        let carry = rem >> 127;
+        rem = (rem << 1) | ((lo >> i) & 1);
+        if carry == 1 || rem >= denom {
+            rem = rem.wrapping_sub(denom);
+            quot |= 1 << i;
+        }
+    }
+    Some(quot)
 }
@@ -1,11 +1,225 @@
 use anchor_lang::prelude::*;
+use common_contracts::constants::BPS_DENOMINATOR;
+
+use crate::curve::Curve;
+use crate::errors::DexError;
+use crate::math::{marginal_price_move_bps, mul_div_u128, price_q64};
 
 pub const SEED_PAIR: &[u8] = b"pair";
 pub const SEED_VAULT_KNITE: &[u8] = b"vault_knite";
 pub const SEED_VAULT_SUB: &[u8] = b"vault_sub";
+pub const SEED_LP_MINT: &[u8] = b"lp_mint";
+pub const SEED_GENERIC_PAIR: &[u8] = b"generic_pair";
+pub const SEED_VAULT_A: &[u8] = b"vault_a";
+pub const SEED_VAULT_B: &[u8] = b"vault_b";
+pub const SEED_PAIR_REGISTRY: &[u8] = b"pair_registry";
+pub const SEED_PAIR_REGISTRY_ENTRY: &[u8] = b"pair_registry_entry";
+pub const SEED_ORDER: &[u8] = b"order";
+pub const SEED_ORDER_ESCROW: &[u8] = b"order_escrow";
+pub const SEED_DCA: &[u8] = b"dca";
+pub const SEED_DCA_ESCROW: &[u8] = b"dca_escrow";
+pub const SEED_CL_POOL: &[u8] = b"cl_pool";
+pub const SEED_CL_POSITION: &[u8] = b"cl_position";
+pub const SEED_BATCH: &[u8] = b"batch";
+pub const SEED_BATCH_COMMIT: &[u8] = b"batch_commit";
+pub const SEED_BATCH_ESCROW_KNITE: &[u8] = b"batch_escrow_knite";
+pub const SEED_BATCH_ESCROW_SUB: &[u8] = b"batch_escrow_sub";
+pub const SEED_TEMP_WSOL: &[u8] = b"temp_wsol";
+pub const SEED_LAUNCH_COOLDOWN: &[u8] = b"launch_cooldown";
+
+
+/// Hard cap for `set_swap_fee_bps` (10%)
+pub const MAX_SWAP_FEE_BPS: u16 = 1_000;
+
+/// LP units locked forever on the first deposit (never minted to anyone)
+pub const MINIMUM_LIQUIDITY: u64 = 1_000;
+
+/// swap_route: accounts per hop in remaining_accounts, and hop cap
+pub const ROUTE_ACCOUNTS_PER_HOP: usize = 7;
+pub const MAX_ROUTE_HOPS: usize = 4;
+
+/// TWAP ring buffer size and min spacing between stored observations
+pub const OBSERVATION_SLOTS: usize = 16;
+pub const MIN_OBSERVATION_INTERVAL_SECS: i64 = 60;
+
+/// Share of `fee_knite` paid to a recorded referrer: default at init, cap for the setter
+pub const DEFAULT_REFERRAL_SHARE_BPS: u16 = 2_000;
+pub const MAX_REFERRAL_SHARE_BPS: u16 = 5_000;
+
+/// Flash loan fee on the borrowed amount (paid to the treasury): default at init, cap for the setter
+pub const DEFAULT_FLASH_FEE_BPS: u16 = 9;
+pub const MAX_FLASH_FEE_BPS: u16 = 1_000;
+/// Position of `pair` in the PairFlashLoan account list (matched by flash_borrow)
+pub const FLASH_REPAY_PAIR_INDEX: usize = 1;
+
+/// StableSwap amplification coefficient bounds, max factor per ramp, and min ramp duration
+pub const MIN_AMP: u64 = 1;
+pub const MAX_AMP: u64 = 1_000_000;
+pub const MAX_AMP_CHANGE: u64 = 10;
+pub const MIN_AMP_RAMP_SECS: i64 = 86_400;
+
+/// Limit orders: cranker fee on the escrowed input, accounts per order in
+/// fill_orders remaining_accounts, and orders per crank
+pub const ORDER_CRANK_FEE_BPS: u16 = 5;
+pub const ORDER_FILL_ACCOUNTS: usize = 5;
+pub const MAX_ORDER_FILLS: usize = 4;
+
+/// DCA vaults: shortest allowed interval between cycles
+pub const MIN_DCA_INTERVAL_SECS: i64 = 60;
+
+/// Concentrated liquidity: initialized ticks a pool can hold, and the share of each
+/// swap fee sent to the treasury ATA (the rest accrues to in-range positions)
+pub const MAX_CL_TICKS: usize = 64;
+pub const CL_PROTOCOL_FEE_SHARE_BPS: u16 = 2_000;
+
+/// Batch auctions: longest epoch, commits per batch (all passed to settle_batch), and slots
+/// after the epoch ends during which it can be settled before commits may withdraw instead
+pub const MAX_BATCH_SLOTS: u64 = 9_000;
+pub const MAX_BATCH_COMMITS: u8 = 16;
+pub const BATCH_SETTLE_WINDOW_SLOTS: u64 = 150;
+
+/// Pair layout version this program reads and writes (0 = the original 174-byte layout,
+/// upgraded by migrate_pair), and bytes kept free at the end for future fields
+/// (v1 reserved 128; v2 took PairStats out of them)
+pub const PAIR_VERSION: u8 = 2;
+pub const PAIR_RESERVED_BYTES: usize = 48;
+
+/// close_pair remaining_accounts per LP holder: [lp token account, holder kNite account, holder sub account]
+pub const CLOSE_PAIR_LP_ACCOUNTS: usize = 3;
+
+/// Launch phase: longest window after `trading_starts_at` with per-wallet limits
+pub const MAX_LAUNCH_PHASE_SECS: i64 = 86_400;
+
+/// Pricing curve chosen at `initialize_pair`; fixed for the pair's lifetime.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CurveType {
+    /// x * y = k
+    ConstantProduct,
+    /// Curve-style StableSwap for pegged pairs, amplification `A` (ramped)
+    StableSwap,
+}
+
+/// How a Pair's kNite swap fee (after any referral share) is divided, in bps summing
+/// to 10_000: `lp_bps` stays in vault_knite for LPs, `treasury_bps` goes to the treasury
+/// ATA, `burn_bps` is burned from kNite supply. Rounding dust goes to the treasury.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FeeSplit {
+    pub lp_bps: u16,
+    pub treasury_bps: u16,
+    pub burn_bps: u16,
+}
+
+impl FeeSplit {
+    /// whole fee to the treasury (behaviour before fee splits existed)
+    pub const TREASURY_ONLY: FeeSplit = FeeSplit { lp_bps: 0, treasury_bps: 10_000, burn_bps: 0 };
+
+    /// three u16
+    pub const fn serialized_size() -> usize {
+        2 + 2 + 2
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        let total = self.lp_bps as u64 + self.treasury_bps as u64 + self.burn_bps as u64;
+        if total != BPS_DENOMINATOR {
+            return err!(DexError::InvalidFeeSplit);
+        }
+        Ok(())
+    }
+}
+
+/// Token a GenericPair takes its swap fee in.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FeeMode {
+    /// whichever token the user sends
+    InputToken,
+    /// always `mint_a` (from the input, or from the output when swapping b -> a)
+    MintA,
+    /// always `mint_b`
+    MintB,
+}
+
+/// Snapshot of the cumulative prices at `ts`.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Observation {
+    pub ts: i64,
+    pub price_knite_cumulative: u128,
+    pub price_sub_cumulative: u128,
+}
+
+impl Observation {
+    /// ts(8) + two u128 cumulatives(32)
+    pub const fn serialized_size() -> usize {
+        8 + 16 + 16
+    }
+}
+
+/// Return data of `observe`: TWAPs (Q64.64) over [start_ts, end_ts].
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TwapResult {
+    pub price_knite_twap_q64: u128,
+    pub price_sub_twap_q64: u128,
+    pub start_ts: i64,
+    pub end_ts: i64,
+}
+
+/// Lifetime trading counters of a Pair, updated by every swap leg (record_swap).
+/// Volumes are user input per direction; the last price is that trade's sub per kNite
+/// (Q64.64, fee included).
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PairStats {
+    pub volume_knite_in: u128,
+    pub volume_sub_in: u128,
+    pub fees_knite: u128,
+    pub swap_count: u64,
+    pub last_price_q64: u128,
+    pub last_trade_ts: i64,
+}
+
+impl PairStats {
+    /// volumes(32) + fees_knite(16) + swap_count(8) + last_price_q64(16) + last_trade_ts(8)
+    pub const fn serialized_size() -> usize {
+        32 + 16 + 8 + 16 + 8
+    }
+
+    /// Counts one trade of `knite` kNite against `sub` sub (the user's side of each).
+    pub fn record(&mut self, is_knite_in: bool, knite: u64, sub: u64, fee_knite: u64, now: i64) -> Result<()> {
+        let overflow = || error!(DexError::MathOverflow);
+        if is_knite_in {
+            self.volume_knite_in = self.volume_knite_in.checked_add(knite as u128).ok_or_else(overflow)?;
+        } else {
+            self.volume_sub_in = self.volume_sub_in.checked_add(sub as u128).ok_or_else(overflow)?;
+        }
+        self.fees_knite = self.fees_knite.checked_add(fee_knite as u128).ok_or_else(overflow)?;
+        self.swap_count = self.swap_count.checked_add(1).ok_or_else(overflow)?;
+        if knite > 0 {
+            self.last_price_q64 = price_q64(sub, knite)?;
+        }
+        self.last_trade_ts = now;
+        Ok(())
+    }
+}
+
+/// Return data of `quote_exact_in` / `quote_exact_out` (borsh, read via simulateTransaction).
+/// `gross_out` is the curve output before the kNite fee; `net_out` is what the user receives;
+/// `stats` are the pair's counters before this trade.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct SwapQuote {
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+    pub amount_in: u64,
+    pub gross_out: u64,
+    pub fee_knite: u64,
+    pub net_out: u64,
+    pub price_impact_bps: u16,
+    pub reserve_knite_after: u64,
+    pub reserve_sub_after: u64,
+    pub stats: PairStats,
+}
 
 #[account]
 pub struct Pair {
+    // Fields up to `enabled` are the original (version 0) layout and keep its offsets;
+    // everything after is appended, so migrate_pair can grow a deployed account in place
     pub bump: u8,
     pub vault_knite_bump: u8,
     pub vault_sub_bump: u8,
@@ -22,10 +236,110 @@ pub struct Pair {
 
     pub swap_fee_bps: u16, // 30 bps (0.3%)
     pub enabled: bool,
+
+    // LP share mint (mint authority MUST be pair PDA)
+    pub lp_mint_bump: u8,
+    pub lp_mint: Pubkey,
+    // LP units locked on first deposit, counted in total supply
+    pub lp_locked: u64,
+
+    // TWAP oracle: Q64.64 prices summed over seconds (wrapping, Uniswap-v2 style)
+    // price_knite = sub per kNite, price_sub = kNite per sub
+    pub price_knite_cumulative: u128,
+    pub price_sub_cumulative: u128,
+    pub last_update_ts: i64,
+
+    // ring buffer of cumulative snapshots, `observation_index` = newest
+    pub observation_index: u8,
+    pub observation_count: u8,
+    pub observations: [Observation; OBSERVATION_SLOTS],
+
+    // Circuit breaker (0 = off): max marginal price move per swap,
+    // and max kNite volume per slot before the pair disables itself
+    pub max_price_impact_bps: u16,
+    pub max_slot_volume_knite: u64,
+    pub volume_slot: u64,
+    pub slot_volume_knite: u64,
+
+    /// bps of `fee_knite` redirected to the referrer on referred swaps
+    pub referral_share_bps: u16,
+
+    /// reserves the pair accounts for; swaps and LP math price off these,
+    /// so tokens donated to the vaults do not move the price (see skim/sync)
+    pub reserve_knite: u64,
+    pub reserve_sub: u64,
+
+    // Flash loans: fee in bps of the borrowed amount, and the loan outstanding
+    // between flash_borrow and flash_repay (at most one per pair at a time)
+    pub flash_fee_bps: u16,
+    pub flash_loan_active: bool,
+    pub flash_loan_is_knite: bool,
+    pub flash_loan_amount: u64,
+
+    // Pricing curve; StableSwap amp moves linearly from `amp_initial` to
+    // `amp_target` over [amp_ramp_start_ts, amp_ramp_end_ts] (both 0 for constant product)
+    pub curve_type: CurveType,
+    pub amp_initial: u64,
+    pub amp_target: u64,
+    pub amp_ramp_start_ts: i64,
+    pub amp_ramp_end_ts: i64,
+
+    /// split of the kNite fee between LPs, treasury and burn
+    pub fee_split: FeeSplit,
+
+    /// batch auction epoch length in slots (0 = off); while on, the pair only
+    /// trades through settle_batch
+    pub batch_slots: u64,
+
+    // Launch phase: no swaps before `trading_starts_at`; until `launch_ends_at` only
+    // swap_exact_in trades, capped per wallet (sub bought per slot, 0 = no cap) and
+    // spaced by `launch_cooldown_secs`, tracked in LaunchCooldown PDAs (all 0 = off)
+    pub trading_starts_at: i64,
+    pub launch_ends_at: i64,
+    pub launch_max_buy_per_slot: u64,
+    pub launch_cooldown_secs: u32,
+
+    /// layout version (PAIR_VERSION once initialized or migrated); new fields are carved
+    /// out of `reserved` so the account size stays fixed across versions
+    pub version: u8,
+    /// since v2
+    pub stats: PairStats,
+    pub reserved: [u8; PAIR_RESERVED_BYTES],
 }
 
 impl Pair {
     pub fn space() -> usize {
+        // version 0 layout (legacy_space)
+        // lp_mint_bump(1) + lp_mint(32) + lp_locked(8)
+        // cumulatives(32) + last_update_ts(8)
+        // observation index/count(2) + observations(40 * OBSERVATION_SLOTS)
+        // circuit breaker(26)
+        Self::legacy_space()
+            + 1 + 32 + 8
+            + 32 + 8
+            + 2 + Observation::serialized_size() * OBSERVATION_SLOTS
+            // max_price_impact_bps(2) + max_slot_volume_knite(8) + volume_slot(8) + slot_volume_knite(8)
+            + 2 + 8 + 8 + 8
+            // referral_share_bps(2)
+            + 2
+            // reserve_knite(8) + reserve_sub(8)
+            + 8 + 8
+            // flash_fee_bps(2) + flash_loan_active(1) + flash_loan_is_knite(1) + flash_loan_amount(8)
+            + 2 + 1 + 1 + 8
+            // curve_type(1) + amp_initial(8) + amp_target(8) + ramp start/end(16)
+            + 1 + 8 + 8 + 16
+            // fee_split(6)
+            + FeeSplit::serialized_size()
+            // batch_slots(8)
+            + 8
+            // trading_starts_at(8) + launch_ends_at(8) + launch_max_buy_per_slot(8) + launch_cooldown_secs(4)
+            + 8 + 8 + 8 + 4
+            // version(1) + stats(80) + reserved
+            + 1 + PairStats::serialized_size() + PAIR_RESERVED_BYTES
+    }
+
+    /// Size of the original, unversioned (version 0) layout: the fields up to `enabled`.
+    pub fn legacy_space() -> usize {
         // disc(8)
         // bumps(3)
         // treasury(32)
@@ -35,4 +349,736 @@ impl Pair {
         // enabled(1)
         8 + 3 + 32 + 64 + 64 + 2 + 1
     }
+
+    /// LP supply including the locked minimum
+    pub fn total_lp(&self, lp_mint_supply: u64) -> Result<u64> {
+        lp_mint_supply
+            .checked_add(self.lp_locked)
+            .ok_or_else(|| error!(DexError::MathOverflow))
+    }
+
+    /// Errors before `trading_starts_at`; true while the launch phase limits apply.
+    pub fn in_launch_phase(&self, now: i64) -> Result<bool> {
+        if now < self.trading_starts_at {
+            return err!(DexError::TradingNotStarted);
+        }
+        Ok(now < self.launch_ends_at)
+    }
+
+    /// StableSwap amp at `now` along the current ramp.
+    pub fn amp_at(&self, now: i64) -> u64 {
+        if now >= self.amp_ramp_end_ts || self.amp_ramp_end_ts <= self.amp_ramp_start_ts {
+            return self.amp_target;
+        }
+        let elapsed = now.saturating_sub(self.amp_ramp_start_ts).max(0) as u128;
+        let duration = (self.amp_ramp_end_ts - self.amp_ramp_start_ts) as u128;
+        let (from, to) = (self.amp_initial as u128, self.amp_target as u128);
+        // |to - from| <= MAX_AMP, so the interpolation stays within u64
+        if to >= from {
+            (from + (to - from) * elapsed / duration) as u64
+        } else {
+            (from - (from - to) * elapsed / duration) as u64
+        }
+    }
+
+    /// Curve to price a swap with at `now`.
+    pub fn curve_at(&self, now: i64) -> Curve {
+        match self.curve_type {
+            CurveType::ConstantProduct => Curve::ConstantProduct,
+            CurveType::StableSwap => Curve::StableSwap { amp: self.amp_at(now) },
+        }
+    }
+
+    /// Starts the oracle at `now` with zero cumulatives.
+    pub fn init_oracle(&mut self, now: i64) {
+        self.price_knite_cumulative = 0;
+        self.price_sub_cumulative = 0;
+        self.last_update_ts = now;
+        self.observation_index = 0;
+        self.observation_count = 1;
+        self.observations = [Observation::default(); OBSERVATION_SLOTS];
+        self.observations[0] = Observation { ts: now, ..Observation::default() };
+    }
+
+    /// Cumulative prices as of `now`, accruing the price implied by the given
+    /// reserves since `last_update_ts`. Reserves must be the ones that held
+    /// over that interval (i.e. call BEFORE moving tokens).
+    pub fn cumulatives_at(&self, reserve_knite: u64, reserve_sub: u64, now: i64) -> Result<(u128, u128)> {
+        let mut knite_cum = self.price_knite_cumulative;
+        let mut sub_cum = self.price_sub_cumulative;
+
+        let elapsed = now.saturating_sub(self.last_update_ts);
+        if elapsed > 0 && reserve_knite > 0 && reserve_sub > 0 {
+            let dt = elapsed as u128;
+            knite_cum = knite_cum.wrapping_add(price_q64(reserve_sub, reserve_knite)?.wrapping_mul(dt));
+            sub_cum = sub_cum.wrapping_add(price_q64(reserve_knite, reserve_sub)?.wrapping_mul(dt));
+        }
+        Ok((knite_cum, sub_cum))
+    }
+
+    /// Accrues the oracle up to `now` and records an observation when the
+    /// newest one is at least MIN_OBSERVATION_INTERVAL_SECS old.
+    pub fn update_oracle(&mut self, reserve_knite: u64, reserve_sub: u64, now: i64) -> Result<()> {
+        if now <= self.last_update_ts {
+            return Ok(());
+        }
+        let (knite_cum, sub_cum) = self.cumulatives_at(reserve_knite, reserve_sub, now)?;
+        self.price_knite_cumulative = knite_cum;
+        self.price_sub_cumulative = sub_cum;
+        self.last_update_ts = now;
+
+        let newest = self.observations[self.observation_index as usize];
+        if now.saturating_sub(newest.ts) >= MIN_OBSERVATION_INTERVAL_SECS {
+            let next = (self.observation_index as usize + 1) % OBSERVATION_SLOTS;
+            self.observations[next] = Observation {
+                ts: now,
+                price_knite_cumulative: knite_cum,
+                price_sub_cumulative: sub_cum,
+            };
+            self.observation_index = next as u8;
+            if (self.observation_count as usize) < OBSERVATION_SLOTS {
+                self.observation_count += 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Rejects swaps that move the marginal price (sub per kNite) by more
+    /// than `max_price_impact_bps`.
+    pub fn check_price_impact(
+        &self,
+        reserves_before: (u64, u64),
+        reserves_after: (u64, u64),
+    ) -> Result<()> {
+        if self.max_price_impact_bps == 0 {
+            return Ok(());
+        }
+        let moved = marginal_price_move_bps(reserves_before, reserves_after)?;
+        if moved > self.max_price_impact_bps as u64 {
+            return err!(DexError::PriceImpactTooHigh);
+        }
+        Ok(())
+    }
+
+    /// Adds `volume_knite` to the current slot's total (reset on a new slot).
+    /// Returns true when this swap pushed the total over the cap and the pair
+    /// was disabled; the crossing swap itself still settles.
+    pub fn record_slot_volume(&mut self, volume_knite: u64, slot: u64) -> Result<bool> {
+        if slot != self.volume_slot {
+            self.volume_slot = slot;
+            self.slot_volume_knite = 0;
+        }
+        self.slot_volume_knite = self
+            .slot_volume_knite
+            .checked_add(volume_knite)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+
+        if self.max_slot_volume_knite > 0 && self.slot_volume_knite > self.max_slot_volume_knite {
+            self.enabled = false;
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// Newest stored observation with `ts <= target_ts`, if any.
+    pub fn observation_at_or_before(&self, target_ts: i64) -> Option<Observation> {
+        (0..self.observation_count as usize)
+            .map(|back| {
+                let idx = (self.observation_index as usize + OBSERVATION_SLOTS - back) % OBSERVATION_SLOTS;
+                self.observations[idx]
+            })
+            .find(|o| o.ts <= target_ts)
+    }
+}
+
+/// Pair between any two mints, created through the factory (`initialize_generic_pair`).
+/// PDA = [SEED_GENERIC_PAIR, mint_a, mint_b] with mint_a < mint_b, so one pair per mint set.
+#[account]
+pub struct GenericPair {
+    pub bump: u8,
+    pub vault_a_bump: u8,
+    pub vault_b_bump: u8,
+    pub lp_mint_bump: u8,
+
+    // registry authority at creation; receives fees
+    pub treasury: Pubkey,
+
+    // canonical order: mint_a < mint_b (byte-wise)
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+
+    // Vault token accounts (authority MUST be the pair PDA)
+    pub vault_a: Pubkey,
+    pub vault_b: Pubkey,
+
+    // LP share mint (mint authority MUST be the pair PDA)
+    pub lp_mint: Pubkey,
+    // LP units locked on first deposit, counted in total supply
+    pub lp_locked: u64,
+
+    pub swap_fee_bps: u16,
+    pub fee_mode: FeeMode,
+    pub enabled: bool,
+
+    pub reserve_a: u64,
+    pub reserve_b: u64,
+
+    pub created_at: i64,
+}
+
+impl GenericPair {
+    pub fn space() -> usize {
+        // disc(8)
+        // bumps(4)
+        // treasury(32)
+        // mints(64) + vaults(64) + lp_mint(32)
+        // lp_locked(8)
+        // fee(2) + fee_mode(1) + enabled(1)
+        // reserves(16)
+        // created_at(8)
+        8 + 4 + 32 + 64 + 64 + 32 + 8 + 2 + 1 + 1 + 16 + 8
+    }
+
+    /// LP supply including the locked minimum
+    pub fn total_lp(&self, lp_mint_supply: u64) -> Result<u64> {
+        lp_mint_supply
+            .checked_add(self.lp_locked)
+            .ok_or_else(|| error!(DexError::MathOverflow))
+    }
+
+    /// Mint the fee is taken in when swapping `mint_in` into this pair.
+    pub fn fee_mint(&self, mint_in: Pubkey) -> Pubkey {
+        match self.fee_mode {
+            FeeMode::InputToken => mint_in,
+            FeeMode::MintA => self.mint_a,
+            FeeMode::MintB => self.mint_b,
+        }
+    }
+}
+
+/// Which account type a registry entry points at.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PairKind {
+    /// `Pair` (initialize_pair): mint_0 = mint_knite, mint_1 = mint_sub
+    Knite,
+    /// `GenericPair` (initialize_generic_pair): mint_0 = mint_a, mint_1 = mint_b
+    Generic,
+    /// `ClPool` (initialize_cl_pool): mint_0 = mint_knite, mint_1 = mint_sub
+    Concentrated,
+}
+
+/// One registry row per pair, in its own PDA = [SEED_PAIR_REGISTRY_ENTRY, index (u64 LE)],
+/// so creating a pair writes a fixed-size account instead of growing a shared list.
+/// Closed together with its pair (close_pair), which leaves a gap at its index.
+///
+/// Layout for indexers reading the account directly (borsh, little endian):
+/// [0..8) discriminator, [8] bump, [9..17) index, [17..49) pair,
+/// [49] kind (0 = Knite, 1 = Generic, 2 = Concentrated), [50..82) mint_0, [82..114) mint_1,
+/// [114..116) fee_bps, [116..124) created_at
+#[account]
+pub struct PairRegistryEntry {
+    pub bump: u8,
+    pub index: u64,
+    pub pair: Pubkey,
+    pub kind: PairKind,
+    pub mint_0: Pubkey,
+    pub mint_1: Pubkey,
+    /// swap fee at creation (the pair account holds the current one)
+    pub fee_bps: u16,
+    pub created_at: i64,
+}
+
+impl PairRegistryEntry {
+    /// disc(8) + bump(1) + index(8) + pair(32) + kind(1) + mints(64) + fee_bps(2) + created_at(8)
+    pub const fn space() -> usize {
+        8 + 1 + 8 + 32 + 1 + 64 + 2 + 8
+    }
+}
+
+/// Singleton registry of every pair (all kinds); its authority is the only signer allowed
+/// to create generic pairs. Pairs are numbered in creation order: to list them, read
+/// `pair_count` and fetch the entry PDAs for indexes [0, pair_count) (`getMultipleAccounts`);
+/// indexes whose pair was closed have no account.
+///
+/// Layout (borsh, little endian): [0..8) discriminator, [8] bump, [9..41) authority,
+/// [41..49) pair_count (u64)
+#[account]
+pub struct PairRegistry {
+    pub bump: u8,
+    pub authority: Pubkey,
+    pub pair_count: u64,
+}
+
+impl PairRegistry {
+    /// disc(8) + bump(1) + authority(32) + pair_count(8)
+    pub const fn space() -> usize {
+        8 + 1 + 32 + 8
+    }
+
+    /// Index of the pair being created (the seed of its entry PDA); bumps the count.
+    pub fn claim_index(&mut self) -> Result<u64> {
+        let index = self.pair_count;
+        self.pair_count = index.checked_add(1).ok_or_else(|| error!(DexError::MathOverflow))?;
+        Ok(index)
+    }
+}
+
+/// Resting limit order on a kNite pair: sell everything in `escrow` (mint_in) for at least
+/// `min_out` of mint_out. Filled whole by the `fill_orders` crank once the pair price allows.
+/// PDA = [SEED_ORDER, pair, owner, order_id (u64 LE)]; escrow = [SEED_ORDER_ESCROW, order].
+#[account]
+pub struct Order {
+    pub bump: u8,
+    pub escrow_bump: u8,
+
+    pub owner: Pubkey,
+    pub pair: Pubkey,
+    pub order_id: u64,
+
+    pub mint_in: Pubkey,
+    pub mint_out: Pubkey,
+
+    // token account holding the input (authority MUST be the order PDA)
+    pub escrow: Pubkey,
+
+    // input escrowed at placement (as received) and the limit for all of it
+    pub amount_in: u64,
+    pub min_out: u64,
+
+    pub created_at: i64,
+}
+
+impl Order {
+    pub fn space() -> usize {
+        // disc(8)
+        // bumps(2)
+        // owner(32) + pair(32) + order_id(8)
+        // mints(64) + escrow(32)
+        // amount_in(8) + min_out(8)
+        // created_at(8)
+        8 + 2 + 32 + 32 + 8 + 64 + 32 + 8 + 8 + 8
+    }
+}
+
+/// Recurring kNite -> sub buys on a kNite pair. `escrow` holds the kNite still to spend;
+/// each cycle swaps `amount_per_cycle` (or what is left) once `next_cycle_ts` is reached,
+/// and must return at least that input times `min_price_q64` (sub per kNite, Q64.64).
+/// PDA = [SEED_DCA, pair, owner, dca_id (u64 LE)]; escrow = [SEED_DCA_ESCROW, dca].
+#[account]
+pub struct DcaVault {
+    pub bump: u8,
+    pub escrow_bump: u8,
+
+    pub owner: Pubkey,
+    pub pair: Pubkey,
+    pub dca_id: u64,
+
+    // kNite token account (authority MUST be the DCA PDA)
+    pub escrow: Pubkey,
+
+    // schedule
+    pub amount_per_cycle: u64,
+    pub interval_secs: i64,
+    pub min_price_q64: u128,
+    pub next_cycle_ts: i64,
+
+    // progress
+    pub cycles_executed: u64,
+    pub total_in: u64,
+    pub total_out: u64,
+
+    pub created_at: i64,
+}
+
+impl DcaVault {
+    pub fn space() -> usize {
+        // disc(8)
+        // bumps(2)
+        // owner(32) + pair(32) + dca_id(8)
+        // escrow(32)
+        // amount_per_cycle(8) + interval(8) + min_price(16) + next_cycle_ts(8)
+        // cycles(8) + totals(16)
+        // created_at(8)
+        8 + 2 + 32 + 32 + 8 + 32 + 8 + 8 + 16 + 8 + 8 + 16 + 8
+    }
+}
+
+/// Batch auction of a kNite pair for one epoch of `Pair::batch_slots` slots. Commits escrow
+/// their input here until `end_slot`; settle_batch then clears them all at one price within
+/// BATCH_SETTLE_WINDOW_SLOTS. Past that window, commits of an unsettled batch withdraw instead.
+/// PDA = [SEED_BATCH, pair, epoch (u64 LE)]; escrows = [SEED_BATCH_ESCROW_KNITE/SUB, batch].
+#[account]
+pub struct Batch {
+    pub bump: u8,
+    pub escrow_knite_bump: u8,
+    pub escrow_sub_bump: u8,
+
+    pub pair: Pubkey,
+    // paid the rent, refunded by close_batch
+    pub payer: Pubkey,
+    pub epoch: u64,
+    // first slot after the commit phase
+    pub end_slot: u64,
+
+    // token accounts (authority MUST be the batch PDA)
+    pub escrow_knite: Pubkey,
+    pub escrow_sub: Pubkey,
+
+    // commits made, and commits not yet claimed or withdrawn
+    pub commits: u8,
+    pub commits_open: u8,
+    pub total_knite_in: u64,
+    pub total_sub_in: u64,
+
+    // set by settle_batch: input of the filled commits per side and the output owed to
+    // them; both count down as commits claim (pro rata on what is left, so nothing strands)
+    pub settled: bool,
+    pub fill_knite_in: u64,
+    pub fill_knite_out: u64,
+    pub fill_sub_in: u64,
+    pub fill_sub_out: u64,
+}
+
+impl Batch {
+    pub fn space() -> usize {
+        // disc(8)
+        // bumps(3)
+        // pair(32) + payer(32) + epoch(8) + end_slot(8)
+        // escrows(64)
+        // commits(2) + totals(16)
+        // settled(1) + fills(32)
+        8 + 3 + 32 + 32 + 8 + 8 + 64 + 2 + 16 + 1 + 32
+    }
+
+    pub fn is_expired(&self, slot: u64) -> bool {
+        slot >= self.end_slot.saturating_add(BATCH_SETTLE_WINDOW_SLOTS)
+    }
+}
+
+/// One swap intent in a Batch: `amount_in` (what the escrow received) of one side of the
+/// pair, filled only if its pro-rata share of the clearing output reaches `min_out`.
+/// PDA = [SEED_BATCH_COMMIT, batch, owner].
+#[account]
+pub struct BatchCommit {
+    pub bump: u8,
+    pub batch: Pubkey,
+    pub owner: Pubkey,
+    pub is_knite_in: bool,
+    pub amount_in: u64,
+    pub min_out: u64,
+    // set by settle_batch; unfilled commits of a settled batch get their input back
+    pub filled: bool,
+}
+
+impl BatchCommit {
+    pub fn space() -> usize {
+        // disc(8) + bump(1) + batch(32) + owner(32) + side(1) + amounts(16) + filled(1)
+        8 + 1 + 32 + 32 + 1 + 16 + 1
+    }
+}
+
+/// A wallet's trading during a Pair's launch phase; closable by the owner once it ends.
+/// PDA = [SEED_LAUNCH_COOLDOWN, pair, owner].
+#[account]
+pub struct LaunchCooldown {
+    pub bump: u8,
+    pub pair: Pubkey,
+    pub owner: Pubkey,
+    pub last_trade_ts: i64,
+    // sub (the launched token) bought within `last_slot`
+    pub last_slot: u64,
+    pub bought_in_slot: u64,
+}
+
+impl LaunchCooldown {
+    pub fn space() -> usize {
+        // disc(8) + bump(1) + pair(32) + owner(32) + last_trade_ts(8) + last_slot(8) + bought_in_slot(8)
+        8 + 1 + 32 + 32 + 8 + 8 + 8
+    }
+
+    /// Applies the pair's launch limits to one trade of this wallet and records it;
+    /// `sub_out` (sub bought, 0 for sells) counts against the per-slot buy cap.
+    pub fn record_trade(&mut self, pair: &Pair, sub_out: u64, now: i64, slot: u64) -> Result<()> {
+        if now < self.last_trade_ts.saturating_add(pair.launch_cooldown_secs as i64) {
+            return err!(DexError::LaunchCooldownActive);
+        }
+        if slot != self.last_slot {
+            self.last_slot = slot;
+            self.bought_in_slot = 0;
+        }
+        let bought = self
+            .bought_in_slot
+            .checked_add(sub_out)
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+        if pair.launch_max_buy_per_slot > 0 && bought > pair.launch_max_buy_per_slot {
+            return err!(DexError::LaunchBuyLimitExceeded);
+        }
+        self.bought_in_slot = bought;
+        self.last_trade_ts = now;
+        Ok(())
+    }
+}
+
+/// Initialized tick of a ClPool. Fee growth "outside" is relative to the current tick
+/// and flips each time the price crosses it.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClTick {
+    pub index: i32,
+    /// liquidity added when the price crosses this tick upward (removed downward)
+    pub liquidity_net: i128,
+    /// total position liquidity referencing this tick; 0 = uninitialized (dropped)
+    pub liquidity_gross: u128,
+    pub fee_growth_outside_knite_q64: u128,
+    pub fee_growth_outside_sub_q64: u128,
+}
+
+impl ClTick {
+    /// index(4) + net(16) + gross(16) + fee growth outside(32)
+    pub const fn serialized_size() -> usize {
+        4 + 16 + 16 + 32
+    }
+}
+
+/// Concentrated-liquidity pool on a kNite/sub mint pair, beside `Pair`.
+/// Liquidity lives in positions bounded by ticks; the price is sqrt(sub per kNite) in Q64.64.
+/// PDA = [SEED_CL_POOL, treasury, mint_knite, mint_sub]; vaults reuse SEED_VAULT_KNITE/SUB.
+#[account]
+pub struct ClPool {
+    pub bump: u8,
+    pub vault_knite_bump: u8,
+    pub vault_sub_bump: u8,
+
+    // receives the protocol share of swap fees
+    pub treasury: Pubkey,
+
+    pub mint_knite: Pubkey,
+    pub mint_sub: Pubkey,
+
+    // Vault token accounts (authority MUST be the pool PDA)
+    pub vault_knite: Pubkey,
+    pub vault_sub: Pubkey,
+
+    pub tick_spacing: u16,
+    pub swap_fee_bps: u16,
+    pub enabled: bool,
+    // a position holds 0 or at least this much liquidity (no dust positions pinning tick slots)
+    pub min_position_liquidity: u128,
+
+    pub sqrt_price_q64: u128,
+    // largest tick with sqrt_price_at_tick(tick) <= sqrt_price_q64 (tick - 1 right after a downward cross)
+    pub tick_current: i32,
+    // liquidity of positions in range at the current price
+    pub liquidity: u128,
+
+    // LP fees per unit of liquidity, Q64.64, wrapping
+    pub fee_growth_global_knite_q64: u128,
+    pub fee_growth_global_sub_q64: u128,
+
+    pub created_at: i64,
+
+    // initialized ticks, sorted by index (at most MAX_CL_TICKS)
+    pub ticks: Vec<ClTick>,
+}
+
+impl ClPool {
+    pub fn space() -> usize {
+        // disc(8)
+        // bumps(3)
+        // treasury(32) + mints(64) + vaults(64)
+        // tick_spacing(2) + fee(2) + enabled(1) + min_position_liquidity(16)
+        // sqrt_price(16) + tick_current(4) + liquidity(16)
+        // fee growth(32)
+        // created_at(8)
+        // ticks: len(4) + MAX_CL_TICKS * 68
+        8 + 3 + 32 + 64 + 64 + 2 + 2 + 1 + 16 + 16 + 4 + 16 + 32 + 8 + 4 + MAX_CL_TICKS * ClTick::serialized_size()
+    }
+
+    pub fn tick_position(&self, index: i32) -> std::result::Result<usize, usize> {
+        self.ticks.binary_search_by_key(&index, |t| t.index)
+    }
+
+    /// Position liquidity must be 0 or at least `min_position_liquidity`.
+    pub fn check_position_liquidity(&self, liquidity: u128) -> Result<()> {
+        if liquidity != 0 && liquidity < self.min_position_liquidity {
+            return err!(DexError::ClPositionTooSmall);
+        }
+        Ok(())
+    }
+
+    /// Adds `liquidity_delta` to the lower or upper tick of a position, initializing the tick
+    /// when needed and dropping it once no position references it.
+    pub fn update_tick(&mut self, index: i32, liquidity_delta: i128, is_upper: bool) -> Result<()> {
+        let overflow = || error!(DexError::MathOverflow);
+        let pos = match self.tick_position(index) {
+            Ok(pos) => pos,
+            Err(pos) => {
+                if liquidity_delta <= 0 {
+                    return err!(DexError::InvalidTick);
+                }
+                if self.ticks.len() >= MAX_CL_TICKS {
+                    return err!(DexError::ClTickLimit);
+                }
+                // by convention all fee growth so far happened below an initialized tick at or under the price
+                let (outside_knite, outside_sub) = if index <= self.tick_current {
+                    (self.fee_growth_global_knite_q64, self.fee_growth_global_sub_q64)
+                } else {
+                    (0, 0)
+                };
+                self.ticks.insert(
+                    pos,
+                    ClTick {
+                        index,
+                        liquidity_net: 0,
+                        liquidity_gross: 0,
+                        fee_growth_outside_knite_q64: outside_knite,
+                        fee_growth_outside_sub_q64: outside_sub,
+                    },
+                );
+                pos
+            }
+        };
+
+        let tick = &mut self.ticks[pos];
+        tick.liquidity_gross = tick
+            .liquidity_gross
+            .checked_add_signed(liquidity_delta)
+            .ok_or_else(overflow)?;
+        tick.liquidity_net = if is_upper {
+            tick.liquidity_net.checked_sub(liquidity_delta)
+        } else {
+            tick.liquidity_net.checked_add(liquidity_delta)
+        }
+        .ok_or_else(overflow)?;
+
+        if tick.liquidity_gross == 0 {
+            self.ticks.remove(pos);
+        }
+        Ok(())
+    }
+
+    /// Fee growth per unit of liquidity inside [lower, upper) (both ticks initialized).
+    pub fn fee_growth_inside(&self, lower: i32, upper: i32) -> Result<(u128, u128)> {
+        let lo = self.tick_position(lower).map_err(|_| error!(DexError::InvalidTick))?;
+        let hi = self.tick_position(upper).map_err(|_| error!(DexError::InvalidTick))?;
+        let (lo, hi) = (&self.ticks[lo], &self.ticks[hi]);
+        let global = (self.fee_growth_global_knite_q64, self.fee_growth_global_sub_q64);
+
+        let below = if self.tick_current >= lower {
+            (lo.fee_growth_outside_knite_q64, lo.fee_growth_outside_sub_q64)
+        } else {
+            (
+                global.0.wrapping_sub(lo.fee_growth_outside_knite_q64),
+                global.1.wrapping_sub(lo.fee_growth_outside_sub_q64),
+            )
+        };
+        let above = if self.tick_current < upper {
+            (hi.fee_growth_outside_knite_q64, hi.fee_growth_outside_sub_q64)
+        } else {
+            (
+                global.0.wrapping_sub(hi.fee_growth_outside_knite_q64),
+                global.1.wrapping_sub(hi.fee_growth_outside_sub_q64),
+            )
+        };
+        Ok((
+            global.0.wrapping_sub(below.0).wrapping_sub(above.0),
+            global.1.wrapping_sub(below.1).wrapping_sub(above.1),
+        ))
+    }
+
+    /// Price crosses initialized tick at `pos`: flip its outside fee growth and
+    /// apply its net liquidity (upward adds, downward removes).
+    pub fn cross_tick(&mut self, pos: usize, upward: bool) -> Result<()> {
+        let global = (self.fee_growth_global_knite_q64, self.fee_growth_global_sub_q64);
+        let tick = &mut self.ticks[pos];
+        tick.fee_growth_outside_knite_q64 = global.0.wrapping_sub(tick.fee_growth_outside_knite_q64);
+        tick.fee_growth_outside_sub_q64 = global.1.wrapping_sub(tick.fee_growth_outside_sub_q64);
+
+        let net = if upward { Some(tick.liquidity_net) } else { tick.liquidity_net.checked_neg() };
+        self.liquidity = net
+            .and_then(|net| self.liquidity.checked_add_signed(net))
+            .ok_or_else(|| error!(DexError::MathOverflow))?;
+        Ok(())
+    }
+}
+
+/// Liquidity position of `owner` in a ClPool between two ticks.
+/// PDA = [SEED_CL_POSITION, pool, owner, tick_lower (i32 LE), tick_upper (i32 LE)].
+#[account]
+pub struct ClPosition {
+    pub bump: u8,
+    pub pool: Pubkey,
+    pub owner: Pubkey,
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+    pub liquidity: u128,
+
+    // fee growth inside the range at the last update, and fees owed since
+    pub fee_growth_inside_knite_last_q64: u128,
+    pub fee_growth_inside_sub_last_q64: u128,
+    pub fees_owed_knite: u64,
+    pub fees_owed_sub: u64,
+}
+
+impl ClPosition {
+    pub fn space() -> usize {
+        // disc(8) + bump(1) + pool(32) + owner(32) + ticks(8) + liquidity(16)
+        // fee growth last(32) + fees owed(16)
+        8 + 1 + 32 + 32 + 8 + 16 + 32 + 16
+    }
+
+    /// Credits fees earned since the last update at `inside` (current fee growth inside).
+    pub fn accrue_fees(&mut self, inside: (u128, u128)) -> Result<()> {
+        let overflow = || error!(DexError::MathOverflow);
+        let earned = |now: u128, last: u128| -> Result<u64> {
+            let fees = mul_div_u128(self.liquidity, now.wrapping_sub(last), 1u128 << 64).ok_or_else(overflow)?;
+            u64::try_from(fees).map_err(|_| overflow())
+        };
+        let knite = earned(inside.0, self.fee_growth_inside_knite_last_q64)?;
+        let sub = earned(inside.1, self.fee_growth_inside_sub_last_q64)?;
+        self.fees_owed_knite = self.fees_owed_knite.checked_add(knite).ok_or_else(overflow)?;
+        self.fees_owed_sub = self.fees_owed_sub.checked_add(sub).ok_or_else(overflow)?;
+        self.fee_growth_inside_knite_last_q64 = inside.0;
+        self.fee_growth_inside_sub_last_q64 = inside.1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_lang::Discriminator;
+
+    #[test]
+    fn version_0_pair_reads_back_after_growing_to_the_current_layout() -> Result<()> {
+        let keys: [Pubkey; 5] = std::array::from_fn(|_| Pubkey::new_unique());
+
+        // the original layout: bumps, treasury, mints, vaults, swap_fee_bps, enabled
+        let mut data = Pair::DISCRIMINATOR.to_vec();
+        data.extend([254, 253, 252]);
+        keys.iter().for_each(|k| data.extend(k.to_bytes()));
+        data.extend(30u16.to_le_bytes());
+        data.push(1);
+        assert_eq!(data.len(), Pair::legacy_space());
+        assert_eq!(data.len(), 174);
+
+        // what migrate_pair sees after the resize
+        data.resize(Pair::space(), 0);
+        let pair = Pair::try_deserialize(&mut &data[..])?;
+        assert_eq!((pair.bump, pair.vault_knite_bump, pair.vault_sub_bump), (254, 253, 252));
+        assert_eq!(
+            [pair.treasury, pair.mint_knite, pair.mint_sub, pair.vault_knite, pair.vault_sub],
+            keys
+        );
+        assert_eq!(pair.swap_fee_bps, 30);
+        assert!(pair.enabled);
+        assert_eq!(pair.version, 0);
+        assert_eq!((pair.lp_mint, pair.lp_locked, pair.reserve_knite), (Pubkey::default(), 0, 0));
+
+        // and serializes back to exactly Pair::space()
+        let mut out = Vec::new();
+        pair.try_serialize(&mut out)?;
+        assert_eq!(out.len(), Pair::space());
+        assert_eq!(out[..Pair::legacy_space()], data[..Pair::legacy_space()]);
+        Ok(())
+    }
 }
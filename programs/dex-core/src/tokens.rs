@@ -0,0 +1,195 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::extension::transfer_fee::{TransferFeeAmount, TransferFeeConfig};
+use anchor_spl::token_2022::spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions};
+use anchor_spl::token_2022::spl_token_2022::state::{Account as AccountState, Mint as MintState};
+use anchor_spl::token_2022::Token2022;
+use anchor_spl::token_2022_extensions::transfer_fee::{self, HarvestWithheldTokensToMint};
+use anchor_spl::token_interface::{self, Burn, CloseAccount, Mint, TokenAccount, TransferChecked};
+
+use crate::errors::DexError;
+
+/// Mint extensions a pair cannot hold safely:
+/// - NonTransferable: vault deposits can never leave again
+/// - PermanentDelegate: the delegate can drain the vaults
+/// - TransferHook: arbitrary CPI on every vault transfer (and extra accounts we do not pass)
+pub const UNSUPPORTED_MINT_EXTENSIONS: [ExtensionType; 3] = [
+    ExtensionType::NonTransferable,
+    ExtensionType::PermanentDelegate,
+    ExtensionType::TransferHook,
+];
+
+/// Legacy SPL mints have no extensions; Token-2022 mints are checked against the deny list.
+pub fn validate_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    if *mint.owner != Token2022::id() {
+        return Ok(());
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let extensions = state.get_extension_types()?;
+    if extensions.iter().any(|e| UNSUPPORTED_MINT_EXTENSIONS.contains(e)) {
+        return err!(DexError::UnsupportedMintExtension);
+    }
+    Ok(())
+}
+
+/// Amount to send so that `net` arrives after a Token-2022 transfer fee (identity otherwise).
+pub fn gross_up_transfer_fee(mint: &AccountInfo, net: u64) -> Result<u64> {
+    if *mint.owner != Token2022::id() || net == 0 {
+        return Ok(net);
+    }
+    let data = mint.try_borrow_data()?;
+    let state = StateWithExtensions::<MintState>::unpack(&data)?;
+    let Ok(config) = state.get_extension::<TransferFeeConfig>() else {
+        return Ok(net);
+    };
+    let fee = config
+        .calculate_inverse_epoch_fee(Clock::get()?.epoch, net)
+        .ok_or_else(|| error!(DexError::MathOverflow))?;
+    net.checked_add(fee).ok_or_else(|| error!(DexError::MathOverflow))
+}
+
+/// Current balance of a (legacy or Token-2022) token account.
+pub fn token_balance(account: &AccountInfo) -> Result<u64> {
+    let data = account.try_borrow_data()?;
+    Ok(TokenAccount::try_deserialize(&mut &data[..])?.amount)
+}
+
+/// Transfer fees withheld in a Token-2022 account (0 for legacy accounts). Such an account
+/// cannot be closed until the fees are harvested to the mint.
+pub fn withheld_transfer_fees(account: &AccountInfo) -> Result<u64> {
+    if *account.owner != Token2022::id() {
+        return Ok(0);
+    }
+    let data = account.try_borrow_data()?;
+    let state = StateWithExtensions::<AccountState>::unpack(&data)?;
+    Ok(state
+        .get_extension::<TransferFeeAmount>()
+        .map(|e| u64::from(e.withheld_amount))
+        .unwrap_or(0))
+}
+
+/// One side of a pair: the token program owning the mint plus what `transfer_checked` needs.
+#[derive(Clone)]
+pub struct TokenSide<'info> {
+    pub token_program: AccountInfo<'info>,
+    pub mint: AccountInfo<'info>,
+    pub decimals: u8,
+}
+
+impl<'info> TokenSide<'info> {
+    pub fn new(token_program: AccountInfo<'info>, mint: &InterfaceAccount<'info, Mint>) -> Self {
+        Self { token_program, mint: mint.to_account_info(), decimals: mint.decimals }
+    }
+
+    /// `transfer_checked`; zero amounts are skipped. Empty `signer_seeds` = user-signed.
+    pub fn transfer(
+        &self,
+        from: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        token_interface::transfer_checked(
+            CpiContext::new_with_signer(
+                self.token_program.clone(),
+                TransferChecked {
+                    from: from.clone(),
+                    mint: self.mint.clone(),
+                    to: to.clone(),
+                    authority: authority.clone(),
+                },
+                signer_seeds,
+            ),
+            amount,
+            self.decimals,
+        )
+    }
+
+    /// Burns `amount` from `from` through `mint`, this side's mint passed writable
+    /// (`self.mint` may be read-only); zero amounts are skipped.
+    pub fn burn(
+        &self,
+        mint: &AccountInfo<'info>,
+        from: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        token_interface::burn(
+            CpiContext::new_with_signer(
+                self.token_program.clone(),
+                Burn { mint: mint.clone(), from: from.clone(), authority: authority.clone() },
+                signer_seeds,
+            ),
+            amount,
+        )
+    }
+
+    /// Closes an empty token account of this side, sending its rent to `destination`.
+    pub fn close(
+        &self,
+        account: &AccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        token_interface::close_account(CpiContext::new_with_signer(
+            self.token_program.clone(),
+            CloseAccount { account: account.clone(), destination: destination.clone(), authority: authority.clone() },
+            signer_seeds,
+        ))
+    }
+
+    /// Moves Token-2022 transfer fees withheld in `account` to this side's mint (permissionless;
+    /// the mint must be passed writable), so the account can be closed. No-op without any.
+    pub fn harvest_withheld(&self, account: &AccountInfo<'info>) -> Result<()> {
+        if withheld_transfer_fees(account)? == 0 {
+            return Ok(());
+        }
+        transfer_fee::harvest_withheld_tokens_to_mint(
+            CpiContext::new(
+                self.token_program.clone(),
+                HarvestWithheldTokensToMint { token_program_id: self.token_program.clone(), mint: self.mint.clone() },
+            ),
+            vec![account.clone()],
+        )
+    }
+
+    /// `close` for an emptied PDA escrow. One holding withheld Token-2022 transfer fees
+    /// cannot be closed and is left behind rather than blocking the caller.
+    pub fn close_escrow(
+        &self,
+        escrow: &AccountInfo<'info>,
+        destination: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+    ) -> Result<()> {
+        if withheld_transfer_fees(escrow)? > 0 {
+            return Ok(());
+        }
+        self.close(escrow, destination, authority, signer_seeds)
+    }
+
+    /// Same as `transfer`, returning what `to` actually received (net of transfer fees).
+    pub fn transfer_measured(
+        &self,
+        from: &AccountInfo<'info>,
+        to: &AccountInfo<'info>,
+        authority: &AccountInfo<'info>,
+        signer_seeds: &[&[&[u8]]],
+        amount: u64,
+    ) -> Result<u64> {
+        let before = token_balance(to)?;
+        self.transfer(from, to, authority, signer_seeds, amount)?;
+        token_balance(to)?
+            .checked_sub(before)
+            .ok_or_else(|| error!(DexError::MathOverflow))
+    }
+}
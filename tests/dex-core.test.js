@@ -6,12 +6,22 @@ const { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } = anchor.web3;
 
 const {
   TOKEN_PROGRAM_ID,
+  TOKEN_2022_PROGRAM_ID,
   ASSOCIATED_TOKEN_PROGRAM_ID,
   getAssociatedTokenAddressSync,
   createAssociatedTokenAccountInstruction,
   createInitializeMintInstruction,
   createMintToInstruction,
+  createInitializeTransferFeeConfigInstruction,
+  createInitializeNonTransferableMintInstruction,
+  getMintLen,
+  ExtensionType,
+  createSyncNativeInstruction,
   getAccount,
+  getMint,
+  getTransferFeeAmount,
+  getTransferFeeConfig,
+  NATIVE_MINT,
 } = require("@solana/spl-token");
 
 // ---------------------------
@@ -22,18 +32,18 @@ async function airdrop(connection, pubkey, sol = 2) {
   await connection.confirmTransaction(sig, "confirmed");
 }
 
-function ata(owner, mint) {
+function ata(owner, mint, tokenProgramId = TOKEN_PROGRAM_ID) {
   return getAssociatedTokenAddressSync(
     mint,
     owner,
     false,
-    TOKEN_PROGRAM_ID,
+    tokenProgramId,
     ASSOCIATED_TOKEN_PROGRAM_ID
   );
 }
 
-async function ensureAta(provider, payerPubkey, ownerPubkey, mintPubkey) {
-  const ataPk = ata(ownerPubkey, mintPubkey);
+async function ensureAta(provider, payerPubkey, ownerPubkey, mintPubkey, tokenProgramId = TOKEN_PROGRAM_ID) {
+  const ataPk = ata(ownerPubkey, mintPubkey, tokenProgramId);
   const info = await provider.connection.getAccountInfo(ataPk, "confirmed");
   if (info) return ataPk;
 
@@ -42,7 +52,7 @@ async function ensureAta(provider, payerPubkey, ownerPubkey, mintPubkey) {
     ataPk,
     ownerPubkey,
     mintPubkey,
-    TOKEN_PROGRAM_ID,
+    tokenProgramId,
     ASSOCIATED_TOKEN_PROGRAM_ID
   );
 
@@ -82,19 +92,41 @@ async function createTestMint(provider, decimals = 6, mintAuthorityPubkey) {
   return { mintKp, mintPk };
 }
 
-async function mintTo(provider, mint, destAta, mintAuthorityKp, amount) {
+async function mintTo(provider, mint, destAta, mintAuthorityKp, amount, tokenProgramId = TOKEN_PROGRAM_ID) {
   const ix = createMintToInstruction(
     mint,
     destAta,
     mintAuthorityKp.publicKey,
     amount,
     [],
-    TOKEN_PROGRAM_ID
+    tokenProgramId
   );
   const tx = new anchor.web3.Transaction().add(ix);
   await provider.sendAndConfirm(tx, [mintAuthorityKp], { commitment: "confirmed" });
 }
 
+// Token-2022 mint with the given extension init instructions (run before InitializeMint)
+async function createToken2022Mint(provider, decimals, mintAuthorityPubkey, extensions, extensionIxs) {
+  const mintKp = Keypair.generate();
+  const mintPk = mintKp.publicKey;
+  const space = getMintLen(extensions);
+  const rent = await provider.connection.getMinimumBalanceForRentExemption(space);
+
+  const tx = new anchor.web3.Transaction().add(
+    SystemProgram.createAccount({
+      fromPubkey: provider.wallet.publicKey,
+      newAccountPubkey: mintPk,
+      lamports: rent,
+      space,
+      programId: TOKEN_2022_PROGRAM_ID,
+    }),
+    ...extensionIxs(mintPk),
+    createInitializeMintInstruction(mintPk, decimals, mintAuthorityPubkey, null, TOKEN_2022_PROGRAM_ID)
+  );
+  await provider.sendAndConfirm(tx, [mintKp], { commitment: "confirmed" });
+  return mintPk;
+}
+
 // PDAs (must match dex-core seeds)
 function findPairPda(programId, treasury, mintKnite, mintSub) {
   return PublicKey.findProgramAddressSync(
@@ -109,6 +141,10 @@ function findVaultPda(programId, seed, pairPk) {
   );
 }
 
+// CurveType args of initialize_pair
+const CONSTANT_PRODUCT = { constantProduct: {} };
+const STABLE_SWAP = { stableSwap: {} };
+
 // Constant-product calc used for exact assertions in tests
 function cpOut(amountIn, reserveIn, reserveOut) {
   // out = (in * R_out) / (R_in + in)
@@ -127,6 +163,58 @@ function feeCeil(amount, bps) {
   const num = amt * b;
   return (num + den - 1n) / den;
 }
+function cpIn(amountOut, reserveIn, reserveOut) {
+  // in = ceil((out * R_in) / (R_out - out))
+  const outU = BigInt(amountOut);
+  const rin = BigInt(reserveIn);
+  const rout = BigInt(reserveOut);
+  const num = outU * rin;
+  const den = rout - outU;
+  return (num + den - 1n) / den;
+}
+function grossUpForFee(net, bps) {
+  // ceil(net * 10_000 / (10_000 - bps))
+  const num = BigInt(net) * 10000n;
+  const den = 10000n - BigInt(bps);
+  return (num + den - 1n) / den;
+}
+
+// StableSwap (n = 2, Ann = 4A) mirroring curve.rs integer steps
+function stableGetD(x, y, amp) {
+  const ann = BigInt(amp) * 4n;
+  const s = x + y;
+  let d = s;
+  for (let i = 0; i < 255; i++) {
+    let dP = (d * d) / (x * 2n);
+    dP = (dP * d) / (y * 2n);
+    const prev = d;
+    d = ((ann * s + dP * 2n) * d) / ((ann - 1n) * d + dP * 3n);
+    if (d - prev <= 1n && prev - d <= 1n) return d;
+  }
+  throw new Error("CurveDidNotConverge");
+}
+function stableGetY(xNew, d, amp) {
+  const ann = BigInt(amp) * 4n;
+  let c = (d * d) / (xNew * 2n);
+  c = (c * d) / (ann * 2n);
+  const b = xNew + d / ann;
+  let y = d;
+  for (let i = 0; i < 255; i++) {
+    const den = y * 2n + b - d;
+    const prev = y;
+    y = (y * y) / den + c / den;
+    if (y - prev <= 1n && prev - y <= 1n) return y + 1n;
+  }
+  throw new Error("CurveDidNotConverge");
+}
+function stableOut(amountIn, reserveIn, reserveOut, amp) {
+  const rin = BigInt(reserveIn);
+  const rout = BigInt(reserveOut);
+  const d = stableGetD(rin, rout, amp);
+  const yNew = stableGetY(rin + BigInt(amountIn), d, amp);
+  const out = rout - yNew - 1n;
+  return out > 0n ? out : 0n;
+}
 
 describe("dex-core (Phase 1)", () => {
   const provider = anchor.AnchorProvider.env();
@@ -148,11 +236,24 @@ describe("dex-core (Phase 1)", () => {
   let pairPda;
   let vaultKnite;
   let vaultSub;
+  let lpMint;
 
   // ATAs
   let treasuryKniteAta;
+
+  // pair registry (singleton); every initialize_pair takes its next index
+  const [registryPda] = PublicKey.findProgramAddressSync([Buffer.from("pair_registry")], program.programId);
+  // one entry PDA per pair, seeded by its creation index
+  const registryEntryPda = (index) =>
+    PublicKey.findProgramAddressSync(
+      [Buffer.from("pair_registry_entry"), new anchor.BN(index).toArrayLike(Buffer, "le", 8)],
+      program.programId
+    )[0];
+  const nextRegistryEntry = async () =>
+    registryEntryPda((await program.account.pairRegistry.fetch(registryPda, "confirmed")).pairCount);
   let userKniteAta;
   let userSubAta;
+  let userLpAta;
 
   const decimals = 6;
 
@@ -161,6 +262,27 @@ describe("dex-core (Phase 1)", () => {
     await airdrop(connection, user.publicKey, 2);
     await airdrop(connection, attacker.publicKey, 2);
 
+    // only the program upgrade authority can create the registry
+    const [programData] = PublicKey.findProgramAddressSync(
+      [program.programId.toBuffer()],
+      new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+    );
+    const initRegistry = (payer) =>
+      program.methods.initializeRegistry(treasury.publicKey).accounts({
+        payer,
+        program: program.programId,
+        programData,
+        registry: registryPda,
+        systemProgram: SystemProgram.programId,
+      });
+    try {
+      await initRegistry(attacker.publicKey).signers([attacker]).rpc();
+      assert.fail("Expected Unauthorized");
+    } catch (e) {
+      assert.ok(e.toString().includes("Unauthorized") || e.toString().includes("custom program error"));
+    }
+    await initRegistry(provider.wallet.publicKey).rpc();
+
     // Create mints with treasury as mint authority so we can seed vaults + give user balances
     const mk = await createTestMint(provider, decimals, treasury.publicKey);
     const ms = await createTestMint(provider, decimals, treasury.publicKey);
@@ -171,6 +293,7 @@ describe("dex-core (Phase 1)", () => {
     [pairPda] = findPairPda(program.programId, treasury.publicKey, mintKnite, mintSub);
     [vaultKnite] = findVaultPda(program.programId, "vault_knite", pairPda);
     [vaultSub] = findVaultPda(program.programId, "vault_sub", pairPda);
+    [lpMint] = findVaultPda(program.programId, "lp_mint", pairPda);
 
     // ATAs
     treasuryKniteAta = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mintKnite);
@@ -184,14 +307,19 @@ describe("dex-core (Phase 1)", () => {
 
   it("initialize_pair: creates pair + vaults; vault authority is pair PDA; fee=30; enabled=true", async () => {
     await program.methods
-      .initializePair(treasury.publicKey)
+      .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
       .accounts({
         treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: await nextRegistryEntry(),
         mintKnite,
         mintSub,
         pair: pairPda,
         vaultKnite,
         vaultSub,
+        lpMint,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
         tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
         rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -207,6 +335,7 @@ describe("dex-core (Phase 1)", () => {
     assert.ok(pair.vaultSub.equals(vaultSub));
     assert.strictEqual(pair.swapFeeBps, 30);
     assert.strictEqual(pair.enabled, true);
+    assert.ok(pair.lpMint.equals(lpMint));
 
     // TokenAccount.owner is the authority pubkey
     const vK = await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID);
@@ -219,17 +348,23 @@ describe("dex-core (Phase 1)", () => {
     const [pair2] = findPairPda(program.programId, treasury.publicKey, mintSub, mintKnite);
     const [vk2] = findVaultPda(program.programId, "vault_knite", pair2);
     const [vs2] = findVaultPda(program.programId, "vault_sub", pair2);
+    const [lp2] = findVaultPda(program.programId, "lp_mint", pair2);
 
     try {
       await program.methods
-        .initializePair(treasury.publicKey)
+        .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
         .accounts({
           treasurySigner: attacker.publicKey, // 
+          registry: registryPda,
+          registryEntry: await nextRegistryEntry(),
           mintKnite: mintSub,
           mintSub: mintKnite,
           pair: pair2,
           vaultKnite: vk2,
           vaultSub: vs2,
+          lpMint: lp2,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
           tokenProgram: TOKEN_PROGRAM_ID,
           systemProgram: SystemProgram.programId,
           rent: anchor.web3.SYSVAR_RENT_PUBKEY,
@@ -248,9 +383,19 @@ describe("dex-core (Phase 1)", () => {
     await mintTo(provider, mintKnite, vaultKnite, treasury, 1_000_000_000n);
     await mintTo(provider, mintSub, vaultSub, treasury, 2_000_000_000n);
 
+    // pricing uses stored reserves: adopt the seeded balances
+    await program.methods
+      .sync()
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda, vaultKnite, vaultSub })
+      .signers([treasury])
+      .rpc();
+
     const rK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
     const rS = (await getAccount(connection, vaultSub, "confirmed", TOKEN_PROGRAM_ID)).amount;
     assert.ok(rK > 0n && rS > 0n);
+    const pair = await program.account.pair.fetch(pairPda);
+    assert.strictEqual(pair.reserveKnite.toString(), rK.toString());
+    assert.strictEqual(pair.reserveSub.toString(), rS.toString());
   });
 
   it("swap_exact_in: kNite -> Sub (fee from input to treasury ATA), respects min_out, updates reserves", async () => {
@@ -290,7 +435,8 @@ describe("dex-core (Phase 1)", () => {
 
         treasuryKniteAta,
 
-        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
       })
       .signers([user])
       .rpc();
@@ -348,7 +494,8 @@ describe("dex-core (Phase 1)", () => {
 
         treasuryKniteAta,
 
-        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
       })
       .signers([user])
       .rpc();
@@ -387,7 +534,8 @@ describe("dex-core (Phase 1)", () => {
           vaultKnite,
           vaultSub,
           treasuryKniteAta,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
         })
         .signers([user])
         .rpc();
@@ -418,7 +566,8 @@ describe("dex-core (Phase 1)", () => {
           vaultKnite,
           vaultSub,
           treasuryKniteAta,
-          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
         })
         .signers([user])
         .rpc();
@@ -443,7 +592,8 @@ describe("dex-core (Phase 1)", () => {
           vaultKnite,
           vaultSub,
           treasuryKniteAta: userKniteAta, //  wrong
-          tokenProgram: TOKEN_PROGRAM_ID,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
         })
         .signers([user])
         .rpc();
@@ -452,4 +602,2778 @@ describe("dex-core (Phase 1)", () => {
       assert.ok(e.toString().includes("Treasury ATA") || e.toString().includes("custom program error"));
     }
   });
+
+  it("swap_exact_out: kNite -> Sub delivers exact amount_out, fee on input, respects max_in", async () => {
+    const amountOut = 150_000n;
+
+    const reserveK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const reserveS = (await getAccount(connection, vaultSub, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    const netNeeded = cpIn(amountOut, reserveK, reserveS);
+    const amountIn = grossUpForFee(netNeeded, 30);
+    const fee = feeCeil(amountIn, 30);
+    const netIn = amountIn - fee;
+
+    const beforeTreasury = (await getAccount(connection, treasuryKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const beforeUserIn = (await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const beforeUserOut = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    await program.methods
+      .swapExactOut(new anchor.BN(amountOut.toString()), new anchor.BN(amountIn.toString()))
+      .accounts({
+        user: user.publicKey,
+        mintIn: mintKnite,
+        mintOut: mintSub,
+        pair: pairPda,
+        userAtaIn: userKniteAta,
+        userAtaOut: userSubAta,
+        vaultKnite,
+        vaultSub,
+        treasuryKniteAta,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const afterTreasury = (await getAccount(connection, treasuryKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const afterUserIn = (await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const afterUserOut = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const afterVK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    assert.strictEqual((afterUserOut - beforeUserOut).toString(), amountOut.toString());
+    assert.strictEqual((beforeUserIn - afterUserIn).toString(), amountIn.toString());
+    assert.strictEqual((afterTreasury - beforeTreasury).toString(), fee.toString());
+    assert.strictEqual((afterVK - reserveK).toString(), netIn.toString());
+  });
+
+  it("swap_exact_out: Sub -> kNite delivers exact amount_out, fee from vault kNite, respects max_in", async () => {
+    const amountOut = 80_000n;
+
+    const reserveK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const reserveS = (await getAccount(connection, vaultSub, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    const grossOut = grossUpForFee(amountOut, 30);
+    const fee = grossOut - amountOut;
+    const amountIn = cpIn(grossOut, reserveS, reserveK);
+
+    const beforeTreasury = (await getAccount(connection, treasuryKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const beforeUserIn = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const beforeUserOut = (await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    await program.methods
+      .swapExactOut(new anchor.BN(amountOut.toString()), new anchor.BN(amountIn.toString()))
+      .accounts({
+        user: user.publicKey,
+        mintIn: mintSub,
+        mintOut: mintKnite,
+        pair: pairPda,
+        userAtaIn: userSubAta,
+        userAtaOut: userKniteAta,
+        vaultKnite,
+        vaultSub,
+        treasuryKniteAta,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const afterTreasury = (await getAccount(connection, treasuryKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const afterUserIn = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const afterUserOut = (await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const afterVK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    assert.strictEqual((afterUserOut - beforeUserOut).toString(), amountOut.toString());
+    assert.strictEqual((beforeUserIn - afterUserIn).toString(), amountIn.toString());
+    assert.strictEqual((afterTreasury - beforeTreasury).toString(), fee.toString());
+    assert.strictEqual((reserveK - afterVK).toString(), grossOut.toString());
+  });
+
+  it("swap_exact_out: fails slippage if max_in too low", async () => {
+    try {
+      await program.methods
+        .swapExactOut(new anchor.BN("100000"), new anchor.BN("1"))
+        .accounts({
+          user: user.publicKey,
+          mintIn: mintKnite,
+          mintOut: mintSub,
+          pair: pairPda,
+          userAtaIn: userKniteAta,
+          userAtaOut: userSubAta,
+          vaultKnite,
+          vaultSub,
+          treasuryKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Expected SlippageExceeded");
+    } catch (e) {
+      assert.ok(e.toString().includes("Slippage") || e.toString().includes("custom program error"));
+    }
+  });
+
+  it("add_liquidity: first deposit mints sqrt(k*s) - MINIMUM_LIQUIDITY, rest locked", async () => {
+    // Fresh pair so the first-deposit path is exercised on empty vaults
+    const mk = await createTestMint(provider, decimals, treasury.publicKey);
+    const ms = await createTestMint(provider, decimals, treasury.publicKey);
+    const [lpPair] = findPairPda(program.programId, treasury.publicKey, mk.mintPk, ms.mintPk);
+    const [lpVK] = findVaultPda(program.programId, "vault_knite", lpPair);
+    const [lpVS] = findVaultPda(program.programId, "vault_sub", lpPair);
+    const [lpMintPk] = findVaultPda(program.programId, "lp_mint", lpPair);
+
+    await program.methods
+      .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: await nextRegistryEntry(),
+        mintKnite: mk.mintPk,
+        mintSub: ms.mintPk,
+        pair: lpPair,
+        vaultKnite: lpVK,
+        vaultSub: lpVS,
+        lpMint: lpMintPk,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([treasury])
+      .rpc();
+
+    const uK = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, mk.mintPk);
+    const uS = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, ms.mintPk);
+    const uLp = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, lpMintPk);
+    await mintTo(provider, mk.mintPk, uK, treasury, 10_000_000n);
+    await mintTo(provider, ms.mintPk, uS, treasury, 10_000_000n);
+
+    const a = 1_000_000n;
+    const b = 4_000_000n;
+    const expectedLp = 2_000_000n - 1_000n; // sqrt(1e6 * 4e6) - MINIMUM_LIQUIDITY
+
+    await program.methods
+      .addLiquidity(new anchor.BN(a.toString()), new anchor.BN(b.toString()), new anchor.BN(expectedLp.toString()))
+      .accounts({
+        user: user.publicKey,
+        pair: lpPair,
+        lpMint: lpMintPk,
+        userAtaKnite: uK,
+        userAtaSub: uS,
+        userAtaLp: uLp,
+        vaultKnite: lpVK,
+        vaultSub: lpVS,
+        mintKnite: mk.mintPk,
+        mintSub: ms.mintPk,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const lpBal = (await getAccount(connection, uLp, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual(lpBal.toString(), expectedLp.toString());
+    const pair = await program.account.pair.fetch(lpPair);
+    assert.strictEqual(pair.lpLocked.toString(), "1000");
+
+    // Second deposit is proportional: offering extra sub only pulls the matching ratio
+    await program.methods
+      .addLiquidity(new anchor.BN("100000"), new anchor.BN("1000000"), new anchor.BN("1"))
+      .accounts({
+        user: user.publicKey,
+        pair: lpPair,
+        lpMint: lpMintPk,
+        userAtaKnite: uK,
+        userAtaSub: uS,
+        userAtaLp: uLp,
+        vaultKnite: lpVK,
+        vaultSub: lpVS,
+        mintKnite: mk.mintPk,
+        mintSub: ms.mintPk,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const vK = (await getAccount(connection, lpVK, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const vS = (await getAccount(connection, lpVS, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual(vK.toString(), "1100000");
+    assert.strictEqual(vS.toString(), "4400000");
+
+    // remove_liquidity: burn all user LP for pro-rata reserves
+    const lpAll = (await getAccount(connection, uLp, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const totalLp = lpAll + 1_000n;
+    const outK = (lpAll * vK) / totalLp;
+    const outS = (lpAll * vS) / totalLp;
+
+    await program.methods
+      .removeLiquidity(new anchor.BN(lpAll.toString()), new anchor.BN(outK.toString()), new anchor.BN(outS.toString()))
+      .accounts({
+        user: user.publicKey,
+        pair: lpPair,
+        lpMint: lpMintPk,
+        userAtaKnite: uK,
+        userAtaSub: uS,
+        userAtaLp: uLp,
+        vaultKnite: lpVK,
+        vaultSub: lpVS,
+        mintKnite: mk.mintPk,
+        mintSub: ms.mintPk,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const vKAfter = (await getAccount(connection, lpVK, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const vSAfter = (await getAccount(connection, lpVS, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual((vK - vKAfter).toString(), outK.toString());
+    assert.strictEqual((vS - vSAfter).toString(), outS.toString());
+    assert.ok(vKAfter > 0n && vSAfter > 0n); // locked minimum keeps dust in the pool
+  });
+
+  it("add_liquidity: a first deposit into synced reserves is priced against them, not on top", async () => {
+    const mk = await createTestMint(provider, decimals, treasury.publicKey);
+    const ms = await createTestMint(provider, decimals, treasury.publicKey);
+    const [sPair] = findPairPda(program.programId, treasury.publicKey, mk.mintPk, ms.mintPk);
+    const [sVK] = findVaultPda(program.programId, "vault_knite", sPair);
+    const [sVS] = findVaultPda(program.programId, "vault_sub", sPair);
+    const [sLp] = findVaultPda(program.programId, "lp_mint", sPair);
+
+    await program.methods
+      .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: await nextRegistryEntry(),
+        mintKnite: mk.mintPk,
+        mintSub: ms.mintPk,
+        pair: sPair,
+        vaultKnite: sVK,
+        vaultSub: sVS,
+        lpMint: sLp,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([treasury])
+      .rpc();
+
+    // vaults funded directly, then adopted as reserves
+    await mintTo(provider, mk.mintPk, sVK, treasury, 1_000_000n);
+    await mintTo(provider, ms.mintPk, sVS, treasury, 4_000_000n);
+    await program.methods
+      .sync()
+      .accounts({ treasurySigner: treasury.publicKey, pair: sPair, vaultKnite: sVK, vaultSub: sVS })
+      .signers([treasury])
+      .rpc();
+
+    const uK = await ensureAta(provider, provider.wallet.publicKey, attacker.publicKey, mk.mintPk);
+    const uS = await ensureAta(provider, provider.wallet.publicKey, attacker.publicKey, ms.mintPk);
+    const uLp = await ensureAta(provider, provider.wallet.publicKey, attacker.publicKey, sLp);
+    await mintTo(provider, mk.mintPk, uK, treasury, 2_000n);
+    await mintTo(provider, ms.mintPk, uS, treasury, 2_000n);
+    const liquidityAccounts = {
+      user: attacker.publicKey,
+      pair: sPair,
+      lpMint: sLp,
+      userAtaKnite: uK,
+      userAtaSub: uS,
+      userAtaLp: uLp,
+      vaultKnite: sVK,
+      vaultSub: sVS,
+      mintKnite: mk.mintPk,
+      mintSub: ms.mintPk,
+      tokenProgramKnite: TOKEN_PROGRAM_ID,
+      tokenProgramSub: TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+    };
+
+    // the existing liquidity sqrt(1e6 * 4e6) = 2e6 is locked; 2000/2000 buys in at the 1:4 price
+    await program.methods
+      .addLiquidity(new anchor.BN("2000"), new anchor.BN("2000"), new anchor.BN("1"))
+      .accounts(liquidityAccounts)
+      .signers([attacker])
+      .rpc();
+
+    const lpMinted = (await getAccount(connection, uLp, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual(lpMinted.toString(), "1000");
+    const pair = await program.account.pair.fetch(sPair);
+    assert.strictEqual(pair.lpLocked.toString(), "2000000");
+    assert.strictEqual(pair.reserveKnite.toString(), "1000500");
+    assert.strictEqual(pair.reserveSub.toString(), "4002000");
+    assert.strictEqual((await getAccount(connection, uK, "confirmed", TOKEN_PROGRAM_ID)).amount.toString(), "1500");
+
+    // withdrawing straight away returns the deposit, not a share of the seeded reserves
+    await program.methods
+      .removeLiquidity(new anchor.BN(lpMinted.toString()), new anchor.BN("0"), new anchor.BN("0"))
+      .accounts(liquidityAccounts)
+      .signers([attacker])
+      .rpc();
+    assert.strictEqual((await getAccount(connection, uK, "confirmed", TOKEN_PROGRAM_ID)).amount.toString(), "2000");
+    assert.strictEqual((await getAccount(connection, uS, "confirmed", TOKEN_PROGRAM_ID)).amount.toString(), "2000");
+  });
+
+  it("swap_route: Sub A -> kNite -> Sub B atomically with one min_out", async () => {
+    // Second pair sharing kNite
+    const mB = await createTestMint(provider, decimals, treasury.publicKey);
+    const mintSubB = mB.mintPk;
+    const [pairB] = findPairPda(program.programId, treasury.publicKey, mintKnite, mintSubB);
+    const [vKB] = findVaultPda(program.programId, "vault_knite", pairB);
+    const [vSB] = findVaultPda(program.programId, "vault_sub", pairB);
+    const [lpB] = findVaultPda(program.programId, "lp_mint", pairB);
+
+    await program.methods
+      .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: await nextRegistryEntry(),
+        mintKnite,
+        mintSub: mintSubB,
+        pair: pairB,
+        vaultKnite: vKB,
+        vaultSub: vSB,
+        lpMint: lpB,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([treasury])
+      .rpc();
+    await mintTo(provider, mintKnite, vKB, treasury, 1_000_000_000n);
+    await mintTo(provider, mintSubB, vSB, treasury, 3_000_000_000n);
+    await program.methods
+      .sync()
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairB, vaultKnite: vKB, vaultSub: vSB })
+      .signers([treasury])
+      .rpc();
+
+    const userSubBAta = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, mintSubB);
+
+    const amountIn = 250_000n;
+
+    // hop 1: Sub A -> kNite (fee on output kNite)
+    const rK1 = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const rS1 = (await getAccount(connection, vaultSub, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const gross1 = cpOut(amountIn, rS1, rK1);
+    const fee1 = feeCeil(gross1, 30);
+    const out1 = gross1 - fee1;
+
+    // hop 2: kNite -> Sub B (fee on input kNite)
+    const rK2 = (await getAccount(connection, vKB, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const rS2 = (await getAccount(connection, vSB, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const fee2 = feeCeil(out1, 30);
+    const out2 = cpOut(out1 - fee2, rK2, rS2);
+
+    const beforeB = (await getAccount(connection, userSubBAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const beforeK = (await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    const hop = (pk) => ({ pubkey: pk, isWritable: true, isSigner: false });
+    const ro = (pk) => ({ pubkey: pk, isWritable: false, isSigner: false });
+    await program.methods
+      .swapRoute(new anchor.BN(amountIn.toString()), new anchor.BN(out2.toString()))
+      .accounts({
+        user: user.publicKey,
+        userAtaIn: userSubAta,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        tokenProgram2022: TOKEN_2022_PROGRAM_ID,
+      })
+      .remainingAccounts([
+        hop(pairPda),
+        hop(mintKnite),
+        ro(mintSub),
+        hop(vaultKnite),
+        hop(vaultSub),
+        hop(treasuryKniteAta),
+        hop(userKniteAta),
+        hop(pairB),
+        hop(mintKnite),
+        ro(mintSubB),
+        hop(vKB),
+        hop(vSB),
+        hop(treasuryKniteAta),
+        hop(userSubBAta),
+      ])
+      .signers([user])
+      .rpc();
+
+    const afterB = (await getAccount(connection, userSubBAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const afterK = (await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual((afterB - beforeB).toString(), out2.toString());
+    // intermediate kNite passes straight through
+    assert.strictEqual(afterK.toString(), beforeK.toString());
+  });
+
+  it("oracle: swaps accrue cumulative prices; observe rejects windows older than history", async () => {
+    const pair = await program.account.pair.fetch(pairPda);
+    assert.ok(pair.lastUpdateTs.toNumber() > 0);
+    assert.ok(pair.observationCount >= 1);
+    assert.strictEqual(pair.observations.length, 16);
+
+    try {
+      await program.methods
+        .observe(3600 * 24)
+        .accounts({ pair: pairPda })
+        .view();
+      assert.fail("Expected OracleWindowUnavailable");
+    } catch (e) {
+      assert.ok(e.toString().includes("oracle history") || e.toString().includes("custom program error"));
+    }
+  });
+
+  it("admin: only treasury can pause/resume and update fee (capped)", async () => {
+    // attacker cannot pause
+    try {
+      await program.methods
+        .setPairEnabled(false)
+        .accounts({ treasurySigner: attacker.publicKey, pair: pairPda })
+        .signers([attacker])
+        .rpc();
+      assert.fail("Expected Unauthorized");
+    } catch (e) {
+      assert.ok(e.toString().includes("Unauthorized") || e.toString().includes("custom program error"));
+    }
+
+    // treasury pauses -> swaps rejected
+    await program.methods
+      .setPairEnabled(false)
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+    assert.strictEqual((await program.account.pair.fetch(pairPda)).enabled, false);
+
+    try {
+      await program.methods
+        .swapExactIn(new anchor.BN("10000"), new anchor.BN("1"))
+        .accounts({
+          user: user.publicKey,
+          mintIn: mintKnite,
+          mintOut: mintSub,
+          pair: pairPda,
+          userAtaIn: userKniteAta,
+          userAtaOut: userSubAta,
+          vaultKnite,
+          vaultSub,
+          treasuryKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Expected PairDisabled");
+    } catch (e) {
+      assert.ok(e.toString().includes("disabled") || e.toString().includes("custom program error"));
+    }
+
+    // resume
+    await program.methods
+      .setPairEnabled(true)
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+    assert.strictEqual((await program.account.pair.fetch(pairPda)).enabled, true);
+
+    // fee above the hard cap is rejected
+    try {
+      await program.methods
+        .setSwapFeeBps(1001)
+        .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+        .signers([treasury])
+        .rpc();
+      assert.fail("Expected InvalidFee");
+    } catch (e) {
+      assert.ok(e.toString().includes("fee") || e.toString().includes("custom program error"));
+    }
+
+    await program.methods
+      .setSwapFeeBps(50)
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+    assert.strictEqual((await program.account.pair.fetch(pairPda)).swapFeeBps, 50);
+
+    // restore default for the remaining tests
+    await program.methods
+      .setSwapFeeBps(30)
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+  });
+
+  it("swap_exact_in_with_deadline: rejects past deadline with Expired, succeeds within bound", async () => {
+    const swapAccounts = {
+      user: user.publicKey,
+      mintIn: mintKnite,
+      mintOut: mintSub,
+      pair: pairPda,
+      userAtaIn: userKniteAta,
+      userAtaOut: userSubAta,
+      vaultKnite,
+      vaultSub,
+      treasuryKniteAta,
+      tokenProgramKnite: TOKEN_PROGRAM_ID,
+      tokenProgramSub: TOKEN_PROGRAM_ID,
+    };
+
+    try {
+      await program.methods
+        .swapExactInWithDeadline(new anchor.BN("10000"), new anchor.BN("1"), new anchor.BN(1), null)
+        .accounts(swapAccounts)
+        .signers([user])
+        .rpc();
+      assert.fail("Expected Expired");
+    } catch (e) {
+      assert.ok(e.toString().includes("expired") || e.toString().includes("custom program error"));
+    }
+
+    try {
+      await program.methods
+        .swapExactInWithDeadline(new anchor.BN("10000"), new anchor.BN("1"), null, new anchor.BN(0))
+        .accounts(swapAccounts)
+        .signers([user])
+        .rpc();
+      assert.fail("Expected Expired");
+    } catch (e) {
+      assert.ok(e.toString().includes("expired") || e.toString().includes("custom program error"));
+    }
+
+    const deadline = Math.floor(Date.now() / 1000) + 600;
+    await program.methods
+      .swapExactInWithDeadline(new anchor.BN("10000"), new anchor.BN("1"), new anchor.BN(deadline), null)
+      .accounts(swapAccounts)
+      .signers([user])
+      .rpc();
+  });
+
+  it("quote_exact_in / quote_exact_out: return data matches swap math, no transfers", async () => {
+    const amountIn = 120_000n;
+    const reserveK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const reserveS = (await getAccount(connection, vaultSub, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    const quoteAccounts = { mintIn: mintKnite, mintOut: mintSub, pair: pairPda };
+
+    const q = await program.methods
+      .quoteExactIn(new anchor.BN(amountIn.toString()))
+      .accounts(quoteAccounts)
+      .view();
+
+    const fee = feeCeil(amountIn, 30);
+    const netOut = cpOut(amountIn - fee, reserveK, reserveS);
+    assert.strictEqual(q.feeKnite.toString(), fee.toString());
+    assert.strictEqual(q.netOut.toString(), netOut.toString());
+    assert.strictEqual(q.grossOut.toString(), cpOut(amountIn, reserveK, reserveS).toString());
+    assert.strictEqual(q.reserveKniteAfter.toString(), (reserveK + amountIn - fee).toString());
+    assert.strictEqual(q.reserveSubAfter.toString(), (reserveS - netOut).toString());
+
+    // vaults untouched by the quote
+    const afterK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual(afterK.toString(), reserveK.toString());
+
+    const amountOut = 50_000n;
+    const qOut = await program.methods
+      .quoteExactOut(new anchor.BN(amountOut.toString()))
+      .accounts({ ...quoteAccounts, mintIn: mintSub, mintOut: mintKnite })
+      .view();
+    const grossOut = grossUpForFee(amountOut, 30);
+    assert.strictEqual(qOut.netOut.toString(), amountOut.toString());
+    assert.strictEqual(qOut.grossOut.toString(), grossOut.toString());
+    assert.strictEqual(qOut.amountIn.toString(), cpIn(grossOut, reserveS, reserveK).toString());
+  });
+
+  it("circuit breaker: rejects swaps over max price impact; slot volume cap disables the pair", async () => {
+    const swapAccounts = {
+      user: user.publicKey,
+      mintIn: mintKnite,
+      mintOut: mintSub,
+      pair: pairPda,
+      userAtaIn: userKniteAta,
+      userAtaOut: userSubAta,
+      vaultKnite,
+      vaultSub,
+      treasuryKniteAta,
+      tokenProgramKnite: TOKEN_PROGRAM_ID,
+      tokenProgramSub: TOKEN_PROGRAM_ID,
+    };
+
+    // 10 bps max marginal price move
+    await program.methods
+      .setCircuitBreaker(10, new anchor.BN(0))
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+
+    try {
+      await program.methods
+        .swapExactIn(new anchor.BN("100000000"), new anchor.BN("1")) // ~10% of kNite reserve
+        .accounts(swapAccounts)
+        .signers([user])
+        .rpc();
+      assert.fail("Expected PriceImpactTooHigh");
+    } catch (e) {
+      assert.ok(e.toString().includes("Price impact") || e.toString().includes("custom program error"));
+    }
+
+    // the quote for the same swap fails the same way
+    try {
+      await program.methods
+        .quoteExactIn(new anchor.BN("100000000"))
+        .accounts({ mintIn: mintKnite, mintOut: mintSub, pair: pairPda })
+        .view();
+      assert.fail("Expected PriceImpactTooHigh");
+    } catch (e) {
+      const logs = (e.simulationResponse?.logs ?? e.logs ?? []).join("\n");
+      assert.ok(logs.includes("PriceImpactTooHigh") || e.toString().includes("custom program error"), e.toString());
+    }
+
+    // tiny volume cap: the crossing swap settles, then the pair trips
+    await program.methods
+      .setCircuitBreaker(0, new anchor.BN(1000))
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+
+    await program.methods
+      .swapExactIn(new anchor.BN("5000"), new anchor.BN("1"))
+      .accounts(swapAccounts)
+      .signers([user])
+      .rpc();
+    assert.strictEqual((await program.account.pair.fetch(pairPda)).enabled, false);
+
+    // reset for the remaining tests
+    await program.methods
+      .setCircuitBreaker(0, new anchor.BN(0))
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+    await program.methods
+      .setPairEnabled(true)
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+  });
+
+  it("skim / sync: donations do not move the price; skim sends surplus to treasury; sync is treasury-only", async () => {
+    const treasurySubAta = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mintSub);
+    const quoteAccounts = { mintIn: mintKnite, mintOut: mintSub, pair: pairPda };
+    const before = await program.methods.quoteExactIn(new anchor.BN("100000")).accounts(quoteAccounts).view();
+
+    // donate straight into the vaults
+    await mintTo(provider, mintKnite, vaultKnite, treasury, 7_000n);
+    await mintTo(provider, mintSub, vaultSub, treasury, 9_000n);
+
+    const donated = await program.methods.quoteExactIn(new anchor.BN("100000")).accounts(quoteAccounts).view();
+    assert.strictEqual(donated.netOut.toString(), before.netOut.toString());
+
+    try {
+      await program.methods
+        .sync()
+        .accounts({ treasurySigner: attacker.publicKey, pair: pairPda, vaultKnite, vaultSub })
+        .signers([attacker])
+        .rpc();
+      assert.fail("Expected Unauthorized");
+    } catch (e) {
+      assert.ok(e.toString().includes("Unauthorized") || e.toString().includes("custom program error"));
+    }
+
+    const tK0 = (await getAccount(connection, treasuryKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const tS0 = (await getAccount(connection, treasurySubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    // no signer needed: surplus always lands in the treasury ATAs
+    await program.methods
+      .skim()
+      .accounts({
+        pair: pairPda,
+        mintKnite,
+        mintSub,
+        vaultKnite,
+        vaultSub,
+        treasuryKniteAta,
+        treasurySubAta,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+
+    const tK1 = (await getAccount(connection, treasuryKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const tS1 = (await getAccount(connection, treasurySubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual((tK1 - tK0).toString(), "7000");
+    assert.strictEqual((tS1 - tS0).toString(), "9000");
+
+    const pair = await program.account.pair.fetch(pairPda);
+    const vK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const vS = (await getAccount(connection, vaultSub, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual(pair.reserveKnite.toString(), vK.toString());
+    assert.strictEqual(pair.reserveSub.toString(), vS.toString());
+
+    // treasury sync adopts a donation into the reserves
+    await mintTo(provider, mintSub, vaultSub, treasury, 1_000_000n);
+    await program.methods
+      .sync()
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda, vaultKnite, vaultSub })
+      .signers([treasury])
+      .rpc();
+    const synced = await program.account.pair.fetch(pairPda);
+    assert.strictEqual(synced.reserveSub.toString(), (vS + 1_000_000n).toString());
+  });
+
+  it("flash_borrow / flash_repay: principal back in the vault, fee to treasury, repay must follow in the same tx", async () => {
+    const treasurySubAta = ata(treasury.publicKey, mintSub);
+    const flashAccounts = {
+      borrower: user.publicKey,
+      pair: pairPda,
+      mint: mintSub,
+      vault: vaultSub,
+      borrowerAta: userSubAta,
+      treasuryAta: treasurySubAta,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      instructions: anchor.web3.SYSVAR_INSTRUCTIONS_PUBKEY,
+    };
+    const amount = 1_000_000n;
+    const pairBefore = await program.account.pair.fetch(pairPda);
+    assert.strictEqual(pairBefore.flashFeeBps, 9);
+    const fee = feeCeil(amount, pairBefore.flashFeeBps);
+
+    const v0 = (await getAccount(connection, vaultSub, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const t0 = (await getAccount(connection, treasurySubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const u0 = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    const borrowIx = await program.methods.flashBorrow(new anchor.BN(amount.toString())).accounts(flashAccounts).instruction();
+    const repayIx = await program.methods.flashRepay().accounts(flashAccounts).instruction();
+    await provider.sendAndConfirm(new anchor.web3.Transaction().add(borrowIx, repayIx), [user], { commitment: "confirmed" });
+
+    const v1 = (await getAccount(connection, vaultSub, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const t1 = (await getAccount(connection, treasurySubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const u1 = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual(v1.toString(), v0.toString());
+    assert.strictEqual((t1 - t0).toString(), fee.toString());
+    assert.strictEqual((u0 - u1).toString(), fee.toString());
+
+    const pairAfter = await program.account.pair.fetch(pairPda);
+    assert.strictEqual(pairAfter.flashLoanActive, false);
+    assert.strictEqual(pairAfter.reserveSub.toString(), pairBefore.reserveSub.toString());
+
+    // borrow without a repay later in the transaction
+    try {
+      await program.methods
+        .flashBorrow(new anchor.BN(amount.toString()))
+        .accounts(flashAccounts)
+        .signers([user])
+        .rpc();
+      assert.fail("Expected FlashRepayMissing");
+    } catch (e) {
+      assert.ok(e.toString().includes("FlashRepayMissing") || e.toString().includes("custom program error"));
+    }
+
+    // repay with nothing outstanding
+    try {
+      await program.methods.flashRepay().accounts(flashAccounts).signers([user]).rpc();
+      assert.fail("Expected NoFlashLoan");
+    } catch (e) {
+      assert.ok(e.toString().includes("NoFlashLoan") || e.toString().includes("custom program error"));
+    }
+  });
+
+  it("token-2022: transfer-fee sub mint prices on received amounts; unsafe extensions are rejected", async () => {
+    // 1% transfer fee on the sub token
+    const mk = await createTestMint(provider, decimals, treasury.publicKey);
+    const sub22 = await createToken2022Mint(provider, decimals, treasury.publicKey, [ExtensionType.TransferFeeConfig], (m) => [
+      createInitializeTransferFeeConfigInstruction(m, treasury.publicKey, treasury.publicKey, 100, 1_000_000_000n, TOKEN_2022_PROGRAM_ID),
+    ]);
+    const [pair22] = findPairPda(program.programId, treasury.publicKey, mk.mintPk, sub22);
+    const [vK22] = findVaultPda(program.programId, "vault_knite", pair22);
+    const [vS22] = findVaultPda(program.programId, "vault_sub", pair22);
+    const [lp22] = findVaultPda(program.programId, "lp_mint", pair22);
+
+    await program.methods
+      .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: await nextRegistryEntry(),
+        mintKnite: mk.mintPk,
+        mintSub: sub22,
+        pair: pair22,
+        vaultKnite: vK22,
+        vaultSub: vS22,
+        lpMint: lp22,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_2022_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([treasury])
+      .rpc();
+
+    const uK = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, mk.mintPk);
+    const uS = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, sub22, TOKEN_2022_PROGRAM_ID);
+    const uLp = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, lp22);
+    const tK = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mk.mintPk);
+    await mintTo(provider, mk.mintPk, uK, treasury, 100_000_000n);
+    await mintTo(provider, sub22, uS, treasury, 100_000_000n, TOKEN_2022_PROGRAM_ID);
+
+    // first deposit: the sub vault receives 1% less, reserves + LP follow the received amount
+    await program.methods
+      .addLiquidity(new anchor.BN("10000000"), new anchor.BN("20000000"), new anchor.BN("1"))
+      .accounts({
+        user: user.publicKey,
+        pair: pair22,
+        lpMint: lp22,
+        mintKnite: mk.mintPk,
+        mintSub: sub22,
+        userAtaKnite: uK,
+        userAtaSub: uS,
+        userAtaLp: uLp,
+        vaultKnite: vK22,
+        vaultSub: vS22,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_2022_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    let pair = await program.account.pair.fetch(pair22);
+    assert.strictEqual(pair.reserveKnite.toString(), "10000000");
+    assert.strictEqual(pair.reserveSub.toString(), "19800000");
+    const vS = (await getAccount(connection, vS22, "confirmed", TOKEN_2022_PROGRAM_ID)).amount;
+    assert.strictEqual(vS.toString(), "19800000");
+
+    // Sub -> kNite: curve input is what the vault received (1_000_000 - 1%)
+    const amountIn = 1_000_000n;
+    const received = amountIn - 10_000n;
+    const gross = cpOut(received, 19_800_000n, 10_000_000n);
+    const netOut = gross - feeCeil(gross, 30);
+    const beforeK = (await getAccount(connection, uK, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    await program.methods
+      .swapExactIn(new anchor.BN(amountIn.toString()), new anchor.BN(netOut.toString()))
+      .accounts({
+        user: user.publicKey,
+        mintIn: sub22,
+        mintOut: mk.mintPk,
+        pair: pair22,
+        userAtaIn: uS,
+        userAtaOut: uK,
+        vaultKnite: vK22,
+        vaultSub: vS22,
+        treasuryKniteAta: tK,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_2022_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const afterK = (await getAccount(connection, uK, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual((afterK - beforeK).toString(), netOut.toString());
+    pair = await program.account.pair.fetch(pair22);
+    assert.strictEqual(pair.reserveSub.toString(), (19_800_000n + received).toString());
+
+    // non-transferable sub mint cannot be listed
+    const locked = await createToken2022Mint(provider, decimals, treasury.publicKey, [ExtensionType.NonTransferable], (m) => [
+      createInitializeNonTransferableMintInstruction(m, TOKEN_2022_PROGRAM_ID),
+    ]);
+    const [pairNt] = findPairPda(program.programId, treasury.publicKey, mk.mintPk, locked);
+    try {
+      await program.methods
+        .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+        .accounts({
+          treasurySigner: treasury.publicKey,
+          registry: registryPda,
+          registryEntry: await nextRegistryEntry(),
+          mintKnite: mk.mintPk,
+          mintSub: locked,
+          pair: pairNt,
+          vaultKnite: findVaultPda(program.programId, "vault_knite", pairNt)[0],
+          vaultSub: findVaultPda(program.programId, "vault_sub", pairNt)[0],
+          lpMint: findVaultPda(program.programId, "lp_mint", pairNt)[0],
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_2022_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
+      assert.fail("Expected UnsupportedMintExtension");
+    } catch (e) {
+      assert.ok(e.toString().includes("extension") || e.toString().includes("custom program error"));
+    }
+  });
+
+  it("stable swap: pegged pair prices on the StableSwap invariant; amp ramps are treasury-only and bounded", async () => {
+    const mk = await createTestMint(provider, decimals, treasury.publicKey);
+    const ms = await createTestMint(provider, decimals, treasury.publicKey);
+    const [pairS] = findPairPda(program.programId, treasury.publicKey, mk.mintPk, ms.mintPk);
+    const [vK] = findVaultPda(program.programId, "vault_knite", pairS);
+    const [vS] = findVaultPda(program.programId, "vault_sub", pairS);
+    const [lp] = findVaultPda(program.programId, "lp_mint", pairS);
+    const initAccounts = {
+      treasurySigner: treasury.publicKey,
+      registry: registryPda,
+      registryEntry: await nextRegistryEntry(),
+      mintKnite: mk.mintPk,
+      mintSub: ms.mintPk,
+      pair: pairS,
+      vaultKnite: vK,
+      vaultSub: vS,
+      lpMint: lp,
+      tokenProgramKnite: TOKEN_PROGRAM_ID,
+      tokenProgramSub: TOKEN_PROGRAM_ID,
+      tokenProgram: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    };
+
+    // amp 0 is only valid for constant product
+    try {
+      await program.methods.initializePair(treasury.publicKey, STABLE_SWAP, new anchor.BN(0)).accounts(initAccounts).signers([treasury]).rpc();
+      assert.fail("Expected InvalidAmp");
+    } catch (e) {
+      assert.ok(e.toString().includes("InvalidAmp") || e.toString().includes("custom program error"));
+    }
+
+    const amp = 100;
+    await program.methods.initializePair(treasury.publicKey, STABLE_SWAP, new anchor.BN(amp)).accounts(initAccounts).signers([treasury]).rpc();
+    let pair = await program.account.pair.fetch(pairS);
+    assert.ok(pair.curveType.stableSwap);
+    assert.strictEqual(pair.ampTarget.toString(), String(amp));
+
+    const uK = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, mk.mintPk);
+    const uS = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, ms.mintPk);
+    const uLp = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, lp);
+    const tK = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mk.mintPk);
+    await mintTo(provider, mk.mintPk, uK, treasury, 100_000_000n);
+    await mintTo(provider, ms.mintPk, uS, treasury, 100_000_000n);
+
+    await program.methods
+      .addLiquidity(new anchor.BN("10000000"), new anchor.BN("10000000"), new anchor.BN("1"))
+      .accounts({
+        user: user.publicKey,
+        pair: pairS,
+        lpMint: lp,
+        mintKnite: mk.mintPk,
+        mintSub: ms.mintPk,
+        userAtaKnite: uK,
+        userAtaSub: uS,
+        userAtaLp: uLp,
+        vaultKnite: vK,
+        vaultSub: vS,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    // kNite -> Sub: same fee handling as constant product, StableSwap output on the net input
+    const amountIn = 1_000_000n;
+    const net = amountIn - feeCeil(amountIn, 30);
+    const expectedOut = stableOut(net, 10_000_000n, 10_000_000n, amp);
+    assert.ok(expectedOut > cpOut(net, 10_000_000n, 10_000_000n));
+
+    const s0 = (await getAccount(connection, uS, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    await program.methods
+      .swapExactIn(new anchor.BN(amountIn.toString()), new anchor.BN(expectedOut.toString()))
+      .accounts({
+        user: user.publicKey,
+        mintIn: mk.mintPk,
+        mintOut: ms.mintPk,
+        pair: pairS,
+        userAtaIn: uK,
+        userAtaOut: uS,
+        vaultKnite: vK,
+        vaultSub: vS,
+        treasuryKniteAta: tK,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+    const s1 = (await getAccount(connection, uS, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual((s1 - s0).toString(), expectedOut.toString());
+
+    // ramp: too short, too far, and on a constant-product pair are rejected
+    const now = Math.floor(Date.now() / 1000);
+    const rampFails = async (pk, target, endTs, name) => {
+      try {
+        await program.methods
+          .rampAmp(new anchor.BN(target), new anchor.BN(endTs))
+          .accounts({ treasurySigner: treasury.publicKey, pair: pk })
+          .signers([treasury])
+          .rpc();
+        assert.fail(`Expected ${name}`);
+      } catch (e) {
+        assert.ok(e.toString().includes(name) || e.toString().includes("custom program error"));
+      }
+    };
+    await rampFails(pairS, 200, now + 60, "InvalidAmp");
+    await rampFails(pairS, amp * 11, now + 2 * 86_400, "InvalidAmp");
+    await rampFails(pairPda, 200, now + 2 * 86_400, "InvalidCurve");
+
+    await program.methods
+      .rampAmp(new anchor.BN(200), new anchor.BN(now + 2 * 86_400))
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairS })
+      .signers([treasury])
+      .rpc();
+    pair = await program.account.pair.fetch(pairS);
+    assert.strictEqual(pair.ampInitial.toString(), String(amp));
+    assert.strictEqual(pair.ampTarget.toString(), "200");
+
+    // stop freezes the amp where the ramp currently is
+    await program.methods.stopRampAmp().accounts({ treasurySigner: treasury.publicKey, pair: pairS }).signers([treasury]).rpc();
+    pair = await program.account.pair.fetch(pairS);
+    assert.strictEqual(pair.ampInitial.toString(), pair.ampTarget.toString());
+    assert.ok(pair.ampTarget.toNumber() >= amp && pair.ampTarget.toNumber() < 200);
+  });
+
+  it("generic pairs: factory for any two mints, canonical order, fee in the input token, listed in the registry", async () => {
+    // two sub tokens, sorted into canonical order
+    const m1 = (await createTestMint(provider, decimals, treasury.publicKey)).mintPk;
+    const m2 = (await createTestMint(provider, decimals, treasury.publicKey)).mintPk;
+    const [mintA, mintB] = Buffer.compare(m1.toBuffer(), m2.toBuffer()) < 0 ? [m1, m2] : [m2, m1];
+
+    const genericPda = (a, b) =>
+      PublicKey.findProgramAddressSync([Buffer.from("generic_pair"), a.toBuffer(), b.toBuffer()], program.programId)[0];
+    const registryEntry = await nextRegistryEntry();
+    const initAccounts = (a, b) => {
+      const pk = genericPda(a, b);
+      return {
+        authority: treasury.publicKey,
+        registry: registryPda,
+        registryEntry,
+        mintA: a,
+        mintB: b,
+        pair: pk,
+        vaultA: findVaultPda(program.programId, "vault_a", pk)[0],
+        vaultB: findVaultPda(program.programId, "vault_b", pk)[0],
+        lpMint: findVaultPda(program.programId, "lp_mint", pk)[0],
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      };
+    };
+
+    // reversed mints cannot open a second pair for the same set
+    try {
+      await program.methods.initializeGenericPair(30, { inputToken: {} }).accounts(initAccounts(mintB, mintA)).signers([treasury]).rpc();
+      assert.fail("Expected MintsNotCanonical");
+    } catch (e) {
+      assert.ok(e.toString().includes("MintsNotCanonical") || e.toString().includes("custom program error"));
+    }
+
+    // only the registry authority creates pairs
+    try {
+      await program.methods
+        .initializeGenericPair(30, { inputToken: {} })
+        .accounts({ ...initAccounts(mintA, mintB), authority: attacker.publicKey })
+        .signers([attacker])
+        .rpc();
+      assert.fail("Expected Unauthorized");
+    } catch (e) {
+      assert.ok(e.toString().includes("Unauthorized") || e.toString().includes("custom program error"));
+    }
+
+    const g = initAccounts(mintA, mintB);
+    await program.methods.initializeGenericPair(30, { inputToken: {} }).accounts(g).signers([treasury]).rpc();
+
+    const entry = await program.account.pairRegistryEntry.fetch(registryEntry);
+    assert.ok(entry.pair.equals(g.pair));
+    assert.ok(entry.kind.generic);
+    assert.ok(entry.mint0.equals(mintA) && entry.mint1.equals(mintB));
+    assert.strictEqual(entry.feeBps, 30);
+
+    const uA = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, mintA);
+    const uB = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, mintB);
+    const uLp = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, g.lpMint);
+    const tA = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mintA);
+    const tB = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mintB);
+    await mintTo(provider, mintA, uA, treasury, 100_000_000n);
+    await mintTo(provider, mintB, uB, treasury, 100_000_000n);
+
+    await program.methods
+      .addGenericLiquidity(new anchor.BN("10000000"), new anchor.BN("20000000"), new anchor.BN("1"))
+      .accounts({
+        user: user.publicKey,
+        pair: g.pair,
+        lpMint: g.lpMint,
+        mintA,
+        mintB,
+        userAtaA: uA,
+        userAtaB: uB,
+        userAtaLp: uLp,
+        vaultA: g.vaultA,
+        vaultB: g.vaultB,
+        tokenProgramA: TOKEN_PROGRAM_ID,
+        tokenProgramB: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+
+    const swapAccounts = (ataIn, ataOut, feeAta) => ({
+      user: user.publicKey,
+      pair: g.pair,
+      mintA,
+      mintB,
+      userAtaIn: ataIn,
+      userAtaOut: ataOut,
+      vaultA: g.vaultA,
+      vaultB: g.vaultB,
+      treasuryFeeAta: feeAta,
+      tokenProgramA: TOKEN_PROGRAM_ID,
+      tokenProgramB: TOKEN_PROGRAM_ID,
+    });
+
+    // a -> b: fee in a (the input)
+    const amountIn = 1_000_000n;
+    const feeA = feeCeil(amountIn, 30);
+    const outB = cpOut(amountIn - feeA, 10_000_000n, 20_000_000n);
+    const tA0 = (await getAccount(connection, tA, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const uB0 = (await getAccount(connection, uB, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    await program.methods
+      .swapGeneric(new anchor.BN(amountIn.toString()), new anchor.BN(outB.toString()))
+      .accounts(swapAccounts(uA, uB, tA))
+      .signers([user])
+      .rpc();
+    assert.strictEqual(((await getAccount(connection, tA, "confirmed", TOKEN_PROGRAM_ID)).amount - tA0).toString(), feeA.toString());
+    assert.strictEqual(((await getAccount(connection, uB, "confirmed", TOKEN_PROGRAM_ID)).amount - uB0).toString(), outB.toString());
+
+    // b -> a: fee in b; a fee ATA of the wrong mint is rejected
+    const rA = 10_000_000n + (amountIn - feeA);
+    const rB = 20_000_000n - outB;
+    try {
+      await program.methods
+        .swapGeneric(new anchor.BN(amountIn.toString()), new anchor.BN(1))
+        .accounts(swapAccounts(uB, uA, tA))
+        .signers([user])
+        .rpc();
+      assert.fail("Expected InvalidTreasuryAta");
+    } catch (e) {
+      assert.ok(e.toString().includes("InvalidTreasuryAta") || e.toString().includes("custom program error"));
+    }
+
+    const feeB = feeCeil(amountIn, 30);
+    const outA = cpOut(amountIn - feeB, rB, rA);
+    const tB0 = (await getAccount(connection, tB, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    await program.methods
+      .swapGeneric(new anchor.BN(amountIn.toString()), new anchor.BN(outA.toString()))
+      .accounts(swapAccounts(uB, uA, tB))
+      .signers([user])
+      .rpc();
+    assert.strictEqual(((await getAccount(connection, tB, "confirmed", TOKEN_PROGRAM_ID)).amount - tB0).toString(), feeB.toString());
+
+    const pair = await program.account.genericPair.fetch(g.pair);
+    assert.strictEqual(pair.reserveA.toString(), (rA - outA).toString());
+    assert.strictEqual(pair.reserveB.toString(), (rB + amountIn - feeB).toString());
+  });
+
+  it("pair registry: one entry PDA per pair (kNite and generic) with mints, fee and created_at, listed by index", async () => {
+    const registry = await program.account.pairRegistry.fetch(registryPda);
+    assert.ok(registry.authority.equals(treasury.publicKey));
+
+    // indexes [0, pair_count) all have an entry (no pair closed yet)
+    const count = registry.pairCount.toNumber();
+    const keys = [...Array(count).keys()].map(registryEntryPda);
+    const entries = await program.account.pairRegistryEntry.fetchMultiple(keys, "confirmed");
+    entries.forEach((e, i) => assert.strictEqual(e.index.toNumber(), i));
+    assert.strictEqual(new Set(entries.map((e) => e.pair.toBase58())).size, count);
+
+    const mainIndex = entries.findIndex((e) => e.pair.equals(pairPda));
+    const main = entries[mainIndex];
+    assert.ok(main.kind.knite);
+    assert.ok(main.mint0.equals(mintKnite) && main.mint1.equals(mintSub));
+    assert.strictEqual(main.feeBps, 30);
+    assert.ok(main.createdAt.toNumber() > 0);
+
+    // documented raw layouts: pair_count at 41 in the registry; pair at 17, kind at 49 in an entry
+    const raw = (await connection.getAccountInfo(registryPda, "confirmed")).data;
+    assert.strictEqual(raw.length, 49);
+    assert.strictEqual(raw.readBigUInt64LE(41), BigInt(count));
+    const rawMain = (await connection.getAccountInfo(keys[mainIndex], "confirmed")).data;
+    assert.ok(new PublicKey(rawMain.subarray(17, 49)).equals(pairPda));
+    assert.strictEqual(rawMain[49], 0);
+  });
+
+  it("fee split: must sum to 10_000 bps; LP share stays in the vault, burn share leaves kNite supply", async () => {
+    const treasuryOnly = { lpBps: 0, treasuryBps: 10_000, burnBps: 0 };
+    assert.deepStrictEqual((await program.account.pair.fetch(pairPda)).feeSplit, treasuryOnly);
+
+    try {
+      await program.methods
+        .setFeeSplit({ lpBps: 5_000, treasuryBps: 3_000, burnBps: 1_000 })
+        .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+        .signers([treasury])
+        .rpc();
+      assert.fail("Expected InvalidFeeSplit");
+    } catch (e) {
+      assert.ok(e.toString().includes("InvalidFeeSplit") || e.toString().includes("custom program error"));
+    }
+
+    try {
+      await program.methods
+        .setFeeSplit({ lpBps: 0, treasuryBps: 0, burnBps: 10_000 })
+        .accounts({ treasurySigner: attacker.publicKey, pair: pairPda })
+        .signers([attacker])
+        .rpc();
+      assert.fail("Expected Unauthorized");
+    } catch (e) {
+      assert.ok(e.toString().includes("Unauthorized") || e.toString().includes("custom program error"));
+    }
+
+    await program.methods
+      .setFeeSplit({ lpBps: 5_000, treasuryBps: 3_000, burnBps: 2_000 })
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+
+    const amountIn = 100_000n;
+    const before = await program.account.pair.fetch(pairPda);
+    const reserveK = BigInt(before.reserveKnite.toString());
+    const reserveS = BigInt(before.reserveSub.toString());
+
+    const fee = feeCeil(amountIn, before.swapFeeBps);
+    const feeLp = (fee * 5_000n) / 10_000n;
+    const feeBurn = (fee * 2_000n) / 10_000n;
+    const feeTreasury = fee - feeLp - feeBurn;
+    const netIn = amountIn - fee;
+    const expectedOut = cpOut(netIn, reserveK, reserveS);
+
+    const beforeTreasury = (await getAccount(connection, treasuryKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const beforeVK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const beforeSupply = (await getMint(connection, mintKnite, "confirmed", TOKEN_PROGRAM_ID)).supply;
+
+    const splitSwap = (extra = {}) =>
+      program.methods
+        .swapExactIn(new anchor.BN(amountIn.toString()), new anchor.BN(expectedOut.toString()))
+        .accounts({
+          user: user.publicKey,
+          mintIn: mintKnite,
+          mintOut: mintSub,
+          pair: pairPda,
+          userAtaIn: userKniteAta,
+          userAtaOut: userSubAta,
+          vaultKnite,
+          vaultSub,
+          treasuryKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+          ...extra,
+        })
+        .signers([user]);
+
+    // swaps keep the mints read-only; a burning split needs the kNite mint as writable burn_mint
+    const plainIx = await splitSwap().instruction();
+    assert.strictEqual(plainIx.keys.find((k) => k.pubkey.equals(mintKnite)).isWritable, false);
+    try {
+      await splitSwap().rpc();
+      assert.fail("Expected BurnMintRequired");
+    } catch (e) {
+      assert.ok(e.toString().includes("BurnMintRequired") || e.toString().includes("custom program error"));
+    }
+    await splitSwap({ burnMint: mintKnite }).rpc();
+
+    const afterTreasury = (await getAccount(connection, treasuryKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const afterVK = (await getAccount(connection, vaultKnite, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const afterSupply = (await getMint(connection, mintKnite, "confirmed", TOKEN_PROGRAM_ID)).supply;
+    const after = await program.account.pair.fetch(pairPda);
+
+    assert.strictEqual((afterTreasury - beforeTreasury).toString(), feeTreasury.toString());
+    assert.strictEqual((afterVK - beforeVK).toString(), (netIn + feeLp).toString());
+    assert.strictEqual((beforeSupply - afterSupply).toString(), feeBurn.toString());
+    // LP share is counted in the stored reserve, so k grows for LPs
+    assert.strictEqual(after.reserveKnite.toString(), (reserveK + netIn + feeLp).toString());
+
+    // restore the treasury-only split for the remaining tests
+    await program.methods
+      .setFeeSplit(treasuryOnly)
+      .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+      .signers([treasury])
+      .rpc();
+  });
+
+  it("limit orders: escrowed input fills via the permissionless crank at the limit or better; owner-only cancel", async () => {
+    const findOrder = (owner, id) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("order"), pairPda.toBuffer(), owner.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+    const findEscrow = (order) =>
+      PublicKey.findProgramAddressSync([Buffer.from("order_escrow"), order.toBuffer()], program.programId);
+
+    const cranker = attacker;
+    const crankerKniteAta = await ensureAta(provider, provider.wallet.publicKey, cranker.publicKey, mintKnite);
+
+    const placeOrder = async (id, amountIn, minOut) => {
+      const [order] = findOrder(user.publicKey, id);
+      const [escrow] = findEscrow(order);
+      await program.methods
+        .placeOrder(new anchor.BN(id), new anchor.BN(amountIn.toString()), new anchor.BN(minOut.toString()))
+        .accounts({
+          owner: user.publicKey,
+          pair: pairPda,
+          mintIn: mintKnite,
+          mintOut: mintSub,
+          order,
+          escrow,
+          ownerAtaIn: userKniteAta,
+          tokenProgramIn: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([user])
+        .rpc();
+      return { order, escrow };
+    };
+    const fillOrders = (order, escrow) =>
+      program.methods
+        .fillOrders()
+        .accounts({
+          cranker: cranker.publicKey,
+          pair: pairPda,
+          mintKnite,
+          mintSub,
+          vaultKnite,
+          vaultSub,
+          treasuryKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts([
+          { pubkey: order, isWritable: true, isSigner: false },
+          { pubkey: escrow, isWritable: true, isSigner: false },
+          { pubkey: user.publicKey, isWritable: true, isSigner: false },
+          { pubkey: userSubAta, isWritable: true, isSigner: false },
+          { pubkey: crankerKniteAta, isWritable: true, isSigner: false },
+        ])
+        .signers([cranker])
+        .rpc();
+
+    const amountIn = 200_000n;
+    const crankFee = (amountIn * 5n) / 10_000n;
+
+    // limit far above the pool price: escrowed, not fillable, cancel is owner-only
+    const u0 = (await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const high = await placeOrder(1, amountIn, 10n * amountIn);
+    assert.strictEqual((await getAccount(connection, high.escrow, "confirmed", TOKEN_PROGRAM_ID)).amount.toString(), amountIn.toString());
+    const placed = await program.account.order.fetch(high.order);
+    assert.ok(placed.owner.equals(user.publicKey) && placed.mintIn.equals(mintKnite));
+    assert.strictEqual(placed.minOut.toString(), (10n * amountIn).toString());
+
+    try {
+      await fillOrders(high.order, high.escrow);
+      assert.fail("Expected NoFillableOrders");
+    } catch (e) {
+      assert.ok(e.toString().includes("NoFillableOrders") || e.toString().includes("custom program error"));
+    }
+
+    try {
+      await program.methods
+        .cancelOrder()
+        .accounts({
+          owner: attacker.publicKey,
+          order: high.order,
+          mintIn: mintKnite,
+          escrow: high.escrow,
+          ownerAtaIn: crankerKniteAta,
+          tokenProgramIn: TOKEN_PROGRAM_ID,
+        })
+        .signers([attacker])
+        .rpc();
+      assert.fail("Expected Unauthorized");
+    } catch (e) {
+      assert.ok(e.toString().includes("Unauthorized") || e.toString().includes("ConstraintSeeds") || e.toString().includes("custom program error"));
+    }
+
+    await program.methods
+      .cancelOrder()
+      .accounts({
+        owner: user.publicKey,
+        order: high.order,
+        mintIn: mintKnite,
+        escrow: high.escrow,
+        ownerAtaIn: userKniteAta,
+        tokenProgramIn: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+    assert.strictEqual((await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount.toString(), u0.toString());
+    assert.strictEqual(await connection.getAccountInfo(high.order, "confirmed"), null);
+    assert.strictEqual(await connection.getAccountInfo(high.escrow, "confirmed"), null);
+
+    // limit just below the pool price: the crank fills it, owner gets at least min_out
+    const pair = await program.account.pair.fetch(pairPda);
+    const swapIn = amountIn - crankFee;
+    const fee = feeCeil(swapIn, pair.swapFeeBps);
+    const expectedOut = cpOut(swapIn - fee, BigInt(pair.reserveKnite.toString()), BigInt(pair.reserveSub.toString()));
+    const minOut = (expectedOut * 99n) / 100n;
+
+    const low = await placeOrder(2, amountIn, minOut);
+    const s0 = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const c0 = (await getAccount(connection, crankerKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    await fillOrders(low.order, low.escrow);
+
+    const s1 = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const c1 = (await getAccount(connection, crankerKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual((s1 - s0).toString(), expectedOut.toString());
+    assert.ok(s1 - s0 >= minOut);
+    assert.strictEqual((c1 - c0).toString(), crankFee.toString());
+    assert.strictEqual(await connection.getAccountInfo(low.order, "confirmed"), null);
+    assert.strictEqual(await connection.getAccountInfo(low.escrow, "confirmed"), null);
+
+    const pairAfter = await program.account.pair.fetch(pairPda);
+    assert.strictEqual(pairAfter.reserveSub.toString(), (BigInt(pair.reserveSub.toString()) - expectedOut).toString());
+  });
+
+  it("dca: escrowed kNite buys sub one cycle per interval above the price floor; owner closes to reclaim the rest", async () => {
+    const findDca = (id) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("dca"), pairPda.toBuffer(), user.publicKey.toBuffer(), new anchor.BN(id).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+    const findEscrow = (dca) => PublicKey.findProgramAddressSync([Buffer.from("dca_escrow"), dca.toBuffer()], program.programId);
+
+    const openDca = async (id, total, perCycle, floorQ64) => {
+      const [dca] = findDca(id);
+      const [escrow] = findEscrow(dca);
+      await program.methods
+        .openDca(
+          new anchor.BN(id),
+          new anchor.BN(total.toString()),
+          new anchor.BN(perCycle.toString()),
+          new anchor.BN(60),
+          new anchor.BN(floorQ64.toString())
+        )
+        .accounts({
+          owner: user.publicKey,
+          pair: pairPda,
+          mintKnite,
+          dca,
+          escrow,
+          ownerKniteAta: userKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([user])
+        .rpc();
+      return { dca, escrow };
+    };
+    const executeCycle = ({ dca, escrow }) =>
+      program.methods
+        .executeDcaCycle()
+        .accounts({
+          cranker: attacker.publicKey,
+          pair: pairPda,
+          dca,
+          escrow,
+          ownerSubAta: userSubAta,
+          mintKnite,
+          mintSub,
+          vaultKnite,
+          vaultSub,
+          treasuryKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+        })
+        .signers([attacker])
+        .rpc();
+    const closeDca = ({ dca, escrow }) =>
+      program.methods
+        .closeDca()
+        .accounts({
+          owner: user.publicKey,
+          dca,
+          mintKnite,
+          escrow,
+          ownerKniteAta: userKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+
+    const total = 300_000n;
+    const perCycle = 100_000n;
+    const Q64 = 1n << 64n;
+
+    // per-cycle amount above the total is rejected
+    try {
+      await openDca(9, perCycle, total, Q64 / 1_000n);
+      assert.fail("Expected InvalidDcaSchedule");
+    } catch (e) {
+      assert.ok(e.toString().includes("InvalidDcaSchedule") || e.toString().includes("custom program error"));
+    }
+
+    // floor of 1 sub per 1000 kNite: the first cycle is due right away
+    const k0 = (await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const vault = await openDca(1, total, perCycle, Q64 / 1_000n);
+
+    const pair = await program.account.pair.fetch(pairPda);
+    const fee = feeCeil(perCycle, pair.swapFeeBps);
+    const expectedOut = cpOut(perCycle - fee, BigInt(pair.reserveKnite.toString()), BigInt(pair.reserveSub.toString()));
+
+    const s0 = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    await executeCycle(vault);
+    const s1 = (await getAccount(connection, userSubAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual((s1 - s0).toString(), expectedOut.toString());
+
+    const state = await program.account.dcaVault.fetch(vault.dca);
+    assert.strictEqual(state.cyclesExecuted.toNumber(), 1);
+    assert.strictEqual(state.totalIn.toString(), perCycle.toString());
+    assert.strictEqual(state.totalOut.toString(), expectedOut.toString());
+    assert.ok(state.nextCycleTs.toNumber() >= state.createdAt.toNumber() + 60);
+
+    // the next cycle waits for the interval
+    try {
+      await executeCycle(vault);
+      assert.fail("Expected DcaCycleNotDue");
+    } catch (e) {
+      assert.ok(e.toString().includes("DcaCycleNotDue") || e.toString().includes("custom program error"));
+    }
+
+    // owner closes: unspent kNite back, accounts gone
+    await closeDca(vault);
+    const k1 = (await getAccount(connection, userKniteAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    assert.strictEqual((k0 - k1).toString(), perCycle.toString());
+    assert.strictEqual(await connection.getAccountInfo(vault.dca, "confirmed"), null);
+    assert.strictEqual(await connection.getAccountInfo(vault.escrow, "confirmed"), null);
+
+    // a floor above the pool price blocks the cycle
+    const strict = await openDca(2, total, perCycle, 1_000n * Q64);
+    try {
+      await executeCycle(strict);
+      assert.fail("Expected SlippageExceeded");
+    } catch (e) {
+      assert.ok(e.toString().includes("SlippageExceeded") || e.toString().includes("custom program error"));
+    }
+
+    // any kNite account other than the vault's own escrow is rejected
+    try {
+      await executeCycle({ dca: strict.dca, escrow: userKniteAta });
+      assert.fail("Expected InvalidDcaEscrow");
+    } catch (e) {
+      assert.ok(e.toString().includes("InvalidDcaEscrow") || e.toString().includes("custom program error"));
+    }
+    await closeDca(strict);
+  });
+
+  it("clmm: positions between ticks earn swap fees; swaps move the sqrt price and pay the protocol share to the treasury", async () => {
+    const { mintPk: clKnite } = await createTestMint(provider, 6, treasury.publicKey);
+    const { mintPk: clSub } = await createTestMint(provider, 6, treasury.publicKey);
+    const [pool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("cl_pool"), treasury.publicKey.toBuffer(), clKnite.toBuffer(), clSub.toBuffer()],
+      program.programId
+    );
+    const [poolVaultKnite] = findVaultPda(program.programId, "vault_knite", pool);
+    const [poolVaultSub] = findVaultPda(program.programId, "vault_sub", pool);
+    const findPosition = (lower, upper) => {
+      const tick = (t) => {
+        const b = Buffer.alloc(4);
+        b.writeInt32LE(t);
+        return b;
+      };
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("cl_position"), pool.toBuffer(), user.publicKey.toBuffer(), tick(lower), tick(upper)],
+        program.programId
+      )[0];
+    };
+
+    const ownerKnite = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, clKnite);
+    const ownerSub = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, clSub);
+    const treasuryClKnite = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, clKnite);
+    await mintTo(provider, clKnite, ownerKnite, treasury, 1_000_000_000n);
+    await mintTo(provider, clSub, ownerSub, treasury, 1_000_000_000n);
+
+    // price 1.0 (tick 0), spacing 60, fee 30 bps, positions of at least 1e9 liquidity
+    await program.methods
+      .initializeClPool(treasury.publicKey, new anchor.BN((1n << 64n).toString()), 60, 30, new anchor.BN(1_000_000_000))
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: await nextRegistryEntry(),
+        mintKnite: clKnite,
+        mintSub: clSub,
+        pool,
+        vaultKnite: poolVaultKnite,
+        vaultSub: poolVaultSub,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([treasury])
+      .rpc();
+
+    const openPosition = async (lower, upper) => {
+      const position = findPosition(lower, upper);
+      await program.methods
+        .openClPosition(lower, upper)
+        .accounts({ owner: user.publicKey, pool, position, systemProgram: SystemProgram.programId })
+        .signers([user])
+        .rpc();
+      return position;
+    };
+    const liquidityAccounts = (position) => ({
+      owner: user.publicKey,
+      pool,
+      position,
+      mintKnite: clKnite,
+      mintSub: clSub,
+      vaultKnite: poolVaultKnite,
+      vaultSub: poolVaultSub,
+      ownerKniteAta: ownerKnite,
+      ownerSubAta: ownerSub,
+      tokenProgramKnite: TOKEN_PROGRAM_ID,
+      tokenProgramSub: TOKEN_PROGRAM_ID,
+    });
+    const balance = async (account) => (await getAccount(connection, account, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    // ticks must sit on the spacing
+    try {
+      await openPosition(-50, 600);
+      assert.fail("Expected InvalidTick");
+    } catch (e) {
+      assert.ok(e.toString().includes("InvalidTick") || e.toString().includes("custom program error"));
+    }
+
+    // [-600, 600) around the price takes both tokens
+    const position = await openPosition(-600, 600);
+    const liquidity = new anchor.BN(10_000_000_000);
+    const k0 = await balance(ownerKnite);
+    const s0 = await balance(ownerSub);
+    await program.methods
+      .addClLiquidity(liquidity, new anchor.BN(300_000_000), new anchor.BN(300_000_000))
+      .accounts(liquidityAccounts(position))
+      .signers([user])
+      .rpc();
+    assert.strictEqual((k0 - (await balance(ownerKnite))).toString(), "295530109");
+    assert.strictEqual((s0 - (await balance(ownerSub))).toString(), "295530109");
+
+    let state = await program.account.clPool.fetch(pool);
+    assert.strictEqual(state.liquidity.toString(), liquidity.toString());
+    assert.strictEqual(state.ticks.length, 2);
+
+    // 1_000_000 kNite in: fee 3000 (600 to the treasury), price moves down
+    const amountIn = 1_000_000n;
+    const t0 = await balance(treasuryClKnite);
+    const out0 = await balance(ownerSub);
+    await program.methods
+      .swapCl(new anchor.BN(amountIn.toString()), new anchor.BN(990_000))
+      .accounts({
+        user: user.publicKey,
+        pool,
+        mintKnite: clKnite,
+        mintSub: clSub,
+        userAtaIn: ownerKnite,
+        userAtaOut: ownerSub,
+        vaultKnite: poolVaultKnite,
+        vaultSub: poolVaultSub,
+        treasuryFeeAta: treasuryClKnite,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+    assert.strictEqual(((await balance(ownerSub)) - out0).toString(), "996900");
+    assert.strictEqual(((await balance(treasuryClKnite)) - t0).toString(), "600");
+
+    state = await program.account.clPool.fetch(pool);
+    assert.ok(BigInt(state.sqrtPriceQ64.toString()) < 1n << 64n);
+    assert.strictEqual(state.tickCurrent, -2);
+
+    // the position's LP share of the fee (2400, minus rounding)
+    const kc = await balance(ownerKnite);
+    await program.methods.collectClFees().accounts(liquidityAccounts(position)).signers([user]).rpc();
+    const collected = (await balance(ownerKnite)) - kc;
+    assert.ok(collected >= 2399n && collected <= 2400n);
+
+    // withdraw everything, then close
+    await program.methods
+      .removeClLiquidity(liquidity, new anchor.BN(0), new anchor.BN(0))
+      .accounts(liquidityAccounts(position))
+      .signers([user])
+      .rpc();
+    state = await program.account.clPool.fetch(pool);
+    assert.strictEqual(state.liquidity.toString(), "0");
+    assert.strictEqual(state.ticks.length, 0);
+
+    await program.methods.closeClPosition().accounts({ owner: user.publicKey, position }).signers([user]).rpc();
+    assert.strictEqual(await connection.getAccountInfo(position, "confirmed"), null);
+  });
+
+  it("clmm: filling the tick table takes the pool minimum liquidity per position; dust positions are rejected", async () => {
+    const { mintPk: clKnite } = await createTestMint(provider, 6, treasury.publicKey);
+    const { mintPk: clSub } = await createTestMint(provider, 6, treasury.publicKey);
+    const [pool] = PublicKey.findProgramAddressSync(
+      [Buffer.from("cl_pool"), treasury.publicKey.toBuffer(), clKnite.toBuffer(), clSub.toBuffer()],
+      program.programId
+    );
+    const [poolVaultKnite] = findVaultPda(program.programId, "vault_knite", pool);
+    const [poolVaultSub] = findVaultPda(program.programId, "vault_sub", pool);
+    const ownerKnite = await ensureAta(provider, provider.wallet.publicKey, attacker.publicKey, clKnite);
+    const ownerSub = await ensureAta(provider, provider.wallet.publicKey, attacker.publicKey, clSub);
+    await mintTo(provider, clKnite, ownerKnite, treasury, 10_000_000_000n);
+    await mintTo(provider, clSub, ownerSub, treasury, 10_000_000_000n);
+
+    const minLiquidity = new anchor.BN(1_000_000_000);
+    await program.methods
+      .initializeClPool(treasury.publicKey, new anchor.BN((1n << 64n).toString()), 60, 30, minLiquidity)
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: await nextRegistryEntry(),
+        mintKnite: clKnite,
+        mintSub: clSub,
+        pool,
+        vaultKnite: poolVaultKnite,
+        vaultSub: poolVaultSub,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([treasury])
+      .rpc();
+
+    const findPosition = (lower, upper) => {
+      const tick = (t) => {
+        const b = Buffer.alloc(4);
+        b.writeInt32LE(t);
+        return b;
+      };
+      return PublicKey.findProgramAddressSync(
+        [Buffer.from("cl_position"), pool.toBuffer(), attacker.publicKey.toBuffer(), tick(lower), tick(upper)],
+        program.programId
+      )[0];
+    };
+    const liquidityIx = (method, position, liquidity, limit) =>
+      program.methods[method](new anchor.BN(liquidity.toString()), new anchor.BN(limit), new anchor.BN(limit)).accounts({
+        owner: attacker.publicKey,
+        pool,
+        position,
+        mintKnite: clKnite,
+        mintSub: clSub,
+        vaultKnite: poolVaultKnite,
+        vaultSub: poolVaultSub,
+        ownerKniteAta: ownerKnite,
+        ownerSubAta: ownerSub,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+      });
+    // open + add in one transaction
+    const openWith = async (lower, upper, liquidity) => {
+      const position = findPosition(lower, upper);
+      await program.methods
+        .openClPosition(lower, upper)
+        .accounts({ owner: attacker.publicKey, pool, position, systemProgram: SystemProgram.programId })
+        .postInstructions([await liquidityIx("addClLiquidity", position, liquidity, "1000000000").instruction()])
+        .signers([attacker])
+        .rpc();
+      return position;
+    };
+    const expectError = async (fn, name) => {
+      try {
+        await fn();
+        assert.fail(`Expected ${name}`);
+      } catch (e) {
+        assert.ok(e.toString().includes(name) || e.toString().includes("custom program error"), e.toString());
+      }
+    };
+
+    // liquidity = 1 positions cannot pin tick slots
+    await expectError(() => openWith(-60, 0, 1), "ClPositionTooSmall");
+    await expectError(() => openWith(-60, 0, 999_999_999), "ClPositionTooSmall");
+
+    // 32 disjoint ranges fill all 64 tick slots, each at the minimum liquidity
+    const k0 = (await getAccount(connection, ownerKnite, "confirmed")).amount;
+    const positions = [];
+    for (let i = 0; i < 32; i++) {
+      positions.push(await openWith(120 * i, 120 * i + 60, minLiquidity));
+    }
+    assert.strictEqual((await program.account.clPool.fetch(pool)).ticks.length, 64);
+    // and that locked real tokens (2.5M-3M kNite units per range at these prices)
+    assert.ok(k0 - (await getAccount(connection, ownerKnite, "confirmed")).amount > 32n * 2_000_000n);
+
+    // full table: a new range fails
+    await expectError(() => openWith(-120, -60, minLiquidity), "ClTickLimit");
+
+    // a position cannot shrink to dust either; withdrawing it entirely frees its two ticks
+    await expectError(() => liquidityIx("removeClLiquidity", positions[31], 1, "0").signers([attacker]).rpc(), "ClPositionTooSmall");
+    await liquidityIx("removeClLiquidity", positions[31], minLiquidity, "0").signers([attacker]).rpc();
+    assert.strictEqual((await program.account.clPool.fetch(pool)).ticks.length, 62);
+    await openWith(-180, -120, minLiquidity);
+    assert.strictEqual((await program.account.clPool.fetch(pool)).ticks.length, 64);
+  });
+
+  it("batch auction: commits clear at one price on settle, unfilled ones are refunded, expired batches withdraw", async () => {
+    const epochSlots = 20;
+    const setBatchSlots = (slots) =>
+      program.methods
+        .setBatchSlots(new anchor.BN(slots))
+        .accounts({ treasurySigner: treasury.publicKey, pair: pairPda })
+        .signers([treasury])
+        .rpc();
+    const waitForSlot = async (slot) => {
+      while ((await connection.getSlot("confirmed")) < slot) {
+        await new Promise((r) => setTimeout(r, 400));
+      }
+    };
+    const findBatch = (epoch) =>
+      PublicKey.findProgramAddressSync(
+        [Buffer.from("batch"), pairPda.toBuffer(), new anchor.BN(epoch).toArrayLike(Buffer, "le", 8)],
+        program.programId
+      )[0];
+    const escrowsOf = (batch) => ({
+      escrowKnite: PublicKey.findProgramAddressSync([Buffer.from("batch_escrow_knite"), batch.toBuffer()], program.programId)[0],
+      escrowSub: PublicKey.findProgramAddressSync([Buffer.from("batch_escrow_sub"), batch.toBuffer()], program.programId)[0],
+    });
+    const findCommit = (batch, owner) =>
+      PublicKey.findProgramAddressSync([Buffer.from("batch_commit"), batch.toBuffer(), owner.toBuffer()], program.programId)[0];
+
+    // opens the batch of the running epoch, leaving enough slots to commit
+    const openBatch = async () => {
+      let slot = await connection.getSlot("confirmed");
+      if (slot % epochSlots > epochSlots - 8) {
+        await waitForSlot(slot - (slot % epochSlots) + epochSlots);
+        slot = await connection.getSlot("confirmed");
+      }
+      const epoch = Math.floor(slot / epochSlots);
+      const batch = findBatch(epoch);
+      await program.methods
+        .openBatch(new anchor.BN(epoch))
+        .accounts({
+          payer: provider.wallet.publicKey,
+          pair: pairPda,
+          mintKnite,
+          mintSub,
+          batch,
+          ...escrowsOf(batch),
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .rpc();
+      return batch;
+    };
+    const commitSwap = (batch, owner, isKniteIn, amountIn, minOut) => {
+      const { escrowKnite, escrowSub } = escrowsOf(batch);
+      return program.methods
+        .commitBatchSwap(new anchor.BN(amountIn.toString()), new anchor.BN(minOut.toString()))
+        .accounts({
+          owner: owner.keypair.publicKey,
+          pair: pairPda,
+          batch,
+          commit: findCommit(batch, owner.keypair.publicKey),
+          mintIn: isKniteIn ? mintKnite : mintSub,
+          escrowIn: isKniteIn ? escrowKnite : escrowSub,
+          ownerAtaIn: isKniteIn ? owner.kniteAta : owner.subAta,
+          tokenProgramIn: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([owner.keypair])
+        .rpc();
+    };
+    const claimAccounts = (batch, owner) => ({
+      owner: owner.keypair.publicKey,
+      batch,
+      commit: findCommit(batch, owner.keypair.publicKey),
+      mintKnite,
+      mintSub,
+      ...escrowsOf(batch),
+      ownerKniteAta: owner.kniteAta,
+      ownerSubAta: owner.subAta,
+      tokenProgramKnite: TOKEN_PROGRAM_ID,
+      tokenProgramSub: TOKEN_PROGRAM_ID,
+    });
+    const balance = async (account) => (await getAccount(connection, account, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    const trader = async () => {
+      const keypair = Keypair.generate();
+      await airdrop(connection, keypair.publicKey, 2);
+      const kniteAta = await ensureAta(provider, provider.wallet.publicKey, keypair.publicKey, mintKnite);
+      const subAta = await ensureAta(provider, provider.wallet.publicKey, keypair.publicKey, mintSub);
+      await mintTo(provider, mintKnite, kniteAta, treasury, 100_000_000n);
+      await mintTo(provider, mintSub, subAta, treasury, 100_000_000n);
+      return { keypair, kniteAta, subAta };
+    };
+    const seller = await trader();
+    const buyer = await trader();
+    const greedy = await trader();
+
+    await setBatchSlots(epochSlots);
+
+    // direct swaps are closed while the pair is in batch mode
+    try {
+      await program.methods
+        .swapExactIn(new anchor.BN("10000"), new anchor.BN("1"))
+        .accounts({
+          user: user.publicKey,
+          mintIn: mintKnite,
+          mintOut: mintSub,
+          pair: pairPda,
+          userAtaIn: userKniteAta,
+          userAtaOut: userSubAta,
+          vaultKnite,
+          vaultSub,
+          treasuryKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Expected BatchModeActive");
+    } catch (e) {
+      assert.ok(e.toString().includes("BatchModeActive") || e.toString().includes("custom program error"));
+    }
+    try {
+      await program.methods
+        .quoteExactIn(new anchor.BN("10000"))
+        .accounts({ mintIn: mintKnite, mintOut: mintSub, pair: pairPda })
+        .view();
+      assert.fail("Expected BatchModeActive");
+    } catch (e) {
+      const logs = (e.simulationResponse?.logs ?? e.logs ?? []).join("\n");
+      assert.ok(logs.includes("BatchModeActive") || e.toString().includes("custom program error"), e.toString());
+    }
+
+    // kNite seller and sub buyer partly match; the greedy commit asks more than any price gives
+    const batch = await openBatch();
+    await commitSwap(batch, seller, true, 5_000_000n, 1n);
+    await commitSwap(batch, buyer, false, 2_000_000n, 1n);
+    await commitSwap(batch, greedy, true, 1_000_000n, 1_000_000_000n);
+
+    // settling needs the epoch to be over
+    const commits = [seller, buyer, greedy]
+      .map((t) => findCommit(batch, t.keypair.publicKey))
+      .sort((a, b) => Buffer.compare(a.toBuffer(), b.toBuffer()))
+      .map((pubkey) => ({ pubkey, isWritable: true, isSigner: false }));
+    const settle = () =>
+      program.methods
+        .settleBatch()
+        .accounts({
+          cranker: attacker.publicKey,
+          pair: pairPda,
+          batch,
+          mintKnite,
+          mintSub,
+          ...escrowsOf(batch),
+          vaultKnite,
+          vaultSub,
+          treasuryKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+        })
+        .remainingAccounts(commits)
+        .signers([attacker])
+        .rpc();
+    try {
+      await settle();
+      assert.fail("Expected BatchNotReady");
+    } catch (e) {
+      assert.ok(e.toString().includes("BatchNotReady") || e.toString().includes("custom program error"));
+    }
+
+    const { endSlot } = await program.account.batch.fetch(batch);
+    await waitForSlot(endSlot.toNumber());
+    await settle();
+
+    const settled = await program.account.batch.fetch(batch);
+    assert.strictEqual(settled.settled, true);
+    assert.strictEqual(settled.fillKniteIn.toString(), "5000000");
+    assert.strictEqual(settled.fillSubIn.toString(), "2000000");
+
+    // one price for both sides: sub per kNite paid to sellers == sub per kNite paid by buyers
+    const sellerPrice = Number(settled.fillKniteOut.toString()) / Number(settled.fillKniteIn.toString());
+    const buyerPrice = Number(settled.fillSubIn.toString()) / Number(settled.fillSubOut.toString());
+    assert.ok(Math.abs(sellerPrice - buyerPrice) / buyerPrice < 1e-5);
+
+    const sub0 = await balance(seller.subAta);
+    await program.methods.claimBatch().accounts(claimAccounts(batch, seller)).signers([seller.keypair]).rpc();
+    assert.strictEqual(((await balance(seller.subAta)) - sub0).toString(), settled.fillKniteOut.toString());
+
+    const knite0 = await balance(buyer.kniteAta);
+    await program.methods.claimBatch().accounts(claimAccounts(batch, buyer)).signers([buyer.keypair]).rpc();
+    assert.strictEqual(((await balance(buyer.kniteAta)) - knite0).toString(), settled.fillSubOut.toString());
+
+    // unfilled commit gets its kNite back
+    const greedy0 = await balance(greedy.kniteAta);
+    await program.methods.claimBatch().accounts(claimAccounts(batch, greedy)).signers([greedy.keypair]).rpc();
+    assert.strictEqual(((await balance(greedy.kniteAta)) - greedy0).toString(), "1000000");
+
+    // every commit is out: escrows and batch close back to the payer
+    await program.methods
+      .closeBatch()
+      .accounts({
+        payer: provider.wallet.publicKey,
+        batch,
+        mintKnite,
+        mintSub,
+        ...escrowsOf(batch),
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+      })
+      .rpc();
+    assert.strictEqual(await connection.getAccountInfo(batch, "confirmed"), null);
+
+    // a batch nobody settles can be withdrawn from once the settle window has passed
+    const stale = await openBatch();
+    await commitSwap(stale, seller, true, 1_000_000n, 1n);
+    try {
+      await program.methods.withdrawBatch().accounts(claimAccounts(stale, seller)).signers([seller.keypair]).rpc();
+      assert.fail("Expected BatchNotReady");
+    } catch (e) {
+      assert.ok(e.toString().includes("BatchNotReady") || e.toString().includes("custom program error"));
+    }
+    const staleState = await program.account.batch.fetch(stale);
+    await waitForSlot(staleState.endSlot.toNumber() + 150);
+    const k0 = await balance(seller.kniteAta);
+    await program.methods.withdrawBatch().accounts(claimAccounts(stale, seller)).signers([seller.keypair]).rpc();
+    assert.strictEqual(((await balance(seller.kniteAta)) - k0).toString(), "1000000");
+
+    await setBatchSlots(0);
+  });
+
+  it("native sol: swaps wrap lamports into a temporary WSOL account and unwrap on the way out; rent is refunded", async () => {
+    const mk = await createTestMint(provider, decimals, treasury.publicKey);
+    const [solPair] = findPairPda(program.programId, treasury.publicKey, mk.mintPk, NATIVE_MINT);
+    const [solVK] = findVaultPda(program.programId, "vault_knite", solPair);
+    const [solVS] = findVaultPda(program.programId, "vault_sub", solPair);
+    const [solLp] = findVaultPda(program.programId, "lp_mint", solPair);
+    const [tempWsol] = PublicKey.findProgramAddressSync(
+      [Buffer.from("temp_wsol"), user.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: await nextRegistryEntry(),
+        mintKnite: mk.mintPk,
+        mintSub: NATIVE_MINT,
+        pair: solPair,
+        vaultKnite: solVK,
+        vaultSub: solVS,
+        lpMint: solLp,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([treasury])
+      .rpc();
+
+    // seed: kNite minted into its vault, lamports wrapped straight into the WSOL vault
+    await mintTo(provider, mk.mintPk, solVK, treasury, 1_000_000_000n);
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        SystemProgram.transfer({ fromPubkey: provider.wallet.publicKey, toPubkey: solVS, lamports: 500_000_000 }),
+        createSyncNativeInstruction(solVS)
+      ),
+      [],
+      { commitment: "confirmed" }
+    );
+    await program.methods
+      .sync()
+      .accounts({ treasurySigner: treasury.publicKey, pair: solPair, vaultKnite: solVK, vaultSub: solVS })
+      .signers([treasury])
+      .rpc();
+
+    const treasuryAta = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mk.mintPk);
+    const userAta = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, mk.mintPk);
+    const solAccounts = {
+      user: user.publicKey,
+      pair: solPair,
+      mintKnite: mk.mintPk,
+      mintSub: NATIVE_MINT,
+      userKniteAta: userAta,
+      tempWsol,
+      vaultKnite: solVK,
+      vaultSub: solVS,
+      treasuryKniteAta: treasuryAta,
+      tokenProgramKnite: TOKEN_PROGRAM_ID,
+      tokenProgramSub: TOKEN_PROGRAM_ID,
+      systemProgram: SystemProgram.programId,
+      rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+    };
+    const lamports = async () => BigInt(await connection.getBalance(user.publicKey, "confirmed"));
+    const kniteBalance = async () => (await getAccount(connection, userAta, "confirmed", TOKEN_PROGRAM_ID)).amount;
+
+    // lamports -> kNite (fee on output kNite); the provider pays tx fees, so the user is down exactly lamportsIn
+    const lamportsIn = 10_000_000n;
+    let pair = await program.account.pair.fetch(solPair);
+    const gross = cpOut(lamportsIn, BigInt(pair.reserveSub.toString()), BigInt(pair.reserveKnite.toString()));
+    const kniteOut = gross - feeCeil(gross, 30);
+
+    const sol0 = await lamports();
+    await program.methods
+      .swapSolIn(new anchor.BN(lamportsIn.toString()), new anchor.BN(kniteOut.toString()))
+      .accounts(solAccounts)
+      .signers([user])
+      .rpc();
+    assert.strictEqual((sol0 - (await lamports())).toString(), lamportsIn.toString());
+    assert.strictEqual((await kniteBalance()).toString(), kniteOut.toString());
+    assert.strictEqual(await connection.getAccountInfo(tempWsol, "confirmed"), null);
+
+    // kNite -> lamports (fee on input kNite); WSOL output and temp account rent both land as lamports
+    const amountIn = kniteOut;
+    pair = await program.account.pair.fetch(solPair);
+    const lamportsOut = cpOut(
+      amountIn - feeCeil(amountIn, 30),
+      BigInt(pair.reserveKnite.toString()),
+      BigInt(pair.reserveSub.toString())
+    );
+
+    const sol1 = await lamports();
+    await program.methods
+      .swapSolOut(new anchor.BN(amountIn.toString()), new anchor.BN(lamportsOut.toString()))
+      .accounts(solAccounts)
+      .signers([user])
+      .rpc();
+    assert.strictEqual(((await lamports()) - sol1).toString(), lamportsOut.toString());
+    assert.strictEqual((await kniteBalance()).toString(), "0");
+    assert.strictEqual(await connection.getAccountInfo(tempWsol, "confirmed"), null);
+
+    // a failed swap leaves nothing behind either (the whole tx reverts)
+    const sol2 = await lamports();
+    try {
+      await program.methods
+        .swapSolIn(new anchor.BN(lamportsIn.toString()), new anchor.BN("1000000000000"))
+        .accounts(solAccounts)
+        .signers([user])
+        .rpc();
+      assert.fail("Expected SlippageExceeded");
+    } catch (e) {
+      assert.ok(e.toString().includes("SlippageExceeded") || e.toString().includes("custom program error"));
+    }
+    assert.strictEqual((await lamports()).toString(), sol2.toString());
+    assert.strictEqual(await connection.getAccountInfo(tempWsol, "confirmed"), null);
+
+    // only kNite/WSOL pairs
+    try {
+      await program.methods
+        .swapSolIn(new anchor.BN(lamportsIn.toString()), new anchor.BN("1"))
+        .accounts({
+          ...solAccounts,
+          pair: pairPda,
+          mintKnite,
+          mintSub,
+          userKniteAta,
+          vaultKnite,
+          vaultSub,
+          treasuryKniteAta,
+        })
+        .signers([user])
+        .rpc();
+      assert.fail("Expected TokenNotAllowed");
+    } catch (e) {
+      assert.ok(e.toString().includes("TokenNotAllowed") || e.toString().includes("custom program error"));
+    }
+  });
+
+  it("launch phase: no swaps before the start, per-wallet cap on sub buys and cooldown via swap_exact_in, normal after", async () => {
+    const mk = await createTestMint(provider, decimals, treasury.publicKey);
+    const ms = await createTestMint(provider, decimals, treasury.publicKey);
+    const [lPair] = findPairPda(program.programId, treasury.publicKey, mk.mintPk, ms.mintPk);
+    const [lVK] = findVaultPda(program.programId, "vault_knite", lPair);
+    const [lVS] = findVaultPda(program.programId, "vault_sub", lPair);
+    const [lLp] = findVaultPda(program.programId, "lp_mint", lPair);
+    const [cooldown] = PublicKey.findProgramAddressSync(
+      [Buffer.from("launch_cooldown"), lPair.toBuffer(), user.publicKey.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: await nextRegistryEntry(),
+        mintKnite: mk.mintPk,
+        mintSub: ms.mintPk,
+        pair: lPair,
+        vaultKnite: lVK,
+        vaultSub: lVS,
+        lpMint: lLp,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([treasury])
+      .rpc();
+    await mintTo(provider, mk.mintPk, lVK, treasury, 1_000_000_000n);
+    await mintTo(provider, ms.mintPk, lVS, treasury, 1_000_000_000n);
+    await program.methods
+      .sync()
+      .accounts({ treasurySigner: treasury.publicKey, pair: lPair, vaultKnite: lVK, vaultSub: lVS })
+      .signers([treasury])
+      .rpc();
+
+    const tK = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mk.mintPk);
+    const uK = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, mk.mintPk);
+    const uS = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, ms.mintPk);
+    await mintTo(provider, mk.mintPk, uK, treasury, 10_000_000n);
+    await mintTo(provider, ms.mintPk, uS, treasury, 10_000_000n);
+
+    const setLaunch = (startsAt, launchSecs, maxBuy, cooldownSecs, signer = treasury) =>
+      program.methods
+        .setLaunchPhase(new anchor.BN(startsAt), new anchor.BN(launchSecs), new anchor.BN(maxBuy), cooldownSecs)
+        .accounts({ treasurySigner: signer.publicKey, pair: lPair })
+        .signers([signer])
+        .rpc();
+    const swapIx = (isKniteIn, amountIn, withCooldown = true) =>
+      program.methods
+        .swapExactIn(new anchor.BN(amountIn.toString()), new anchor.BN("1"))
+        .accounts({
+          user: user.publicKey,
+          mintIn: isKniteIn ? mk.mintPk : ms.mintPk,
+          mintOut: isKniteIn ? ms.mintPk : mk.mintPk,
+          pair: lPair,
+          userAtaIn: isKniteIn ? uK : uS,
+          userAtaOut: isKniteIn ? uS : uK,
+          vaultKnite: lVK,
+          vaultSub: lVS,
+          treasuryKniteAta: tK,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+          ...(withCooldown ? { launchCooldown: cooldown } : {}),
+        })
+        .signers([user]);
+    const expectError = async (fn, name) => {
+      try {
+        await fn();
+        assert.fail(`Expected ${name}`);
+      } catch (e) {
+        // simulated views carry the program error in their logs
+        const msg = [e.toString(), ...(e.simulationResponse?.logs ?? e.logs ?? [])].join("\n");
+        assert.ok(msg.includes(name) || msg.includes("custom program error"), msg);
+      }
+    };
+    const now = Math.floor(Date.now() / 1000);
+
+    await expectError(() => setLaunch(now, 3_600, 0, 30, attacker), "Unauthorized");
+    await expectError(() => setLaunch(now, 0, 0, 30), "InvalidLaunchPhase");
+    await expectError(() => setLaunch(now, 86_401, 0, 0), "InvalidLaunchPhase");
+
+    await program.methods
+      .openLaunchCooldown()
+      .accounts({ user: user.publicKey, pair: lPair, launchCooldown: cooldown, systemProgram: SystemProgram.programId })
+      .signers([user])
+      .rpc();
+
+    // scheduled launch: nothing trades before the start
+    await setLaunch(now + 3_600, 600, 0, 0);
+    await expectError(() => swapIx(false, 100_000n).rpc(), "TradingNotStarted");
+
+    // launch phase running, cap of 150_000 sub (the launched token) per wallet per slot
+    await setLaunch(now - 60, 3_600, 150_000, 0);
+    await expectError(() => swapIx(true, 100_000n, false).rpc(), "LaunchPhaseActive");
+    const s0 = (await getAccount(connection, uS, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    await swapIx(true, 100_000n).rpc();
+    const bought = (await getAccount(connection, uS, "confirmed", TOKEN_PROGRAM_ID)).amount - s0;
+    assert.ok(bought > 0n && bought <= 150_000n);
+    const state = await program.account.launchCooldown.fetch(cooldown);
+    assert.ok(state.owner.equals(user.publicKey));
+    assert.strictEqual(state.boughtInSlot.toString(), bought.toString());
+
+    // two buys in one tx land in the same slot and add up past the cap
+    const sameSlot = async (isKniteIn) => {
+      const tx = new anchor.web3.Transaction().add(
+        await swapIx(isKniteIn, 100_000n).instruction(),
+        await swapIx(isKniteIn, 100_000n).instruction()
+      );
+      await provider.sendAndConfirm(tx, [user], { commitment: "confirmed" });
+    };
+    await expectError(() => sameSlot(true), "LaunchBuyLimitExceeded");
+
+    // selling the launched token back is not a buy and is not capped
+    const k0 = (await getAccount(connection, uK, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    await sameSlot(false);
+    assert.ok((await getAccount(connection, uK, "confirmed", TOKEN_PROGRAM_ID)).amount > k0 + 150_000n);
+
+    // other swap paths wait for the launch phase to end
+    await expectError(
+      () =>
+        program.methods
+          .swapExactOut(new anchor.BN("1000"), new anchor.BN("100000"))
+          .accounts({
+            user: user.publicKey,
+            mintIn: ms.mintPk,
+            mintOut: mk.mintPk,
+            pair: lPair,
+            userAtaIn: uS,
+            userAtaOut: uK,
+            vaultKnite: lVK,
+            vaultSub: lVS,
+            treasuryKniteAta: tK,
+            tokenProgramKnite: TOKEN_PROGRAM_ID,
+            tokenProgramSub: TOKEN_PROGRAM_ID,
+          })
+          .signers([user])
+          .rpc(),
+      "LaunchPhaseActive"
+    );
+
+    // quotes fail where the swaps would; a single quote-sized buy is checked against the cap
+    const launchQuote = (isKniteIn) => ({
+      mintIn: isKniteIn ? mk.mintPk : ms.mintPk,
+      mintOut: isKniteIn ? ms.mintPk : mk.mintPk,
+      pair: lPair,
+    });
+    await expectError(
+      () => program.methods.quoteExactOut(new anchor.BN("1000")).accounts(launchQuote(false)).view(),
+      "LaunchPhaseActive"
+    );
+    await expectError(
+      () => program.methods.quoteExactIn(new anchor.BN("200000")).accounts(launchQuote(true)).view(),
+      "LaunchBuyLimitExceeded"
+    );
+    const launchQ = await program.methods.quoteExactIn(new anchor.BN("100000")).accounts(launchQuote(true)).view();
+    assert.ok(BigInt(launchQ.netOut.toString()) <= 150_000n);
+
+    // cooldown: the wallet traded moments ago, so it has to wait
+    await setLaunch(now - 60, 3_600, 0, 3_600);
+    await expectError(() => swapIx(true, 10_000n).rpc(), "LaunchCooldownActive");
+    await expectError(() => swapIx(false, 10_000n).rpc(), "LaunchCooldownActive");
+
+    const closeCooldown = () =>
+      program.methods
+        .closeLaunchCooldown()
+        .accounts({ owner: user.publicKey, pair: lPair, launchCooldown: cooldown })
+        .signers([user])
+        .rpc();
+    await expectError(closeCooldown, "LaunchPhaseActive");
+
+    // launch phase over: plain swap_exact_in again, and the cooldown account can go
+    await setLaunch(now - 7_200, 3_600, 150_000, 3_600);
+    await swapIx(true, 10_000n, false).rpc();
+    await swapIx(false, 500_000n, false).rpc();
+    await closeCooldown();
+    assert.strictEqual(await connection.getAccountInfo(cooldown, "confirmed"), null);
+  });
+
+  it("close_pair: disabled pair pays LPs pro rata, the rest to the treasury, closes vaults and pair", async () => {
+    const mk = await createTestMint(provider, decimals, treasury.publicKey);
+    const ms = await createTestMint(provider, decimals, treasury.publicKey);
+    const [cPair] = findPairPda(program.programId, treasury.publicKey, mk.mintPk, ms.mintPk);
+    const [cVK] = findVaultPda(program.programId, "vault_knite", cPair);
+    const [cVS] = findVaultPda(program.programId, "vault_sub", cPair);
+    const [cLp] = findVaultPda(program.programId, "lp_mint", cPair);
+
+    const cEntry = await nextRegistryEntry();
+    await program.methods
+      .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registry: registryPda,
+        registryEntry: cEntry,
+        mintKnite: mk.mintPk,
+        mintSub: ms.mintPk,
+        pair: cPair,
+        vaultKnite: cVK,
+        vaultSub: cVS,
+        lpMint: cLp,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([treasury])
+      .rpc();
+
+    // two LPs: user and attacker
+    const lps = [];
+    for (const kp of [user, attacker]) {
+      const lp = {
+        kp,
+        knite: await ensureAta(provider, provider.wallet.publicKey, kp.publicKey, mk.mintPk),
+        sub: await ensureAta(provider, provider.wallet.publicKey, kp.publicKey, ms.mintPk),
+        lp: await ensureAta(provider, provider.wallet.publicKey, kp.publicKey, cLp),
+      };
+      await mintTo(provider, mk.mintPk, lp.knite, treasury, 4_000_000n);
+      await mintTo(provider, ms.mintPk, lp.sub, treasury, 4_000_000n);
+      lps.push(lp);
+    }
+    for (const [lp, amount] of [
+      [lps[0], 1_000_000n],
+      [lps[1], 3_000_000n],
+    ]) {
+      await program.methods
+        .addLiquidity(new anchor.BN(amount.toString()), new anchor.BN(amount.toString()), new anchor.BN("1"))
+        .accounts({
+          user: lp.kp.publicKey,
+          pair: cPair,
+          lpMint: cLp,
+          userAtaKnite: lp.knite,
+          userAtaSub: lp.sub,
+          userAtaLp: lp.lp,
+          vaultKnite: cVK,
+          vaultSub: cVS,
+          mintKnite: mk.mintPk,
+          mintSub: ms.mintPk,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([lp.kp])
+        .rpc();
+    }
+    // a donation is drained with everything else
+    await mintTo(provider, ms.mintPk, cVS, treasury, 7_777n);
+
+    const tK = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mk.mintPk);
+    const tS = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, ms.mintPk);
+    const balance = async (pk) => (await getAccount(connection, pk, "confirmed", TOKEN_PROGRAM_ID)).amount;
+    const holderAccounts = (list) =>
+      [...list]
+        .sort((a, b) => a.lp.toBuffer().compare(b.lp.toBuffer()))
+        .flatMap((lp) => [
+          { pubkey: lp.lp, isWritable: false, isSigner: false },
+          { pubkey: lp.knite, isWritable: true, isSigner: false },
+          { pubkey: lp.sub, isWritable: true, isSigner: false },
+        ]);
+    const closePair = (holders, signer = treasury) =>
+      program.methods
+        .closePair()
+        .accounts({
+          treasurySigner: signer.publicKey,
+          registryEntry: cEntry,
+          pair: cPair,
+          mintKnite: mk.mintPk,
+          mintSub: ms.mintPk,
+          lpMint: cLp,
+          vaultKnite: cVK,
+          vaultSub: cVS,
+          treasuryKniteAta: tK,
+          treasurySubAta: tS,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+        })
+        .remainingAccounts(holderAccounts(holders))
+        .signers([signer])
+        .rpc();
+    const expectError = async (fn, name) => {
+      try {
+        await fn();
+        assert.fail(`Expected ${name}`);
+      } catch (e) {
+        assert.ok(e.toString().includes(name) || e.toString().includes("custom program error"), e.toString());
+      }
+    };
+
+    await expectError(() => closePair(lps), "PairStillEnabled");
+    await program.methods
+      .setPairEnabled(false)
+      .accounts({ treasurySigner: treasury.publicKey, pair: cPair })
+      .signers([treasury])
+      .rpc();
+    await expectError(() => closePair(lps, attacker), "Unauthorized");
+    // every LP share must be accounted for
+    await expectError(() => closePair([lps[0]]), "InvalidLpHolders");
+
+    const vK = await balance(cVK);
+    const vS = await balance(cVS);
+    const { lpLocked } = await program.account.pair.fetch(cPair);
+    const totalLp = (await getMint(connection, cLp, "confirmed", TOKEN_PROGRAM_ID)).supply + BigInt(lpLocked.toString());
+    const before = [];
+    for (const lp of lps) {
+      before.push({ lp: await balance(lp.lp), knite: await balance(lp.knite), sub: await balance(lp.sub) });
+    }
+    const tK0 = await balance(tK);
+    const tS0 = await balance(tS);
+    const treasuryLamports0 = await connection.getBalance(treasury.publicKey, "confirmed");
+
+    await closePair(lps);
+
+    let paidK = 0n;
+    let paidS = 0n;
+    for (const [i, lp] of lps.entries()) {
+      const shareK = (before[i].lp * vK) / totalLp;
+      const shareS = (before[i].lp * vS) / totalLp;
+      assert.strictEqual(((await balance(lp.knite)) - before[i].knite).toString(), shareK.toString());
+      assert.strictEqual(((await balance(lp.sub)) - before[i].sub).toString(), shareS.toString());
+      paidK += shareK;
+      paidS += shareS;
+    }
+    assert.strictEqual(((await balance(tK)) - tK0).toString(), (vK - paidK).toString());
+    assert.strictEqual(((await balance(tS)) - tS0).toString(), (vS - paidS).toString());
+
+    // rent of pair, vaults and registry entry back to the treasury; nothing left allocated
+    for (const pk of [cPair, cVK, cVS, cEntry]) {
+      assert.strictEqual(await connection.getAccountInfo(pk, "confirmed"), null);
+    }
+    assert.ok((await connection.getBalance(treasury.publicKey, "confirmed")) > treasuryLamports0);
+  });
+
+  it("close_pair: withheld Token-2022 fees are harvested so the vaults close; closing is permanent", async () => {
+    // 1% transfer fee on the sub token: every deposit leaves fees withheld in the vault
+    const mk = await createTestMint(provider, decimals, treasury.publicKey);
+    const ms = await createToken2022Mint(provider, decimals, treasury.publicKey, [ExtensionType.TransferFeeConfig], (m) => [
+      createInitializeTransferFeeConfigInstruction(m, treasury.publicKey, treasury.publicKey, 100, 1_000_000_000n, TOKEN_2022_PROGRAM_ID),
+    ]);
+    const [cPair] = findPairPda(program.programId, treasury.publicKey, mk.mintPk, ms);
+    const [cVK] = findVaultPda(program.programId, "vault_knite", cPair);
+    const [cVS] = findVaultPda(program.programId, "vault_sub", cPair);
+    const [cLp] = findVaultPda(program.programId, "lp_mint", cPair);
+
+    const initializePair = async () =>
+      program.methods
+        .initializePair(treasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+        .accounts({
+          treasurySigner: treasury.publicKey,
+          registry: registryPda,
+          registryEntry: await nextRegistryEntry(),
+          mintKnite: mk.mintPk,
+          mintSub: ms,
+          pair: cPair,
+          vaultKnite: cVK,
+          vaultSub: cVS,
+          lpMint: cLp,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_2022_PROGRAM_ID,
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+        })
+        .signers([treasury])
+        .rpc();
+    const cEntry = await nextRegistryEntry();
+    await initializePair();
+
+    const lpKnite = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, mk.mintPk);
+    const lpSub = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, ms, TOKEN_2022_PROGRAM_ID);
+    const lpLp = await ensureAta(provider, provider.wallet.publicKey, user.publicKey, cLp);
+    await mintTo(provider, mk.mintPk, lpKnite, treasury, 2_000_000n);
+    await mintTo(provider, ms, lpSub, treasury, 2_000_000n, TOKEN_2022_PROGRAM_ID);
+    await program.methods
+      .addLiquidity(new anchor.BN(1_000_000), new anchor.BN(1_000_000), new anchor.BN("1"))
+      .accounts({
+        user: user.publicKey,
+        pair: cPair,
+        lpMint: cLp,
+        userAtaKnite: lpKnite,
+        userAtaSub: lpSub,
+        userAtaLp: lpLp,
+        vaultKnite: cVK,
+        vaultSub: cVS,
+        mintKnite: mk.mintPk,
+        mintSub: ms,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_2022_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+      })
+      .signers([user])
+      .rpc();
+    const vaultFee = getTransferFeeAmount(await getAccount(connection, cVS, "confirmed", TOKEN_2022_PROGRAM_ID));
+    assert.ok(vaultFee.withheldAmount > 0n);
+
+    await program.methods
+      .setPairEnabled(false)
+      .accounts({ treasurySigner: treasury.publicKey, pair: cPair })
+      .signers([treasury])
+      .rpc();
+    const tK = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, mk.mintPk);
+    const tS = await ensureAta(provider, provider.wallet.publicKey, treasury.publicKey, ms, TOKEN_2022_PROGRAM_ID);
+    await program.methods
+      .closePair()
+      .accounts({
+        treasurySigner: treasury.publicKey,
+        registryEntry: cEntry,
+        pair: cPair,
+        mintKnite: mk.mintPk,
+        mintSub: ms,
+        lpMint: cLp,
+        vaultKnite: cVK,
+        vaultSub: cVS,
+        treasuryKniteAta: tK,
+        treasurySubAta: tS,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_2022_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: lpLp, isWritable: false, isSigner: false },
+        { pubkey: lpKnite, isWritable: true, isSigner: false },
+        { pubkey: lpSub, isWritable: true, isSigner: false },
+      ])
+      .signers([treasury])
+      .rpc();
+
+    for (const pk of [cPair, cVK, cVS, cEntry]) {
+      assert.strictEqual(await connection.getAccountInfo(pk, "confirmed"), null);
+    }
+    // the vault's withheld fees moved to the mint for the withdraw authority
+    const mintFee = getTransferFeeConfig(await getMint(connection, ms, "confirmed", TOKEN_2022_PROGRAM_ID));
+    assert.ok(mintFee.withheldAmount >= vaultFee.withheldAmount);
+
+    // the LP mint outlives the pair with its supply, so the pair cannot be created again
+    const lpMint = await getMint(connection, cLp, "confirmed", TOKEN_PROGRAM_ID);
+    assert.ok(lpMint.supply > 0n);
+    try {
+      await initializePair();
+      assert.fail("Expected initialize_pair to fail after close");
+    } catch (e) {
+      assert.ok(e.toString().includes("already in use") || e.toString().includes("custom program error"), e.toString());
+    }
+  });
+
+  it("pair versioning: migrate_pair grows an original 174-byte pair in place; current and non-pair accounts are rejected", async () => {
+    const pair = await program.account.pair.fetch(pairPda);
+    assert.strictEqual(pair.version, 2);
+    assert.strictEqual(pair.reserved.length, 48);
+    assert.ok(pair.reserved.every((b) => b === 0));
+
+    // loaded at genesis from tests/fixtures (Anchor.toml): a pair in the original layout
+    // (bumps, treasury, mints, vaults, fee 30, enabled) over vaults of 1_000 kNite / 4_000 sub
+    const legacyPair = new PublicKey("4XteKFSE6Jyzz16eVYiGL1eHEkSymKKNYLdAnjenPwYk");
+    const legacyMintKnite = new PublicKey("xGS9c7Kwyt9Pou4QD2pquHuAEkbbxpU8v8k813F1Yoj");
+    const legacyMintSub = new PublicKey("8aZrN7bJNt64fNFtJHbqiMdpJyV3tZsMvCfZ4VhfcXu8");
+    const [legacyVaultKnite] = findVaultPda(program.programId, "vault_knite", legacyPair);
+    const [legacyVaultSub] = findVaultPda(program.programId, "vault_sub", legacyPair);
+    const [legacyLp] = findVaultPda(program.programId, "lp_mint", legacyPair);
+    const baseline = (await connection.getAccountInfo(legacyPair, "confirmed")).data;
+    assert.strictEqual(baseline.length, 174);
+
+    const expectError = async (fn, name) => {
+      try {
+        await fn();
+        assert.fail(`Expected ${name}`);
+      } catch (e) {
+        assert.ok(e.toString().includes(name) || e.toString().includes("custom program error"), e.toString());
+      }
+    };
+    const migrate = (target, accounts = {}) =>
+      program.methods
+        .migratePair()
+        .accounts({
+          payer: provider.wallet.publicKey,
+          pair: target,
+          mintKnite,
+          vaultKnite,
+          vaultSub,
+          lpMint: findVaultPda(program.programId, "lp_mint", target)[0],
+          tokenProgram: TOKEN_PROGRAM_ID,
+          systemProgram: SystemProgram.programId,
+          ...accounts,
+        })
+        .rpc();
+    const legacyAccounts = { mintKnite: legacyMintKnite, vaultKnite: legacyVaultKnite, vaultSub: legacyVaultSub };
+
+    // mint and vaults must be the pair's own
+    await expectError(() => migrate(legacyPair, { ...legacyAccounts, vaultKnite }), "MintMismatch");
+    await migrate(legacyPair, legacyAccounts);
+
+    // grown to the current size, original bytes untouched
+    const data = (await connection.getAccountInfo(legacyPair, "confirmed")).data;
+    assert.strictEqual(data.length, (await connection.getAccountInfo(pairPda, "confirmed")).data.length);
+    assert.ok(data.subarray(0, 174).equals(baseline));
+
+    const migrated = await program.account.pair.fetch(legacyPair);
+    assert.strictEqual(migrated.version, 2);
+    assert.ok(migrated.mintKnite.equals(legacyMintKnite) && migrated.mintSub.equals(legacyMintSub));
+    assert.ok(migrated.vaultKnite.equals(legacyVaultKnite) && migrated.vaultSub.equals(legacyVaultSub));
+    assert.strictEqual(migrated.swapFeeBps, 30);
+    assert.strictEqual(migrated.enabled, true);
+    // reserves start at the vault balances it used to price off; new fields get initialize_pair's defaults
+    assert.strictEqual(migrated.reserveKnite.toString(), "1000000000");
+    assert.strictEqual(migrated.reserveSub.toString(), "4000000000");
+    assert.ok(migrated.lpMint.equals(legacyLp));
+    assert.strictEqual(migrated.lpLocked.toNumber(), 0);
+    assert.ok(migrated.curveType.constantProduct);
+    assert.deepStrictEqual(migrated.feeSplit, { lpBps: 0, treasuryBps: 10_000, burnBps: 0 });
+    assert.strictEqual(migrated.referralShareBps, pair.referralShareBps);
+    assert.strictEqual(migrated.flashFeeBps, pair.flashFeeBps);
+    const lp = await getMint(connection, legacyLp, "confirmed", TOKEN_PROGRAM_ID);
+    assert.ok(lp.mintAuthority.equals(legacyPair));
+    assert.strictEqual(lp.decimals, 6);
+    assert.strictEqual(lp.supply, 0n);
+
+    // trades at the price it had: 1 kNite quotes just under 4 sub
+    const quote = await program.methods
+      .quoteExactIn(new anchor.BN(1_000_000))
+      .accounts({ mintIn: legacyMintKnite, mintOut: legacyMintSub, pair: legacyPair })
+      .view();
+    assert.ok(quote.netOut.toNumber() > 3_900_000 && quote.netOut.toNumber() < 4_000_000, quote.netOut.toString());
+
+    await expectError(() => migrate(legacyPair, legacyAccounts), "PairAlreadyMigrated");
+    await expectError(() => migrate(pairPda), "PairAlreadyMigrated");
+    await expectError(() => migrate(registryPda), "UnsupportedPairVersion");
+  });
+
+  it("pair stats: every swap adds to volume per direction, fees and count, and sets the last price; quotes return them", async () => {
+    const big = (v) => BigInt(v.toString());
+    const swap = async (isKniteIn, amountIn) => {
+      const accounts = {
+        mintIn: isKniteIn ? mintKnite : mintSub,
+        mintOut: isKniteIn ? mintSub : mintKnite,
+        pair: pairPda,
+      };
+      const quote = await program.methods.quoteExactIn(new anchor.BN(amountIn.toString())).accounts(accounts).view();
+      await program.methods
+        .swapExactIn(new anchor.BN(amountIn.toString()), quote.netOut)
+        .accounts({
+          user: user.publicKey,
+          ...accounts,
+          userAtaIn: isKniteIn ? userKniteAta : userSubAta,
+          userAtaOut: isKniteIn ? userSubAta : userKniteAta,
+          vaultKnite,
+          vaultSub,
+          treasuryKniteAta,
+          tokenProgramKnite: TOKEN_PROGRAM_ID,
+          tokenProgramSub: TOKEN_PROGRAM_ID,
+        })
+        .signers([user])
+        .rpc();
+      return quote;
+    };
+
+    const s0 = (await program.account.pair.fetch(pairPda)).stats;
+
+    // kNite -> sub
+    const q1 = await swap(true, 100_000n);
+    assert.deepStrictEqual(
+      Object.keys(q1.stats).map((k) => q1.stats[k].toString()),
+      Object.keys(s0).map((k) => s0[k].toString())
+    );
+    const s1 = (await program.account.pair.fetch(pairPda)).stats;
+    assert.strictEqual((big(s1.volumeKniteIn) - big(s0.volumeKniteIn)).toString(), "100000");
+    assert.strictEqual(s1.volumeSubIn.toString(), s0.volumeSubIn.toString());
+    assert.strictEqual((big(s1.feesKnite) - big(s0.feesKnite)).toString(), q1.feeKnite.toString());
+    assert.strictEqual((big(s1.swapCount) - big(s0.swapCount)).toString(), "1");
+    assert.strictEqual(s1.lastPriceQ64.toString(), ((big(q1.netOut) << 64n) / 100_000n).toString());
+    assert.ok(s1.lastTradeTs.toNumber() > 0);
+
+    // sub -> kNite
+    const q2 = await swap(false, 100_000n);
+    assert.strictEqual(q2.stats.swapCount.toString(), s1.swapCount.toString());
+    const s2 = (await program.account.pair.fetch(pairPda)).stats;
+    assert.strictEqual((big(s2.volumeSubIn) - big(s1.volumeSubIn)).toString(), "100000");
+    assert.strictEqual(s2.volumeKniteIn.toString(), s1.volumeKniteIn.toString());
+    assert.strictEqual((big(s2.feesKnite) - big(s1.feesKnite)).toString(), q2.feeKnite.toString());
+    assert.strictEqual((big(s2.swapCount) - big(s1.swapCount)).toString(), "1");
+    assert.strictEqual(s2.lastPriceQ64.toString(), ((100_000n << 64n) / big(q2.netOut)).toString());
+  });
 });
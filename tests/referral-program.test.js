@@ -4,6 +4,19 @@ const assert = require("assert");
 
 const { Keypair, PublicKey, SystemProgram, LAMPORTS_PER_SOL } = anchor.web3;
 
+// CurveType arg of dex-core initialize_pair
+const CONSTANT_PRODUCT = { constantProduct: {} };
+
+const {
+  TOKEN_PROGRAM_ID,
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+  createAssociatedTokenAccountInstruction,
+  createInitializeMintInstruction,
+  createMintToInstruction,
+  getAccount,
+} = require("@solana/spl-token");
+
 async function airdrop(connection, pubkey, sol = 2) {
   const sig = await connection.requestAirdrop(pubkey, sol * LAMPORTS_PER_SOL);
   await connection.confirmTransaction(sig, "confirmed");
@@ -20,6 +33,54 @@ function findMarkerPda(programId, userPubkey) {
   );
 }
 
+function ata(owner, mint) {
+  return getAssociatedTokenAddressSync(mint, owner, false, TOKEN_PROGRAM_ID, ASSOCIATED_TOKEN_PROGRAM_ID);
+}
+
+async function ensureAta(provider, ownerPubkey, mintPubkey) {
+  const ataPk = ata(ownerPubkey, mintPubkey);
+  const tx = new anchor.web3.Transaction().add(
+    createAssociatedTokenAccountInstruction(
+      provider.wallet.publicKey,
+      ataPk,
+      ownerPubkey,
+      mintPubkey,
+      TOKEN_PROGRAM_ID,
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    )
+  );
+  await provider.sendAndConfirm(tx, [], { commitment: "confirmed" });
+  return ataPk;
+}
+
+async function createTestMint(provider, decimals, mintAuthorityPubkey) {
+  const mintKp = Keypair.generate();
+  const rent = await provider.connection.getMinimumBalanceForRentExemption(82);
+  const tx = new anchor.web3.Transaction().add(
+    SystemProgram.createAccount({
+      fromPubkey: provider.wallet.publicKey,
+      newAccountPubkey: mintKp.publicKey,
+      lamports: rent,
+      space: 82,
+      programId: TOKEN_PROGRAM_ID,
+    }),
+    createInitializeMintInstruction(mintKp.publicKey, decimals, mintAuthorityPubkey, null, TOKEN_PROGRAM_ID)
+  );
+  await provider.sendAndConfirm(tx, [mintKp], { commitment: "confirmed" });
+  return mintKp.publicKey;
+}
+
+async function mintTo(provider, mint, destAta, mintAuthorityKp, amount) {
+  const tx = new anchor.web3.Transaction().add(
+    createMintToInstruction(mint, destAta, mintAuthorityKp.publicKey, amount, [], TOKEN_PROGRAM_ID)
+  );
+  await provider.sendAndConfirm(tx, [mintAuthorityKp], { commitment: "confirmed" });
+}
+
+async function balance(connection, ataPk) {
+  return (await getAccount(connection, ataPk, "confirmed", TOKEN_PROGRAM_ID)).amount;
+}
+
 // Anchor error helper (works across versions)
 function expectAnchorError(e, containsAny = []) {
   const msg = (e?.error?.errorMessage || e?.message || e?.toString() || "").toLowerCase();
@@ -44,11 +105,10 @@ describe("referral-program (Phase 1)", () => {
   const user = Keypair.generate();
   const referrer = Keypair.generate();
 
-  // "dex-core" program id used for authorization
-  // In production this will be your deployed dex-core program id.
-  // In tests we just use a random Keypair pubkey and mark it as "executable" by pointing to SystemProgram
-  // is NOT possible, so we test the *treasury signer* auth path + mismatch path.
-  const allowedDexProgram = Keypair.generate().publicKey;
+  // dex-core program id used for authorization: dex-core swap_exact_in calls
+  // record_first_swap via CPI with itself as dex_program (last test).
+  const dex = anchor.workspace.DexCoreContracts;
+  const allowedDexProgram = dex.programId;
   const wrongDexProgram = Keypair.generate().publicKey;
 
   let configPda;
@@ -225,4 +285,181 @@ it("initialize_config: rejects re-initialize (AlreadyInitialized)", async () =>
       expectAnchorError(e, ["invalid referrer", "invalidreferrer"]);
     }
   });
+
+  it("dex-core swap_exact_in: first referred swap records the referee via CPI once the referrer signs; referrer earns its fee share", async () => {
+    const dexTreasury = Keypair.generate();
+    const trader = Keypair.generate();
+    const referrer2 = Keypair.generate();
+    await airdrop(connection, dexTreasury.publicKey, 4);
+    await airdrop(connection, trader.publicKey, 2);
+
+    // fresh dex-core pair with seeded reserves
+    const mintKnite = await createTestMint(provider, 6, dexTreasury.publicKey);
+    const mintSub = await createTestMint(provider, 6, dexTreasury.publicKey);
+    const [pairPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pair"), dexTreasury.publicKey.toBuffer(), mintKnite.toBuffer(), mintSub.toBuffer()],
+      dex.programId
+    );
+    const [vaultKnite] = PublicKey.findProgramAddressSync([Buffer.from("vault_knite"), pairPda.toBuffer()], dex.programId);
+    const [vaultSub] = PublicKey.findProgramAddressSync([Buffer.from("vault_sub"), pairPda.toBuffer()], dex.programId);
+    const [lpMint] = PublicKey.findProgramAddressSync([Buffer.from("lp_mint"), pairPda.toBuffer()], dex.programId);
+
+    // dex-core pair registry (created by the dex-core suite; created here when run alone)
+    const [dexRegistry] = PublicKey.findProgramAddressSync([Buffer.from("pair_registry")], dex.programId);
+    if (!(await connection.getAccountInfo(dexRegistry, "confirmed"))) {
+      await dex.methods
+        .initializeRegistry(dexTreasury.publicKey)
+        .accounts({
+          payer: provider.wallet.publicKey,
+          program: dex.programId,
+          programData: PublicKey.findProgramAddressSync(
+            [dex.programId.toBuffer()],
+            new PublicKey("BPFLoaderUpgradeab1e11111111111111111111111")
+          )[0],
+          registry: dexRegistry,
+          systemProgram: SystemProgram.programId,
+        })
+        .rpc();
+    }
+
+    const { pairCount } = await dex.account.pairRegistry.fetch(dexRegistry, "confirmed");
+    const [dexRegistryEntry] = PublicKey.findProgramAddressSync(
+      [Buffer.from("pair_registry_entry"), pairCount.toArrayLike(Buffer, "le", 8)],
+      dex.programId
+    );
+    await dex.methods
+      .initializePair(dexTreasury.publicKey, CONSTANT_PRODUCT, new anchor.BN(0))
+      .accounts({
+        treasurySigner: dexTreasury.publicKey,
+        registry: dexRegistry,
+        registryEntry: dexRegistryEntry,
+        mintKnite,
+        mintSub,
+        pair: pairPda,
+        vaultKnite,
+        vaultSub,
+        lpMint,
+        tokenProgramKnite: TOKEN_PROGRAM_ID,
+        tokenProgramSub: TOKEN_PROGRAM_ID,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
+        rent: anchor.web3.SYSVAR_RENT_PUBKEY,
+      })
+      .signers([dexTreasury])
+      .rpc();
+    await mintTo(provider, mintKnite, vaultKnite, dexTreasury, 1_000_000_000n);
+    await mintTo(provider, mintSub, vaultSub, dexTreasury, 2_000_000_000n);
+    await dex.methods
+      .sync()
+      .accounts({ treasurySigner: dexTreasury.publicKey, pair: pairPda, vaultKnite, vaultSub })
+      .signers([dexTreasury])
+      .rpc();
+
+    const treasuryKniteAta = await ensureAta(provider, dexTreasury.publicKey, mintKnite);
+    const referrerKniteAta = await ensureAta(provider, referrer2.publicKey, mintKnite);
+    const traderKniteAta = await ensureAta(provider, trader.publicKey, mintKnite);
+    const traderSubAta = await ensureAta(provider, trader.publicKey, mintSub);
+    await mintTo(provider, mintKnite, traderKniteAta, dexTreasury, 1_000_000n);
+
+    const [markerPda] = findMarkerPda(program.programId, trader.publicKey);
+    const swapAccounts = {
+      user: trader.publicKey,
+      mintIn: mintKnite,
+      mintOut: mintSub,
+      pair: pairPda,
+      userAtaIn: traderKniteAta,
+      userAtaOut: traderSubAta,
+      vaultKnite,
+      vaultSub,
+      treasuryKniteAta,
+      tokenProgramKnite: TOKEN_PROGRAM_ID,
+      tokenProgramSub: TOKEN_PROGRAM_ID,
+      referee: markerPda,
+      referrerKniteAta,
+      referrer: referrer2.publicKey,
+      referralConfig: configPda,
+      referralProgram: program.programId,
+      dexProgram: dex.programId,
+      systemProgram: SystemProgram.programId,
+    };
+
+    // fee = ceil(100_000 * 30 / 10_000) = 300; referrer share 20% = 60
+    const amountIn = 100_000n;
+    const fee = 300n;
+    const share = (fee * 2000n) / 10000n;
+
+    // self-referral through a second wallet: the trader's own ATA choice is not enough,
+    // with or without the referrer account, while the referrer has not signed
+    const secondWallet = Keypair.generate();
+    const secondWalletAta = await ensureAta(provider, secondWallet.publicKey, mintKnite);
+    for (const referrer of [null, secondWallet.publicKey]) {
+      try {
+        const tx = await dex.methods
+          .swapExactIn(new anchor.BN(amountIn.toString()), new anchor.BN("1"))
+          .accounts({ ...swapAccounts, referrerKniteAta: secondWalletAta, referrer })
+          .transaction();
+        await provider.sendAndConfirm(tx, [trader], { commitment: "confirmed" });
+        assert.fail("Expected ReferrerNotSigner");
+      } catch (e) {
+        expectAnchorError(e, ["referrer must sign", "referrernotsigner", "signature verification failed", "missing signature"]);
+      }
+    }
+    // the referrer signing for someone else's ATA does not help either
+    try {
+      await dex.methods
+        .swapExactIn(new anchor.BN(amountIn.toString()), new anchor.BN("1"))
+        .accounts({ ...swapAccounts, referrerKniteAta: secondWalletAta })
+        .signers([trader, referrer2])
+        .rpc();
+      assert.fail("Expected ReferrerNotSigner");
+    } catch (e) {
+      expectAnchorError(e, ["referrer must sign", "referrernotsigner"]);
+    }
+    assert.strictEqual(await connection.getAccountInfo(markerPda, "confirmed"), null);
+
+    const beforeTreasury = await balance(connection, treasuryKniteAta);
+    await dex.methods
+      .swapExactIn(new anchor.BN(amountIn.toString()), new anchor.BN("1"))
+      .accounts(swapAccounts)
+      .signers([trader, referrer2])
+      .rpc();
+
+    const st = await program.account.refereeState.fetch(markerPda);
+    assert.ok(st.user.equals(trader.publicKey));
+    assert.ok(st.referrer.equals(referrer2.publicKey));
+    assert.ok(st.pair.equals(pairPda));
+    assert.strictEqual((await balance(connection, referrerKniteAta)).toString(), share.toString());
+    assert.strictEqual(
+      ((await balance(connection, treasuryKniteAta)) - beforeTreasury).toString(),
+      (fee - share).toString()
+    );
+
+    // later swaps read the recorded referee; the referrer signature and CPI accounts are no longer needed
+    await dex.methods
+      .swapExactIn(new anchor.BN(amountIn.toString()), new anchor.BN("1"))
+      .accounts({
+        ...swapAccounts,
+        referrer: null,
+        referralConfig: null,
+        referralProgram: null,
+        dexProgram: null,
+        systemProgram: null,
+      })
+      .signers([trader])
+      .rpc();
+    assert.strictEqual((await balance(connection, referrerKniteAta)).toString(), (share * 2n).toString());
+
+    // a different referrer ATA does not match the recorded referee
+    const otherAta = await ensureAta(provider, Keypair.generate().publicKey, mintKnite);
+    try {
+      await dex.methods
+        .swapExactIn(new anchor.BN(amountIn.toString()), new anchor.BN("1"))
+        .accounts({ ...swapAccounts, referrer: null, referrerKniteAta: otherAta })
+        .signers([trader])
+        .rpc();
+      assert.fail("Expected InvalidReferral");
+    } catch (e) {
+      expectAnchorError(e, ["invalid referral", "invalidreferral"]);
+    }
+  });
 });